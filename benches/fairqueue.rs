@@ -1,5 +1,5 @@
 use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
-use fairqueue::{FairGroup, FairQueue, FairStack};
+use fairqueue::{FairGroup, FairHash, FairQueue, FairStack, PrehashedFairQueue};
 
 #[derive(Debug)]
 struct Event {
@@ -13,6 +13,41 @@ impl FairGroup for Event {
     }
 }
 
+/// Event whose group identity is a long string, making `is_same_group` expensive.
+#[derive(Debug)]
+struct TenantEvent {
+    tenant: String,
+    hash: u64,
+}
+
+impl FairGroup for TenantEvent {
+    #[inline(always)]
+    fn is_same_group(&self, other: &Self) -> bool {
+        self.tenant == other.tenant
+    }
+}
+
+impl FairHash for TenantEvent {
+    #[inline(always)]
+    fn group_hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+fn make_tenant_events(group_count: usize, items_per_group: usize) -> Vec<TenantEvent> {
+    (0..group_count)
+        .flat_map(|group| {
+            (0..items_per_group).map(move |_| {
+                let tenant = format!("{}{group:08}", "tenant-".repeat(32));
+                let hash = tenant.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                    (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+                });
+                TenantEvent { tenant, hash }
+            })
+        })
+        .collect()
+}
+
 fn make_events(group_count: usize, items_per_group: usize) -> Vec<Event> {
     (0..group_count)
         .flat_map(|group| {
@@ -45,6 +80,29 @@ fn bench_insert(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_insert_with_capacity(c: &mut Criterion) {
+    let mut group = c.benchmark_group("queue_insert_with_capacity");
+    for &groups in &[4usize, 16, 64] {
+        let per_group = (64 / groups.max(1)).max(1);
+        let events = make_events(groups, per_group);
+        let references: Vec<&Event> = events.iter().collect();
+
+        group.bench_function(BenchmarkId::from_parameter(groups), |b| {
+            b.iter_batched(
+                || FairQueue::<Event>::with_group_capacity(groups, per_group),
+                |mut queue| {
+                    for event in &references {
+                        queue.insert(*event);
+                    }
+                    queue
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
 fn bench_round_robin(c: &mut Criterion) {
     let mut group = c.benchmark_group("queue_round_robin");
     let events = make_events(16, 16);
@@ -113,11 +171,106 @@ fn bench_stack_round_robin(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_prehashed_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prehashed_insert");
+    for &groups in &[16usize, 64, 256] {
+        let events = make_tenant_events(groups, 4);
+        let references: Vec<&TenantEvent> = events.iter().collect();
+
+        group.bench_function(BenchmarkId::new("scan", groups), |b| {
+            b.iter_batched(
+                FairQueue::<TenantEvent>::new,
+                |mut queue| {
+                    for event in &references {
+                        queue.insert(*event);
+                    }
+                    queue
+                },
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.bench_function(BenchmarkId::new("prehashed", groups), |b| {
+            b.iter_batched(
+                PrehashedFairQueue::<TenantEvent>::new,
+                |mut queue| {
+                    for event in &references {
+                        queue.insert(*event);
+                    }
+                    queue
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_bulk_collect(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bulk_collect");
+    let events = make_events(100, 500);
+    let references: Vec<&Event> = events.iter().collect();
+
+    group.bench_function("insert_loop", |b| {
+        b.iter(|| {
+            let mut queue = FairQueue::new();
+            for event in &references {
+                queue.insert(*event);
+            }
+            queue
+        })
+    });
+
+    group.bench_function("collect", |b| {
+        b.iter(|| references.iter().copied().collect::<FairQueue<Event>>())
+    });
+    group.finish();
+}
+
+fn bench_hashed_by_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hashed_by_insert");
+    for &groups in &[100usize, 1000] {
+        let events = make_events(groups, 4);
+        let references: Vec<&Event> = events.iter().collect();
+
+        group.bench_function(BenchmarkId::new("scan", groups), |b| {
+            b.iter_batched(
+                FairQueue::<Event>::new,
+                |mut queue| {
+                    for event in &references {
+                        queue.insert(*event);
+                    }
+                    queue
+                },
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.bench_function(BenchmarkId::new("hashed_by", groups), |b| {
+            b.iter_batched(
+                || FairQueue::hashed_by(|event: &Event| event.user_id),
+                |mut queue| {
+                    for event in &references {
+                        queue.insert(*event);
+                    }
+                    queue
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_insert,
+    bench_insert_with_capacity,
     bench_round_robin,
     bench_stack_push,
-    bench_stack_round_robin
+    bench_stack_round_robin,
+    bench_prehashed_insert,
+    bench_bulk_collect,
+    bench_hashed_by_insert
 );
 criterion_main!(benches);
@@ -0,0 +1,79 @@
+//! `#[derive(FairGroup)]` for the `fairqueue` crate. Enable it through the `derive`
+//! feature of `fairqueue` rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Index, Member, parse_macro_input, spanned::Spanned};
+
+/// Implements `FairGroup` by comparing the fields marked `#[fair_group(key)]`.
+/// Several key fields are combined with `&&`; each must implement `PartialEq`.
+#[proc_macro_derive(FairGroup, attributes(fair_group))]
+pub fn derive_fair_group(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            input.ident.span(),
+            "`FairGroup` can only be derived for structs",
+        ));
+    };
+
+    let keys = key_members(&data.fields)?;
+    if keys.is_empty() {
+        return Err(Error::new(
+            input.ident.span(),
+            "`#[derive(FairGroup)]` needs at least one field marked `#[fair_group(key)]`",
+        ));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::fairqueue::FairGroup for #name #ty_generics #where_clause {
+            #[inline]
+            fn is_same_group(&self, other: &Self) -> bool {
+                #(self.#keys == other.#keys)&&*
+            }
+        }
+    })
+}
+
+/// Collects the fields carrying `#[fair_group(key)]`, rejecting any other attribute content.
+fn key_members(fields: &Fields) -> syn::Result<Vec<Member>> {
+    let mut keys = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let mut is_key = false;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("fair_group"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") && !is_key {
+                    is_key = true;
+                    Ok(())
+                } else if meta.path.is_ident("key") {
+                    Err(meta.error("duplicate `key` marker"))
+                } else {
+                    Err(meta.error("expected `key`"))
+                }
+            })?;
+        }
+        if is_key {
+            keys.push(match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index {
+                    index: index as u32,
+                    span: field.span(),
+                }),
+            });
+        }
+    }
+    Ok(keys)
+}
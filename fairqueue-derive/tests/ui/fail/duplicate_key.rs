@@ -0,0 +1,7 @@
+#[derive(fairqueue::FairGroup)]
+struct Event {
+    #[fair_group(key, key)]
+    tenant: &'static str,
+}
+
+fn main() {}
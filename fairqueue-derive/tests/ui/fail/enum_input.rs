@@ -0,0 +1,6 @@
+#[derive(fairqueue::FairGroup)]
+enum Event {
+    Login(#[fair_group(key)] u32),
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[derive(fairqueue::FairGroup)]
+struct Event {
+    tenant: &'static str,
+}
+
+fn main() {}
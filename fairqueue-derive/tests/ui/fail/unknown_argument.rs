@@ -0,0 +1,7 @@
+#[derive(fairqueue::FairGroup)]
+struct Event {
+    #[fair_group(id)]
+    tenant: &'static str,
+}
+
+fn main() {}
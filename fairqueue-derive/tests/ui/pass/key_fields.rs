@@ -0,0 +1,26 @@
+use fairqueue::FairGroup;
+
+#[derive(FairGroup)]
+struct Event {
+    #[fair_group(key)]
+    tenant: &'static str,
+    #[fair_group(key)]
+    region: u8,
+    value: u32,
+}
+
+#[derive(FairGroup)]
+struct Tagged<T: PartialEq>(#[fair_group(key)] T, u32);
+
+fn main() {
+    let a = Event { tenant: "alice", region: 1, value: 1 };
+    let b = Event { tenant: "alice", region: 2, value: 2 };
+    let c = Event { tenant: "alice", region: 1, value: 3 };
+    assert!(a.is_same_group(&c));
+    assert!(!a.is_same_group(&b));
+    assert_eq!(a.value + b.value + c.value, 6);
+
+    let x = Tagged('x', 1);
+    assert!(x.is_same_group(&Tagged('x', 2)));
+    assert!(!x.is_same_group(&Tagged('y', x.1)));
+}
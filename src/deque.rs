@@ -0,0 +1,306 @@
+use alloc::{collections::VecDeque, vec::Vec};
+use core::ptr;
+
+use crate::{
+    FairGroup,
+    rotation::{self, Rotation, Slots},
+};
+
+/// Spatially distancing double-ended structure: groups rotate exactly like in
+/// [`FairQueue`](crate::FairQueue), while each pop chooses which end of the served group
+/// to take from. `pop_front` gives FIFO order within a group (queue behaviour) and
+/// `pop_back` gives LIFO order (stack behaviour), over the same stored items.
+///
+/// ```
+/// use fairqueue::{FairDeque, FairGroup};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     user_id: &'static str,
+///     value: u32,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.user_id == other.user_id
+///     }
+/// }
+///
+/// let a1 = Event { user_id: "alice", value: 1 };
+/// let a2 = Event { user_id: "alice", value: 2 };
+/// let b1 = Event { user_id: "bob", value: 10 };
+///
+/// let mut deque = FairDeque::new();
+/// deque.insert_back(&a1);
+/// deque.insert_back(&a2);
+/// deque.insert_back(&b1);
+///
+/// assert_eq!(deque.pop_back(), Some(&a2));
+/// assert_eq!(deque.pop_front(), Some(&b1));
+/// assert_eq!(deque.pop_front(), Some(&a1));
+/// assert!(deque.pop_front().is_none());
+/// ```
+pub struct FairDeque<'a, V: FairGroup> {
+    groups: Vec<VecDeque<&'a V>>,
+    rotation: Rotation,
+    pointer: usize,
+    len: usize,
+}
+
+impl<'a, V: FairGroup> FairDeque<'a, V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            groups: Vec::new(),
+            rotation: Rotation::new(),
+            pointer: 0,
+            len: 0,
+        }
+    }
+
+    /// Appends an item to the back of its group, the end `pop_back` takes from.
+    pub fn insert_back(&mut self, value: &'a V) {
+        self.group_for(value).push_back(value);
+        self.len += 1;
+    }
+
+    /// Prepends an item to the front of its group, the end `pop_front` takes from.
+    pub fn insert_front(&mut self, value: &'a V) {
+        self.group_for(value).push_front(value);
+        self.len += 1;
+    }
+
+    /// Takes the front item of the group under the pointer, then advances the rotation.
+    #[inline(always)]
+    pub fn pop_front(&mut self) -> Option<&'a V> {
+        self.pop_with(VecDeque::pop_front)
+    }
+
+    /// Takes the back item of the group under the pointer, then advances the rotation.
+    #[inline(always)]
+    pub fn pop_back(&mut self) -> Option<&'a V> {
+        self.pop_with(VecDeque::pop_back)
+    }
+
+    /// Peeks at the item `pop_front` would return.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek_front(&self) -> Option<&'a V> {
+        self.groups.get(self.pointer)?.front().copied()
+    }
+
+    /// Peeks at the item `pop_back` would return.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek_back(&self) -> Option<&'a V> {
+        self.groups.get(self.pointer)?.back().copied()
+    }
+
+    /// Returns the number of stored items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when the deque holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct groups tracked by the deque.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Clears all items and resets the round-robin pointer.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.rotation.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+
+    /// Returns the group `value` belongs to, creating it at the end of the rotation if needed.
+    fn group_for(&mut self, value: &V) -> &mut VecDeque<&'a V> {
+        let slot = self.groups.iter().position(|group| {
+            group
+                .front()
+                .is_some_and(|v| ptr::eq(*v, value) || (*v).is_same_group(value))
+        });
+        let slot = slot.unwrap_or_else(|| {
+            self.groups.push(VecDeque::new());
+            self.rotation.push();
+            self.groups.len() - 1
+        });
+        &mut self.groups[slot]
+    }
+
+    /// Shared rotation step: takes an item from the pointed group with `take`, then moves on,
+    /// dropping the group once it is empty.
+    fn pop_with(&mut self, take: fn(&mut VecDeque<&'a V>) -> Option<&'a V>) -> Option<&'a V> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let mut ends = Ends {
+            groups: &mut self.groups,
+            take,
+        };
+        let popped =
+            rotation::pop_ordered(&mut ends, &mut self.rotation, &mut self.pointer, false)?;
+        self.len -= 1;
+        Some(popped.item)
+    }
+}
+
+/// The groups of a deque, each served from the end `take` picks.
+struct Ends<'g, 'a, V> {
+    groups: &'g mut Vec<VecDeque<&'a V>>,
+    take: fn(&mut VecDeque<&'a V>) -> Option<&'a V>,
+}
+
+impl<'a, V> Slots for Ends<'_, 'a, V> {
+    type Item = &'a V;
+
+    fn count(&self) -> usize {
+        self.groups.len()
+    }
+
+    fn take(&mut self, slot: usize) -> Option<&'a V> {
+        (self.take)(&mut self.groups[slot])
+    }
+
+    fn is_drained(&self, slot: usize) -> bool {
+        self.groups[slot].is_empty()
+    }
+
+    fn remove(&mut self, slot: usize) {
+        self.groups.swap_remove(slot);
+    }
+}
+
+impl<V: FairGroup> Default for FairDeque<'_, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FairQueue, FairStack};
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    fn events(groups: &[usize]) -> Vec<Event> {
+        groups
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                group,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn front_and_back_ends() {
+        let events = events(&[0, 1, 0, 1]);
+        let mut deque = FairDeque::new();
+        deque.insert_back(&events[0]);
+        deque.insert_back(&events[1]);
+        deque.insert_front(&events[2]);
+        deque.insert_back(&events[3]);
+        assert_eq!(deque.len(), 4);
+        assert_eq!(deque.group_count(), 2);
+
+        assert_eq!(deque.peek_front(), Some(&events[2]));
+        assert_eq!(deque.peek_back(), Some(&events[0]));
+        assert_eq!(deque.pop_front(), Some(&events[2]));
+        assert_eq!(deque.pop_back(), Some(&events[3]));
+        assert_eq!(deque.pop_back(), Some(&events[0]));
+        assert_eq!(deque.pop_front(), Some(&events[1]));
+        assert!(deque.pop_back().is_none());
+
+        deque.insert_front(&events[0]);
+        deque.clear();
+        assert!(deque.is_empty());
+        assert_eq!(deque.group_count(), 0);
+    }
+
+    #[test]
+    fn single_ended_use_matches_queue_and_stack() {
+        let events = events(&[0, 1, 0, 2, 1, 0, 2]);
+        let mut fifo = FairDeque::new();
+        let mut lifo = FairDeque::new();
+        let mut queue = FairQueue::new();
+        let mut stack = FairStack::new();
+        for event in &events {
+            fifo.insert_back(event);
+            lifo.insert_back(event);
+            queue.insert(event);
+            stack.push(event);
+        }
+
+        while let Some(expected) = queue.pop() {
+            assert_eq!(fifo.pop_front(), Some(expected));
+        }
+        while let Some(expected) = stack.pop() {
+            assert_eq!(lifo.pop_back(), Some(expected));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_mixed_ends_keep_spacing(
+            items in proptest::collection::vec((0usize..4, any::<bool>()), 1..32),
+            ends in proptest::collection::vec(any::<bool>(), 32),
+        ) {
+            let events = events(&items.iter().map(|&(group, _)| group).collect::<Vec<_>>());
+            let mut deque = FairDeque::new();
+            let mut remaining = [0usize; 4];
+            for (event, &(_, front)) in events.iter().zip(&items) {
+                if front {
+                    deque.insert_front(event);
+                } else {
+                    deque.insert_back(event);
+                }
+                remaining[event.group] += 1;
+            }
+
+            let mut last_group: Option<usize> = None;
+            for &front in ends.iter().cycle() {
+                let Some(event) = (if front { deque.pop_front() } else { deque.pop_back() }) else {
+                    break;
+                };
+                let gid = event.group;
+                let other_pending = remaining
+                    .iter()
+                    .enumerate()
+                    .any(|(idx, &count)| idx != gid && count > 0);
+                if other_pending && let Some(prev) = last_group {
+                    prop_assert_ne!(prev, gid);
+                }
+                remaining[gid] -= 1;
+                last_group = Some(gid);
+            }
+            prop_assert_eq!(remaining, [0; 4]);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use alloc::{collections::VecDeque, vec::Vec};
+use core::ptr;
+
+use crate::{
+    FairGroup,
+    rotation::{self, Rotation, Slots},
+};
+
+/// Group of a DRR queue: items with their costs and the credit left from earlier visits.
+struct DrrGroup<'a, V> {
+    deficit: u64,
+    items: VecDeque<(&'a V, u32)>,
+}
+
+/// Each item taken is paid for out of its group's deficit.
+impl<'a, V> Slots for Vec<DrrGroup<'a, V>> {
+    type Item = &'a V;
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn take(&mut self, slot: usize) -> Option<&'a V> {
+        let group = &mut self[slot];
+        let (item, cost) = group.items.pop_front()?;
+        group.deficit -= u64::from(cost);
+        Some(item)
+    }
+
+    fn is_drained(&self, slot: usize) -> bool {
+        self[slot].items.is_empty()
+    }
+
+    fn remove(&mut self, slot: usize) {
+        self.swap_remove(slot);
+    }
+}
+
+/// Fair queue that shares total cost rather than item count (deficit round robin).
+///
+/// Every item carries a cost given at insertion. Each time the rotation reaches a group, the
+/// group's deficit grows by the quantum, and the group keeps dispatching while its deficit
+/// covers the cost of its head item; the pointer then moves on and the leftover credit is
+/// kept for the next visit. Over time every backlogged group receives the same total cost,
+/// within one quantum plus one maximal item cost. A group that empties is removed together
+/// with its credit.
+///
+/// Choosing the quantum: with a quantum of at least the largest item cost every visit
+/// dispatches at least one item, so `pop` stays O(1) amortized. A smaller quantum interleaves
+/// groups more finely but lets `pop` spin through visits that dispatch nothing, up to
+/// `max_cost / quantum` passes over the groups. A much larger quantum produces long bursts
+/// from one group. A quantum of 0 is treated as 1.
+///
+/// ```
+/// use fairqueue::{DrrFairQueue, FairGroup};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Job {
+///     tenant: &'static str,
+///     id: u32,
+/// }
+///
+/// impl FairGroup for Job {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.tenant == other.tenant
+///     }
+/// }
+///
+/// let heavy = Job { tenant: "batch", id: 1 };
+/// let light: Vec<Job> = (10..14).map(|id| Job { tenant: "web", id }).collect();
+///
+/// let mut queue = DrrFairQueue::new(4);
+/// queue.insert(&heavy, 8);
+/// for job in &light {
+///     queue.insert(job, 2);
+/// }
+///
+/// // The heavy job needs two visits of credit; the light group spends 4 per visit.
+/// let order: Vec<u32> = std::iter::from_fn(|| queue.pop()).map(|job| job.id).collect();
+/// assert_eq!(order, vec![10, 11, 1, 12, 13]);
+/// ```
+pub struct DrrFairQueue<'a, V: FairGroup> {
+    groups: Vec<DrrGroup<'a, V>>,
+    rotation: Rotation,
+    quantum: u32,
+    pointer: usize,
+    /// Whether the group under the pointer already received its quantum for this visit.
+    visiting: bool,
+    len: usize,
+}
+
+impl<'a, V: FairGroup> DrrFairQueue<'a, V> {
+    /// Creates an empty queue crediting `quantum` cost units per group visit.
+    #[must_use]
+    pub fn new(quantum: u32) -> Self {
+        Self {
+            groups: Vec::new(),
+            rotation: Rotation::new(),
+            quantum: quantum.max(1),
+            pointer: 0,
+            visiting: false,
+            len: 0,
+        }
+    }
+
+    /// Inserts an item with its processing cost into its group.
+    pub fn insert(&mut self, value: &'a V, cost: u32) {
+        if let Some(group) = self.groups.iter_mut().find(|group| {
+            group
+                .items
+                .front()
+                .is_some_and(|(v, _)| ptr::eq(*v, value) || (*v).is_same_group(value))
+        }) {
+            group.items.push_back((value, cost));
+        } else {
+            let mut items = VecDeque::new();
+            items.push_back((value, cost));
+            self.groups.push(DrrGroup { deficit: 0, items });
+            self.rotation.push();
+        }
+        self.len += 1;
+    }
+
+    /// Retrieves the next item, letting the current group continue while its deficit
+    /// covers the cost of its head item.
+    pub fn pop(&mut self) -> Option<&'a V> {
+        if self.len == 0 || self.groups.is_empty() {
+            return None;
+        }
+
+        loop {
+            if self.pointer >= self.groups.len() {
+                self.pointer = self.rotation.first();
+            }
+
+            let group = &mut self.groups[self.pointer];
+            if !self.visiting {
+                group.deficit += u64::from(self.quantum);
+                self.visiting = true;
+            }
+
+            let &(_, cost) = group.items.front()?;
+            if u64::from(cost) > group.deficit {
+                self.pointer = self.rotation.next(self.pointer);
+                self.visiting = false;
+                continue;
+            }
+
+            // The visit goes on until the deficit no longer covers the head item.
+            let popped = rotation::pop_ordered(
+                &mut self.groups,
+                &mut self.rotation,
+                &mut self.pointer,
+                true,
+            )?;
+            self.len -= 1;
+            if !popped.kept {
+                self.visiting = false;
+            }
+            return Some(popped.item);
+        }
+    }
+
+    /// Returns the quantum credited per group visit.
+    #[inline(always)]
+    #[must_use]
+    pub fn quantum(&self) -> u32 {
+        self.quantum
+    }
+
+    /// Returns the number of enqueued items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when the queue holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct groups tracked by the queue.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Clears all items and deficits and resets the round-robin pointer.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.rotation.clear();
+        self.pointer = 0;
+        self.visiting = false;
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FairQueue;
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    #[test]
+    fn unit_costs_match_fair_queue() {
+        let events: Vec<Event> = (0..6)
+            .map(|timestamp| Event {
+                timestamp,
+                group: timestamp as usize % 3,
+            })
+            .collect();
+
+        let mut queue = DrrFairQueue::new(0);
+        assert_eq!(queue.quantum(), 1);
+        for event in &events {
+            queue.insert(event, 1);
+        }
+        assert_eq!(queue.group_count(), 3);
+
+        // Emptying group 0 keeps groups 1 and 2 in arrival order, exactly as in `FairQueue`.
+        let plain: FairQueue<_> = events.iter().collect();
+        let order: Vec<u32> = core::iter::from_fn(|| queue.pop())
+            .map(|event| event.timestamp)
+            .collect();
+        assert_eq!(order, vec![0, 1, 2, 3, 4, 5]);
+        assert!(plain.into_iter().map(|event| event.timestamp).eq(order));
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_backlogged_cost_tracks_equal_shares(
+            costs in proptest::collection::vec(proptest::collection::vec(1u32..=10, 8..32), 2..5),
+            quantum in 1u32..20,
+        ) {
+            let mut events = Vec::new();
+            for (group, group_costs) in costs.iter().enumerate() {
+                for cost in group_costs {
+                    events.push((Event { timestamp: events.len() as u32, group }, *cost));
+                }
+            }
+
+            let mut queue = DrrFairQueue::new(quantum);
+            for (event, cost) in &events {
+                queue.insert(event, *cost);
+            }
+
+            // served = visits * quantum - deficit with deficit < max cost, and visit counts
+            // of backlogged groups differ by at most one.
+            let bound = u64::from(quantum) + 10;
+            let mut served = vec![0u64; costs.len()];
+            let mut remaining: Vec<usize> = costs.iter().map(Vec::len).collect();
+            while let Some(event) = queue.pop() {
+                served[event.group] += u64::from(events[event.timestamp as usize].1);
+                remaining[event.group] -= 1;
+                if remaining.iter().all(|&left| left > 0) {
+                    let max = served.iter().max().copied().unwrap_or(0);
+                    let min = served.iter().min().copied().unwrap_or(0);
+                    prop_assert!(max - min < bound, "served {:?} beyond {}", served, bound);
+                }
+            }
+            prop_assert_eq!(remaining.iter().sum::<usize>(), 0);
+        }
+    }
+}
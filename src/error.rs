@@ -0,0 +1,152 @@
+use core::fmt;
+
+/// Error returned by checked queue operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueError {
+    /// Internal state disagrees with itself: `len` does not match the group contents,
+    /// a tracked group is empty, or the pointer is out of range.
+    Inconsistent,
+    /// `is_same_group` matched the head of a group but not all of its members, so the
+    /// relation is not transitive.
+    NonTransitiveGroup,
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inconsistent => f.write_str("queue state is inconsistent"),
+            Self::NonTransitiveGroup => {
+                f.write_str("item matches a group head but not every group member")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QueueError {}
+
+/// Broken invariant reported by [`FairQueue::validate`](crate::FairQueue::validate), naming
+/// the first check that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantError {
+    /// The group at `slot` is tracked but holds no items.
+    EmptyGroup { slot: usize },
+    /// `len` disagrees with the number of items stored across the groups.
+    LenMismatch { len: usize, stored: usize },
+    /// The pointer addresses no group although the queue tracks `group_count` of them.
+    PointerOutOfRange { pointer: usize, group_count: usize },
+    /// The id list does not have one entry per group.
+    IdCountMismatch { ids: usize, group_count: usize },
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyGroup { slot } => write!(f, "group at slot {slot} is empty"),
+            Self::LenMismatch { len, stored } => {
+                write!(f, "len is {len} but the groups store {stored} items")
+            }
+            Self::PointerOutOfRange {
+                pointer,
+                group_count,
+            } => write!(
+                f,
+                "pointer {pointer} is out of range for {group_count} groups"
+            ),
+            Self::IdCountMismatch { ids, group_count } => {
+                write!(f, "{ids} group ids are tracked for {group_count} groups")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvariantError {}
+
+/// Error returned by [`TryFairGroup::try_is_same_group`](crate::TryFairGroup::try_is_same_group)
+/// when the group of a value cannot be determined, e.g. because a key field is malformed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupError {
+    reason: &'static str,
+}
+
+impl GroupError {
+    /// Creates an error carrying a short description of what went wrong.
+    #[must_use]
+    pub const fn new(reason: &'static str) -> Self {
+        Self { reason }
+    }
+
+    /// Returns the description given at construction.
+    pub fn reason(&self) -> &'static str {
+        self.reason
+    }
+}
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot determine group: {}", self.reason)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GroupError {}
+
+/// Error returned by bounded inserts when the queue or the target group is full.
+/// Carries the rejected value so the caller can retry or shed it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError<T> {
+    value: T,
+}
+
+impl<T> CapacityError<T> {
+    #[cfg(any(feature = "alloc", feature = "heapless"))]
+    pub(crate) fn new(value: T) -> Self {
+        Self { value }
+    }
+
+    /// Returns the rejected value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("queue is at capacity")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> std::error::Error for CapacityError<T> {}
+
+/// Spacing violation reported by [`FairQueue::check_fairness`](crate::FairQueue::check_fairness).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FairnessViolation {
+    position: usize,
+}
+
+impl FairnessViolation {
+    #[cfg(feature = "alloc")]
+    pub(crate) fn new(position: usize) -> Self {
+        Self { position }
+    }
+
+    /// Returns the 0-based pop position that served the same group twice in a row.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for FairnessViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pop {} served the previous group again while another group was pending",
+            self.position
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FairnessViolation {}
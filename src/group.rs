@@ -1,5 +1,83 @@
+use crate::GroupError;
+
 /// Trait for defining grouping logic for fair scheduling structures.
 /// Values belong to the same group when `is_same_group` returns true.
+///
+/// The relation must be an equivalence (reflexive, symmetric and transitive): inserts only
+/// compare a new value against the head of each group, so a non-transitive relation can
+/// place a value in a group whose other members it does not match, breaking spacing.
+/// [`FairQueue::try_insert_strict`](crate::FairQueue::try_insert_strict) detects this.
 pub trait FairGroup {
     fn is_same_group(&self, other: &Self) -> bool;
+
+    /// Optional cheap integer identity of the group, compared instead of calling
+    /// `is_same_group` when both values report one. Two values with tags must have
+    /// equal tags exactly when `is_same_group` holds for them. Defaults to `None`,
+    /// which keeps every comparison on `is_same_group`.
+    #[inline(always)]
+    fn group_tag(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Counts the groups `items` would form in a fair queue, e.g. to size
+/// [`FairQueue::with_capacity`](crate::FairQueue::with_capacity) before inserting them. Each
+/// item is compared with the ones before it until a match is found, so this is O(n²)
+/// comparisons in the worst case (every item in its own group) and needs no allocation. For
+/// types with a hashable key, [`distinct_group_keys`](crate::distinct_group_keys) counts in
+/// O(n) under the `std` feature.
+///
+/// ```
+/// use fairqueue::{FairGroup, distinct_groups};
+///
+/// struct Job(char);
+///
+/// impl FairGroup for Job {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.0 == other.0
+///     }
+/// }
+///
+/// assert_eq!(distinct_groups(&[Job('a'), Job('b'), Job('a')]), 2);
+/// ```
+#[must_use]
+pub fn distinct_groups<V: FairGroup>(items: &[V]) -> usize {
+    items
+        .iter()
+        .enumerate()
+        .filter(|&(idx, item)| !items[..idx].iter().any(|seen| seen.is_same_group(item)))
+        .count()
+}
+
+/// Fallible counterpart of [`FairGroup`] for grouping that can fail, e.g. when the key is
+/// parsed from a field. Every `FairGroup` type implements it through a blanket impl that
+/// never fails, so implement it directly only for types without a `FairGroup` impl.
+/// [`FairQueue::try_insert_grouped`](crate::FairQueue::try_insert_grouped) rejects items
+/// whose comparison fails instead of inserting them.
+pub trait TryFairGroup {
+    fn try_is_same_group(&self, other: &Self) -> Result<bool, GroupError>;
+}
+
+impl<T: FairGroup + ?Sized> TryFairGroup for T {
+    #[inline(always)]
+    fn try_is_same_group(&self, other: &Self) -> Result<bool, GroupError> {
+        Ok(self.is_same_group(other))
+    }
+}
+
+/// Handle to a group of a [`FairQueue`](crate::FairQueue), returned by
+/// [`insert_with_id`](crate::FairQueue::insert_with_id). Ids are handed out in increasing
+/// order per queue and never reused by it. An id stays valid while its group holds items,
+/// even as other groups move between slots; once the group empties the id resolves to nothing,
+/// and a later item of the same group opens it under a new id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GroupId(pub(crate) u64);
+
+impl GroupId {
+    /// Returns the raw id, e.g. for logging.
+    #[inline(always)]
+    #[must_use]
+    pub const fn get(self) -> u64 {
+        self.0
+    }
 }
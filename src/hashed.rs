@@ -0,0 +1,537 @@
+use alloc::{collections::VecDeque, vec::Vec};
+use core::{
+    fmt::{self, Debug},
+    hash::Hash,
+    iter::FusedIterator,
+};
+
+use hashbrown::HashMap;
+
+#[cfg(feature = "std")]
+use crate::FairKey;
+use crate::rotation::{self, Rotation, Slots, Walk};
+
+/// Fair queue grouping items by the key `key_fn` extracts, with a `HashMap<K, usize>` from
+/// each key to its group's slot: [`KeyedFairQueue`](crate::KeyedFairQueue) without its scan
+/// over the group keys, so `insert` is one hash lookup however many groups there are. Each
+/// group stores its key next to its items; when an emptied group is swap-removed, the stored
+/// key of the group moved into its slot is used to re-point the index. Keys are cloned once
+/// per new group. Usually built through [`FairQueue::hashed_by`](crate::FairQueue::hashed_by).
+///
+/// ```
+/// use fairqueue::FairQueue;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Request {
+///     tenant: &'static str,
+///     id: u32,
+/// }
+///
+/// let a1 = Request { tenant: "alice", id: 1 };
+/// let a2 = Request { tenant: "alice", id: 2 };
+/// let b1 = Request { tenant: "bob", id: 3 };
+///
+/// let mut queue = FairQueue::hashed_by(|req: &Request| req.tenant);
+/// queue.insert(&a1);
+/// queue.insert(&a2);
+/// queue.insert(&b1);
+///
+/// assert_eq!(queue.pop(), Some(&a1));
+/// assert_eq!(queue.pop(), Some(&b1));
+/// assert_eq!(queue.pop(), Some(&a2));
+/// assert!(queue.pop().is_none());
+/// ```
+pub struct HashedFairQueue<'a, V, K, F = fn(&V) -> K> {
+    groups: Vec<(K, VecDeque<&'a V>)>,
+    rotation: Rotation,
+    index: HashMap<K, usize>,
+    key_fn: F,
+    pointer: usize,
+    len: usize,
+}
+
+impl<'a, V, K: Eq + Hash + Clone, F: Fn(&V) -> K> HashedFairQueue<'a, V, K, F> {
+    #[must_use]
+    pub fn new(key_fn: F) -> Self {
+        Self {
+            groups: Vec::new(),
+            rotation: Rotation::new(),
+            index: HashMap::new(),
+            key_fn,
+            pointer: 0,
+            len: 0,
+        }
+    }
+
+    /// Inserts a new item, locating its group with a single hash lookup.
+    pub fn insert(&mut self, value: &'a V) {
+        let key = (self.key_fn)(value);
+        let slot = match self.index.get(&key) {
+            Some(&slot) => slot,
+            None => {
+                let slot = self.groups.len();
+                self.index.insert(key.clone(), slot);
+                self.groups.push((key, VecDeque::new()));
+                self.rotation.push();
+                slot
+            }
+        };
+        self.groups[slot].1.push_back(value);
+        self.len += 1;
+    }
+
+    /// Retrieves the next item in the queue (FIFO) while maintaining spatial distancing.
+    /// When a group empties, the index entry of the group moved into its slot is updated.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<&'a V> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let mut slots = HashedSlots {
+            groups: &mut self.groups,
+            index: &mut self.index,
+        };
+        let popped =
+            rotation::pop_ordered(&mut slots, &mut self.rotation, &mut self.pointer, false)?;
+        self.len -= 1;
+        Some(popped.item)
+    }
+
+    /// Peeks at the next item in the queue without removing it.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek(&self) -> Option<&'a V> {
+        self.groups.get(self.pointer)?.1.front().copied()
+    }
+
+    /// Returns how many items are queued under `key`.
+    #[must_use]
+    pub fn len_of_key(&self, key: &K) -> usize {
+        self.index
+            .get(key)
+            .map_or(0, |&slot| self.groups[slot].1.len())
+    }
+
+    /// Returns how many items the group of `sample` holds, or 0 when it has none queued.
+    #[must_use]
+    pub fn len_of_group(&self, sample: &V) -> usize {
+        self.len_of_key(&(self.key_fn)(sample))
+    }
+
+    /// Peeks at the next item for the group of `sample` without disturbing rotation.
+    #[must_use]
+    pub fn peek_group(&self, sample: &V) -> Option<&'a V> {
+        let slot = *self.index.get(&(self.key_fn)(sample))?;
+        self.groups[slot].1.front().copied()
+    }
+
+    /// Iterates over the current head item of each group without consuming them, in the order
+    /// the groups arrived.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_heads(&self) -> HashedGroupHeads<'_, 'a, V, K> {
+        HashedGroupHeads {
+            groups: &self.groups,
+            walk: self.rotation.iter(),
+        }
+    }
+
+    /// Keeps only the items for which `f` returns true, preserving their order within each group.
+    /// Groups left empty are dropped and their keys leave the index; the rotation resumes at
+    /// the next surviving group.
+    pub fn retain<P: FnMut(&V) -> bool>(&mut self, mut f: P) {
+        for (_, group) in &mut self.groups {
+            group.retain(|value| f(value));
+        }
+        self.len = self.groups.iter().map(|(_, group)| group.len()).sum();
+
+        let mut slots = HashedSlots {
+            groups: &mut self.groups,
+            index: &mut self.index,
+        };
+        self.pointer = rotation::prune(&mut slots, &mut self.rotation, self.pointer);
+    }
+
+    /// Pops every item in fair order through an iterator.
+    /// The queue is left empty with its pointer reset once the iterator is dropped,
+    /// even if iteration stopped early.
+    pub fn drain(&mut self) -> HashedDrain<'_, 'a, V, K, F> {
+        HashedDrain { queue: self }
+    }
+
+    /// Returns the number of enqueued items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when the queue holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct keys tracked by the queue.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Clears all items and the key index and resets the round-robin pointer.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.rotation.clear();
+        self.index.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+}
+
+/// Snapshots the scheduling state. Only references are copied, so `V` need not be `Clone`.
+impl<V, K: Clone, F: Clone> Clone for HashedFairQueue<'_, V, K, F> {
+    fn clone(&self) -> Self {
+        Self {
+            groups: self.groups.clone(),
+            rotation: self.rotation.clone(),
+            index: self.index.clone(),
+            key_fn: self.key_fn.clone(),
+            pointer: self.pointer,
+            len: self.len,
+        }
+    }
+}
+
+/// Shows `len`, `group_count`, the position of the pointer and every group in rotation
+/// order as its key and items, front first. The key closure is omitted.
+impl<V: Debug, K: Debug, F> Debug for HashedFairQueue<'_, V, K, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer < self.groups.len() {
+            self.rotation.position(self.pointer)
+        } else {
+            0
+        };
+        let groups: Vec<_> = self
+            .rotation
+            .iter()
+            .map(|slot| &self.groups[slot])
+            .collect();
+        f.debug_struct("HashedFairQueue")
+            .field("len", &self.len)
+            .field("group_count", &self.groups.len())
+            .field("pointer", &pointer)
+            .field("groups", &groups)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keyed groups of an [`HashedFairQueue`] with the key index to maintain.
+struct HashedSlots<'g, 'a, V, K> {
+    groups: &'g mut Vec<(K, VecDeque<&'a V>)>,
+    index: &'g mut HashMap<K, usize>,
+}
+
+impl<'a, V, K: Eq + Hash> Slots for HashedSlots<'_, 'a, V, K> {
+    type Item = &'a V;
+
+    fn count(&self) -> usize {
+        self.groups.len()
+    }
+
+    fn take(&mut self, slot: usize) -> Option<&'a V> {
+        self.groups[slot].1.pop_front()
+    }
+
+    fn is_drained(&self, slot: usize) -> bool {
+        self.groups[slot].1.is_empty()
+    }
+
+    /// Drops the drained group's key and re-points the key of the group moved into `slot`.
+    fn remove(&mut self, slot: usize) {
+        let (key, _) = self.groups.swap_remove(slot);
+        self.index.remove(&key);
+        if let Some((moved, _)) = self.groups.get(slot)
+            && let Some(moved_slot) = self.index.get_mut(moved)
+        {
+            *moved_slot = slot;
+        }
+    }
+}
+
+/// Groups by [`FairKey::group_key`], so the type can be named without a closure
+/// (requires the `std` feature).
+#[cfg(feature = "std")]
+impl<V: FairKey<Key: Clone>> Default for HashedFairQueue<'_, V, V::Key, fn(&V) -> V::Key> {
+    fn default() -> Self {
+        Self::new(V::group_key)
+    }
+}
+
+impl<'a, V, K: Eq + Hash + Clone, F: Fn(&V) -> K> Extend<&'a V> for HashedFairQueue<'a, V, K, F> {
+    /// Inserts every item in iteration order, exactly like repeated `insert` calls.
+    fn extend<I: IntoIterator<Item = &'a V>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+/// Collects into a queue grouped by [`FairKey::group_key`], like `Default`.
+#[cfg(feature = "std")]
+impl<'a, V: FairKey<Key: Clone>> FromIterator<&'a V>
+    for HashedFairQueue<'a, V, V::Key, fn(&V) -> V::Key>
+{
+    fn from_iter<I: IntoIterator<Item = &'a V>>(iter: I) -> Self {
+        let mut queue = Self::default();
+        queue.extend(iter);
+        queue
+    }
+}
+
+impl<'a, V, K: Eq + Hash + Clone, F: Fn(&V) -> K> IntoIterator for HashedFairQueue<'a, V, K, F> {
+    type Item = &'a V;
+    type IntoIter = HashedIntoIter<'a, V, K, F>;
+
+    /// Consumes the queue, yielding items in the order `pop` would return them.
+    fn into_iter(self) -> Self::IntoIter {
+        HashedIntoIter { queue: self }
+    }
+}
+
+/// Owning iterator over an [`HashedFairQueue`] in fair pop order.
+pub struct HashedIntoIter<'a, V, K, F> {
+    queue: HashedFairQueue<'a, V, K, F>,
+}
+
+impl<'a, V, K: Eq + Hash + Clone, F: Fn(&V) -> K> Iterator for HashedIntoIter<'a, V, K, F> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.queue.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.len, Some(self.queue.len))
+    }
+}
+
+impl<V, K: Eq + Hash + Clone, F: Fn(&V) -> K> ExactSizeIterator for HashedIntoIter<'_, V, K, F> {}
+
+impl<V, K: Eq + Hash + Clone, F: Fn(&V) -> K> FusedIterator for HashedIntoIter<'_, V, K, F> {}
+
+/// Draining iterator returned by [`HashedFairQueue::drain`].
+pub struct HashedDrain<'queue, 'a, V, K: Eq + Hash + Clone, F: Fn(&V) -> K> {
+    queue: &'queue mut HashedFairQueue<'a, V, K, F>,
+}
+
+impl<'a, V, K: Eq + Hash + Clone, F: Fn(&V) -> K> Iterator for HashedDrain<'_, 'a, V, K, F> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.queue.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.len, Some(self.queue.len))
+    }
+}
+
+impl<V, K: Eq + Hash + Clone, F: Fn(&V) -> K> ExactSizeIterator for HashedDrain<'_, '_, V, K, F> {}
+
+impl<V, K: Eq + Hash + Clone, F: Fn(&V) -> K> FusedIterator for HashedDrain<'_, '_, V, K, F> {}
+
+impl<V, K: Eq + Hash + Clone, F: Fn(&V) -> K> Drop for HashedDrain<'_, '_, V, K, F> {
+    fn drop(&mut self) {
+        self.queue.clear();
+    }
+}
+
+/// Iterator over the first element of each group of an [`HashedFairQueue`].
+pub struct HashedGroupHeads<'queue, 'a, V, K> {
+    groups: &'queue [(K, VecDeque<&'a V>)],
+    walk: Walk<'queue>,
+}
+
+impl<'a, V, K> Iterator for HashedGroupHeads<'_, 'a, V, K> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walk
+            .next()
+            .and_then(|slot| self.groups[slot].1.front().copied())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.walk.size_hint()
+    }
+}
+
+impl<V, K> ExactSizeIterator for HashedGroupHeads<'_, '_, V, K> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FairGroup, FairQueue};
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl crate::FairKey for Event {
+        type Key = usize;
+
+        fn group_key(&self) -> usize {
+            self.group
+        }
+    }
+
+    #[test]
+    fn index_follows_swap_remove() {
+        let events: Vec<Event> = [0, 1, 2, 2, 1, 2]
+            .into_iter()
+            .enumerate()
+            .map(|(timestamp, group)| Event {
+                timestamp: timestamp as u32,
+                group,
+            })
+            .collect();
+
+        let mut queue = FairQueue::hashed_by(|event: &Event| event.group);
+        for event in &events {
+            queue.insert(event);
+        }
+        assert_eq!(queue.group_count(), 3);
+        assert_eq!(queue.len_of_key(&2), 3);
+
+        // Emptying group 0 moves group 2 into slot 0; inserts must still find it there.
+        assert_eq!(queue.pop(), Some(&events[0]));
+        let late = Event {
+            timestamp: 6,
+            group: 2,
+        };
+        queue.insert(&late);
+        assert_eq!(queue.len_of_key(&2), 4);
+        assert_eq!(queue.len_of_key(&0), 0);
+        assert_eq!(queue.group_count(), 2);
+
+        let order: Vec<u32> = core::iter::from_fn(|| queue.pop())
+            .map(|event| event.timestamp)
+            .collect();
+        assert_eq!(order, vec![1, 2, 4, 3, 5, 6]);
+        assert!(queue.is_empty());
+        assert!(queue.index.is_empty());
+    }
+
+    #[test]
+    fn mirrors_queue_surface() {
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 0, 3]
+            .into_iter()
+            .enumerate()
+            .map(|(timestamp, group)| Event {
+                timestamp: timestamp as u32,
+                group,
+            })
+            .collect();
+
+        let mut queue = HashedFairQueue::new(|event: &Event| event.group);
+        queue.extend(&events);
+        let plain: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.len(), 7);
+        assert!(queue.group_heads().eq(plain.group_heads()));
+        assert_eq!(queue.group_heads().len(), 4);
+        assert_eq!(queue.peek_group(&events[5]), Some(&events[0]));
+        assert_eq!(queue.len_of_group(&events[5]), 3);
+        assert!(format!("{queue:?}").starts_with("HashedFairQueue { len: 7"));
+        assert!(queue.clone().into_iter().eq(plain.clone()));
+
+        // Dropping group 2 re-points the index at the group moved past it.
+        queue.retain(|event| event.group != 2 && event.timestamp != 5);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.group_count(), 3);
+        assert_eq!(queue.len_of_group(&events[6]), 1);
+        assert_eq!(queue.peek_group(&events[2]), None);
+        queue.extend(&events[2..3]);
+        assert_eq!(queue.len_of_key(&2), 1);
+
+        let order: Vec<u32> = queue.drain().take(2).map(|event| event.timestamp).collect();
+        assert_eq!(order, vec![0, 1]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+        assert!(queue.index.is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn collects_by_fair_key() {
+        let events: Vec<Event> = [0, 1, 0, 2]
+            .into_iter()
+            .enumerate()
+            .map(|(timestamp, group)| Event {
+                timestamp: timestamp as u32,
+                group,
+            })
+            .collect();
+
+        let queue: HashedFairQueue<_, _> = events.iter().collect();
+        assert_eq!(queue.group_count(), 3);
+        assert!(
+            queue
+                .into_iter()
+                .eq(events.iter().collect::<FairQueue<_>>())
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_keyed_and_plain_queues(
+            ops in proptest::collection::vec(proptest::option::of(0usize..40), 1..128)
+        ) {
+            let events: Vec<Event> = ops
+                .iter()
+                .enumerate()
+                .map(|(idx, op)| Event {
+                    timestamp: idx as u32,
+                    group: op.unwrap_or(0),
+                })
+                .collect();
+
+            let mut hashed = FairQueue::hashed_by(|event: &Event| event.group);
+            let mut keyed = FairQueue::new_by(|event: &Event| event.group);
+            let mut plain = FairQueue::new();
+            for (op, event) in ops.iter().zip(&events) {
+                if op.is_some() {
+                    hashed.insert(event);
+                    keyed.insert(event);
+                    plain.insert(event);
+                } else {
+                    let popped = hashed.pop().map(|e| e.timestamp);
+                    prop_assert_eq!(popped, keyed.pop().map(|e| e.timestamp));
+                    prop_assert_eq!(popped, plain.pop().map(|e| e.timestamp));
+                }
+                prop_assert_eq!(hashed.group_count(), keyed.group_count());
+                prop_assert_eq!(hashed.group_count(), plain.group_count());
+                prop_assert_eq!(hashed.len(), keyed.len());
+            }
+
+            while let Some(expected) = plain.pop() {
+                prop_assert_eq!(hashed.pop().map(|e| e.timestamp), Some(expected.timestamp));
+                prop_assert_eq!(keyed.pop().map(|e| e.timestamp), Some(expected.timestamp));
+            }
+            prop_assert!(hashed.pop().is_none());
+            prop_assert!(keyed.pop().is_none());
+        }
+    }
+}
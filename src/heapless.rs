@@ -0,0 +1,367 @@
+//! Fixed-capacity [`FairQueue`] and [`FairStack`] backed by [`heapless::Vec`] and
+//! [`heapless::Deque`] (requires the `heapless` feature). At most `G` groups of at most `N`
+//! items each are stored inline, so nothing is allocated after construction. With the default
+//! `alloc` feature disabled, the crate links no `alloc` and the types can live in a `static`
+//! on targets without a global allocator.
+//!
+//! Grouping and rotation match the heap-backed [`crate::FairQueue`] and [`crate::FairStack`],
+//! including how each removes emptied groups. `insert` and `push` hand the item back inside
+//! a [`CapacityError`] when its group is full or when it would open group `G + 1`; a rejected
+//! item changes nothing. `heapless::Deque` requires `N > 0`, which is checked at compile time.
+
+use core::ptr;
+
+use ::heapless::{Deque, Vec};
+
+use crate::{
+    CapacityError, FairGroup,
+    rotation::{self, Slots},
+};
+
+/// Groups of a heapless structure together with the rotation state.
+struct Groups<'a, V, const G: usize, const N: usize> {
+    groups: Vec<Deque<&'a V, N>, G>,
+    pointer: usize,
+    len: usize,
+}
+
+impl<'a, V: FairGroup, const G: usize, const N: usize> Groups<'a, V, G, N> {
+    const EMPTY: Self = Self {
+        groups: Vec::new(),
+        pointer: 0,
+        len: 0,
+    };
+
+    /// Appends `value` to its group, or opens a new group at the end of the rotation.
+    /// `head` picks the end of a group that stays stable while items are appended.
+    fn insert(
+        &mut self,
+        value: &'a V,
+        head: for<'g> fn(&'g Deque<&'a V, N>) -> Option<&'g &'a V>,
+    ) -> Result<(), CapacityError<&'a V>> {
+        let slot = self.groups.iter().position(|group| {
+            head(group).is_some_and(|v| ptr::eq(*v, value) || v.is_same_group(value))
+        });
+        let group = match slot {
+            Some(slot) => &mut self.groups[slot],
+            None if self.groups.is_full() => return Err(CapacityError::new(value)),
+            None => {
+                // Cannot fail: the vector was checked for room above.
+                let _ = self.groups.push(Deque::new());
+                let last = self.groups.len() - 1;
+                &mut self.groups[last]
+            }
+        };
+        group.push_back(value).map_err(CapacityError::new)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Takes an item from the pointed group with `take`, then moves on. An emptied group is
+    /// removed with `Vec::remove` when `shift` is set, keeping the arrival order like
+    /// `FairQueue`, and with `swap_remove` otherwise, like `FairStack`.
+    fn pop_with(
+        &mut self,
+        take: fn(&mut Deque<&'a V, N>) -> Option<&'a V>,
+        shift: bool,
+    ) -> Option<&'a V> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let mut slots = Ends {
+            groups: &mut self.groups,
+            take,
+            shift,
+        };
+        let popped = rotation::pop(&mut slots, &mut self.pointer, false)?;
+        self.len -= 1;
+        Some(popped.item)
+    }
+
+    fn peek_with(
+        &self,
+        head: for<'g> fn(&'g Deque<&'a V, N>) -> Option<&'g &'a V>,
+    ) -> Option<&'a V> {
+        head(self.groups.get(self.pointer)?).copied()
+    }
+
+    fn clear(&mut self) {
+        self.groups.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+}
+
+/// Heapless groups served from the end `take` picks.
+struct Ends<'g, 'a, V, const G: usize, const N: usize> {
+    groups: &'g mut Vec<Deque<&'a V, N>, G>,
+    take: fn(&mut Deque<&'a V, N>) -> Option<&'a V>,
+    shift: bool,
+}
+
+impl<'a, V, const G: usize, const N: usize> Slots for Ends<'_, 'a, V, G, N> {
+    type Item = &'a V;
+
+    fn count(&self) -> usize {
+        self.groups.len()
+    }
+
+    fn take(&mut self, slot: usize) -> Option<&'a V> {
+        (self.take)(&mut self.groups[slot])
+    }
+
+    fn is_drained(&self, slot: usize) -> bool {
+        self.groups[slot].is_empty()
+    }
+
+    fn remove(&mut self, slot: usize) {
+        if self.shift {
+            self.groups.remove(slot);
+        } else {
+            self.groups.swap_remove(slot);
+        }
+    }
+}
+
+/// Fair queue storing at most `G` groups of at most `N` items in heapless collections.
+///
+/// ```
+/// use fairqueue::FairGroup;
+/// use fairqueue::heapless::FairQueue;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     user_id: u8,
+///     value: u32,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.user_id == other.user_id
+///     }
+/// }
+///
+/// let a1 = Event { user_id: 1, value: 1 };
+/// let a2 = Event { user_id: 1, value: 2 };
+/// let a3 = Event { user_id: 1, value: 3 };
+/// let b1 = Event { user_id: 2, value: 10 };
+/// let c1 = Event { user_id: 3, value: 20 };
+///
+/// let mut queue: FairQueue<Event, 2, 2> = FairQueue::new();
+/// assert!(queue.insert(&a1).is_ok());
+/// assert!(queue.insert(&a2).is_ok());
+/// assert_eq!(queue.insert(&a3).unwrap_err().into_inner(), &a3);
+/// assert!(queue.insert(&b1).is_ok());
+/// assert_eq!(queue.insert(&c1).unwrap_err().into_inner(), &c1);
+///
+/// assert_eq!(queue.pop(), Some(&a1));
+/// assert_eq!(queue.pop(), Some(&b1));
+/// assert_eq!(queue.pop(), Some(&a2));
+/// assert!(queue.pop().is_none());
+/// ```
+pub struct FairQueue<'a, V: FairGroup, const G: usize, const N: usize> {
+    inner: Groups<'a, V, G, N>,
+}
+
+impl<'a, V: FairGroup, const G: usize, const N: usize> FairQueue<'a, V, G, N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            inner: Groups::EMPTY,
+        }
+    }
+
+    /// Appends an item to the back of its group, or opens a new group at the end of the rotation.
+    pub fn insert(&mut self, value: &'a V) -> Result<(), CapacityError<&'a V>> {
+        self.inner.insert(value, Deque::front)
+    }
+
+    /// Retrieves the next item in the queue (FIFO) while maintaining spatial distancing.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<&'a V> {
+        self.inner.pop_with(Deque::pop_front, true)
+    }
+
+    /// Peeks at the next item in the queue without removing it.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek(&self) -> Option<&'a V> {
+        self.inner.peek_with(Deque::front)
+    }
+
+    /// Returns the number of enqueued items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.len
+    }
+
+    /// Returns true when the queue holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inner.len == 0
+    }
+
+    /// Returns the number of distinct groups tracked by the queue.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.inner.groups.len()
+    }
+
+    /// Clears all items and resets the round-robin pointer.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+impl<V: FairGroup, const G: usize, const N: usize> Default for FairQueue<'_, V, G, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fair stack storing at most `G` groups of at most `N` items in heapless collections.
+pub struct FairStack<'a, V: FairGroup, const G: usize, const N: usize> {
+    inner: Groups<'a, V, G, N>,
+}
+
+impl<'a, V: FairGroup, const G: usize, const N: usize> FairStack<'a, V, G, N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            inner: Groups::EMPTY,
+        }
+    }
+
+    /// Pushes an item onto its group, or opens a new group at the end of the rotation.
+    pub fn push(&mut self, value: &'a V) -> Result<(), CapacityError<&'a V>> {
+        self.inner.insert(value, Deque::back)
+    }
+
+    /// Pops the next item while rotating across groups fairly.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<&'a V> {
+        self.inner.pop_with(Deque::pop_back, false)
+    }
+
+    /// Peeks at the next item due for popping.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek(&self) -> Option<&'a V> {
+        self.inner.peek_with(Deque::back)
+    }
+
+    /// Returns the number of stacked items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.len
+    }
+
+    /// Returns true when the stack holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inner.len == 0
+    }
+
+    /// Returns the number of distinct groups tracked by the stack.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.inner.groups.len()
+    }
+
+    /// Clears all items and resets the round-robin pointer.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+impl<V: FairGroup, const G: usize, const N: usize> Default for FairStack<'_, V, G, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    fn events(groups: &[usize]) -> std::vec::Vec<Event> {
+        groups
+            .iter()
+            .enumerate()
+            .map(|(timestamp, &group)| Event {
+                timestamp: timestamp as u32,
+                group,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn zero_groups_rejects_everything() {
+        let events = events(&[0]);
+        let mut queue: FairQueue<Event, 0, 4> = FairQueue::new();
+        assert!(queue.insert(&events[0]).is_err());
+        let mut stack: FairStack<Event, 0, 4> = FairStack::new();
+        assert!(stack.push(&events[0]).is_err());
+        assert_eq!(stack.group_count(), 0);
+        assert!(queue.is_empty() && stack.is_empty());
+    }
+
+    #[test]
+    fn stack_serves_newest_per_group() {
+        let events = events(&[0, 1, 0, 1, 0]);
+        let mut stack: FairStack<Event, 2, 2> = FairStack::new();
+        for event in &events[..4] {
+            assert!(stack.push(event).is_ok());
+        }
+        assert_eq!(stack.push(&events[4]).unwrap_err().into_inner(), &events[4]);
+        assert_eq!(stack.peek(), Some(&events[2]));
+        assert_eq!(stack.pop(), Some(&events[2]));
+        assert_eq!(stack.pop(), Some(&events[3]));
+        assert!(stack.push(&events[4]).is_ok());
+        assert_eq!(stack.pop(), Some(&events[4]));
+        assert_eq!(stack.len(), 2);
+        stack.clear();
+        assert!(stack.pop().is_none());
+    }
+
+    #[cfg(feature = "alloc")]
+    proptest! {
+        #[test]
+        fn prop_matches_heap_backed_order(groups in proptest::collection::vec(0usize..3, 8)) {
+            let events = events(&groups);
+            let mut queue: FairQueue<Event, 3, 8> = FairQueue::new();
+            let mut stack: FairStack<Event, 3, 8> = FairStack::new();
+            let mut heap_queue = crate::FairQueue::new();
+            let mut heap_stack = crate::FairStack::new();
+            for event in &events {
+                prop_assert!(queue.insert(event).is_ok());
+                prop_assert!(stack.push(event).is_ok());
+                heap_queue.insert(event);
+                heap_stack.push(event);
+            }
+            prop_assert!(core::iter::from_fn(|| queue.pop()).eq(core::iter::from_fn(|| heap_queue.pop())));
+            prop_assert!(core::iter::from_fn(|| stack.pop()).eq(core::iter::from_fn(|| heap_stack.pop())));
+        }
+    }
+}
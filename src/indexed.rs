@@ -0,0 +1,452 @@
+use alloc::{collections::VecDeque, vec::Vec};
+use core::hash::Hash;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    FairGroup,
+    rotation::{self, Rotation, Slots},
+};
+
+/// Grouping that exposes a hashable key for the group identity.
+/// Two values must report equal keys exactly when `is_same_group` holds for them.
+pub trait FairKey: FairGroup {
+    type Key: Eq + Hash;
+
+    fn group_key(&self) -> Self::Key;
+}
+
+/// Counts the groups `items` would form, like [`distinct_groups`](crate::distinct_groups)
+/// but in O(n) through a set of group keys (requires the `std` feature).
+#[must_use]
+pub fn distinct_group_keys<V: FairKey>(items: &[V]) -> usize {
+    items
+        .iter()
+        .map(FairKey::group_key)
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Fair queue that finds an item's group through a key-to-slot index instead of a scan,
+/// making `insert` O(1) amortized regardless of the number of groups (requires the `std` feature).
+/// Usually built through [`FairQueue::new_indexed`](crate::FairQueue::new_indexed).
+///
+/// ```
+/// use fairqueue::{FairGroup, FairKey, FairQueue};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     user_id: u32,
+///     value: u32,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.user_id == other.user_id
+///     }
+/// }
+///
+/// impl FairKey for Event {
+///     type Key = u32;
+///
+///     fn group_key(&self) -> u32 {
+///         self.user_id
+///     }
+/// }
+///
+/// let a1 = Event { user_id: 1, value: 1 };
+/// let a2 = Event { user_id: 1, value: 2 };
+/// let b1 = Event { user_id: 2, value: 10 };
+///
+/// let mut queue = FairQueue::new_indexed();
+/// queue.insert(&a1);
+/// queue.insert(&a2);
+/// queue.insert(&b1);
+///
+/// assert_eq!(queue.pop(), Some(&a1));
+/// assert_eq!(queue.pop(), Some(&b1));
+/// assert_eq!(queue.pop(), Some(&a2));
+/// assert!(queue.pop().is_none());
+/// ```
+pub struct IndexedFairQueue<'a, V: FairKey> {
+    groups: Vec<VecDeque<&'a V>>,
+    rotation: Rotation,
+    /// Emptied group buffers kept by `recycle` for reuse by the next new groups.
+    spare: Vec<VecDeque<&'a V>>,
+    index: HashMap<V::Key, usize>,
+    served: Option<HashMap<V::Key, u64>>,
+    pointer: usize,
+    len: usize,
+}
+
+impl<'a, V: FairKey> IndexedFairQueue<'a, V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            groups: Vec::new(),
+            rotation: Rotation::new(),
+            spare: Vec::new(),
+            index: HashMap::new(),
+            served: None,
+            pointer: 0,
+            len: 0,
+        }
+    }
+
+    /// Creates a queue in metrics mode: every pop also bumps a per-key served counter.
+    /// Counters belong to the logical group, not its slot, so they persist after the group
+    /// drains and keep accumulating when an item with the same key recreates it.
+    #[must_use]
+    pub fn with_metrics() -> Self {
+        Self {
+            served: Some(HashMap::new()),
+            ..Self::new()
+        }
+    }
+
+    /// Returns how many items of `sample`'s group were ever popped, or 0 outside metrics mode.
+    #[must_use]
+    pub fn served_count(&self, sample: &V) -> u64 {
+        self.served
+            .as_ref()
+            .and_then(|served| served.get(&sample.group_key()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Iterates over the cumulative served count of every group popped at least once, in
+    /// arbitrary order. Empty outside metrics mode.
+    pub fn served_counts(&self) -> impl Iterator<Item = (&V::Key, u64)> {
+        self.served
+            .iter()
+            .flat_map(HashMap::iter)
+            .map(|(key, count)| (key, *count))
+    }
+
+    /// Inserts a new item, locating its group with a single hash lookup.
+    pub fn insert(&mut self, value: &'a V) {
+        let next_slot = self.groups.len();
+        let slot = *self.index.entry(value.group_key()).or_insert(next_slot);
+        if slot == next_slot {
+            self.groups.push(self.spare.pop().unwrap_or_default());
+            self.rotation.push();
+        }
+        self.groups[slot].push_back(value);
+        self.len += 1;
+    }
+
+    /// Retrieves the next item in the queue (FIFO) while maintaining spatial distancing.
+    /// When a group empties, the index entry of the group moved into its slot is updated.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<&'a V> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let mut slots = KeyIndexed {
+            groups: &mut self.groups,
+            index: &mut self.index,
+        };
+        let popped =
+            rotation::pop_ordered(&mut slots, &mut self.rotation, &mut self.pointer, false)?;
+        let item = popped.item;
+        self.len -= 1;
+        if let Some(served) = &mut self.served {
+            *served.entry(item.group_key()).or_insert(0) += 1;
+        }
+        if !popped.kept {
+            self.index.remove(&item.group_key());
+        }
+        Some(item)
+    }
+
+    /// Peeks at the next item in the queue without removing it.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek(&self) -> Option<&'a V> {
+        self.groups.get(self.pointer)?.front().copied()
+    }
+
+    /// Returns the number of enqueued items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when the queue holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct groups tracked by the queue.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Clears all items, the key index and resets the round-robin pointer.
+    /// Served counters are cumulative and survive the clear.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.rotation.clear();
+        self.spare.clear();
+        self.index.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+
+    /// Empties the queue like [`clear`](Self::clear) but keeps the group buffers for the
+    /// groups created afterwards, so refilling with a similar key set allocates nothing. The
+    /// group list and the key index keep their capacity either way; served counters survive.
+    pub fn recycle(&mut self) {
+        for mut group in self.groups.drain(..) {
+            group.clear();
+            self.spare.push(group);
+        }
+        self.rotation.clear();
+        self.index.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+}
+
+impl<V: FairKey> Default for IndexedFairQueue<'_, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Groups of an [`IndexedFairQueue`] with the key index to re-point when a slot moves.
+struct KeyIndexed<'g, 'a, V: FairKey> {
+    groups: &'g mut Vec<VecDeque<&'a V>>,
+    index: &'g mut HashMap<V::Key, usize>,
+}
+
+impl<'a, V: FairKey> Slots for KeyIndexed<'_, 'a, V> {
+    type Item = &'a V;
+
+    fn count(&self) -> usize {
+        self.groups.len()
+    }
+
+    fn take(&mut self, slot: usize) -> Option<&'a V> {
+        self.groups[slot].pop_front()
+    }
+
+    fn is_drained(&self, slot: usize) -> bool {
+        self.groups[slot].is_empty()
+    }
+
+    /// The drained group's own key is unknown here; `pop` removes it from the index.
+    fn remove(&mut self, slot: usize) {
+        self.groups.swap_remove(slot);
+        if let Some(moved) = self.groups.get(slot).and_then(VecDeque::front) {
+            self.index.insert(moved.group_key(), slot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FairQueue;
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    impl FairKey for Event {
+        type Key = usize;
+
+        fn group_key(&self) -> usize {
+            self.group
+        }
+    }
+
+    #[test]
+    fn index_follows_swap_remove() {
+        let a1 = Event {
+            timestamp: 1,
+            group: 0,
+        };
+        let b1 = Event {
+            timestamp: 2,
+            group: 1,
+        };
+        let c1 = Event {
+            timestamp: 3,
+            group: 2,
+        };
+        let c2 = Event {
+            timestamp: 4,
+            group: 2,
+        };
+        let c3 = Event {
+            timestamp: 5,
+            group: 2,
+        };
+
+        let mut queue = FairQueue::new_indexed();
+        queue.insert(&a1);
+        queue.insert(&b1);
+        queue.insert(&c1);
+
+        // Emptying group 0 moves group 2 into slot 0; later inserts must find it there, while
+        // group 1 keeps its turn.
+        assert_eq!(queue.pop(), Some(&a1));
+        queue.insert(&c2);
+        assert_eq!(queue.group_count(), 2);
+        assert_eq!(queue.pop(), Some(&b1));
+        queue.insert(&c3);
+        assert_eq!(queue.pop(), Some(&c1));
+        assert_eq!(queue.group_count(), 1);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(&c2));
+        assert_eq!(queue.pop(), Some(&c3));
+        assert!(queue.is_empty());
+
+        queue.insert(&a1);
+        queue.clear();
+        assert_eq!(queue.group_count(), 0);
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn recycle_keeps_buffers_and_index_capacity() {
+        let events: Vec<Event> = (0..24)
+            .map(|timestamp| Event {
+                timestamp,
+                group: timestamp as usize % 3,
+            })
+            .collect();
+
+        let mut queue = IndexedFairQueue::new();
+        events.iter().for_each(|event| queue.insert(event));
+        queue.recycle();
+        assert!(queue.pop().is_none());
+        let buffers: usize = queue.spare.iter().map(VecDeque::capacity).sum();
+        let index = queue.index.capacity();
+        assert_eq!(queue.spare.len(), 3);
+
+        events.iter().for_each(|event| queue.insert(event));
+        assert!(queue.spare.is_empty());
+        assert_eq!(
+            queue.groups.iter().map(VecDeque::capacity).sum::<usize>(),
+            buffers
+        );
+        assert_eq!(queue.index.capacity(), index);
+        assert_eq!(queue.pop(), Some(&events[0]));
+        assert_eq!(queue.pop(), Some(&events[1]));
+    }
+
+    #[test]
+    fn distinct_group_counts_match_the_queue() {
+        let events: Vec<Event> = [3, 1, 3, 0, 1, 3, 7]
+            .into_iter()
+            .enumerate()
+            .map(|(timestamp, group)| Event {
+                timestamp: timestamp as u32,
+                group,
+            })
+            .collect();
+
+        assert_eq!(crate::distinct_groups(&events), 4);
+        assert_eq!(distinct_group_keys(&events), 4);
+        assert_eq!(crate::distinct_groups(&events[..3]), 2);
+        assert_eq!(distinct_group_keys::<Event>(&[]), 0);
+
+        let queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.group_count(), 4);
+    }
+
+    #[test]
+    fn served_counts_survive_recreated_groups() {
+        let events: Vec<Event> = [0, 1, 0, 0, 1, 2]
+            .into_iter()
+            .enumerate()
+            .map(|(timestamp, group)| Event {
+                timestamp: timestamp as u32,
+                group,
+            })
+            .collect();
+
+        let mut queue = IndexedFairQueue::with_metrics();
+        queue.insert(&events[0]);
+        queue.insert(&events[1]);
+        while queue.pop().is_some() {}
+        assert_eq!(queue.served_count(&events[0]), 1);
+        assert_eq!(queue.served_count(&events[1]), 1);
+        assert_eq!(queue.served_count(&events[5]), 0);
+
+        // Both groups drained; new items recreate them and their counters keep accumulating.
+        for event in &events[2..] {
+            queue.insert(event);
+        }
+        while queue.pop().is_some() {}
+        queue.clear();
+        assert_eq!(queue.served_count(&events[0]), 3);
+        assert_eq!(queue.served_count(&events[1]), 2);
+        assert_eq!(queue.served_count(&events[5]), 1);
+
+        let mut counts: Vec<(usize, u64)> = queue
+            .served_counts()
+            .map(|(key, count)| (*key, count))
+            .collect();
+        counts.sort_unstable();
+        assert_eq!(counts, [(0, 3), (1, 2), (2, 1)]);
+
+        let mut plain = IndexedFairQueue::new();
+        plain.insert(&events[0]);
+        plain.pop();
+        assert_eq!(plain.served_count(&events[0]), 0);
+        assert_eq!(plain.served_counts().count(), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_plain_queue(
+            ops in proptest::collection::vec(proptest::option::of(0usize..6), 1..64)
+        ) {
+            let events: Vec<Event> = ops
+                .iter()
+                .enumerate()
+                .map(|(idx, op)| Event {
+                    timestamp: idx as u32,
+                    group: op.unwrap_or(0),
+                })
+                .collect();
+
+            let mut indexed = IndexedFairQueue::new();
+            let mut plain = FairQueue::new();
+            for (op, event) in ops.iter().zip(&events) {
+                if op.is_some() {
+                    indexed.insert(event);
+                    plain.insert(event);
+                } else {
+                    prop_assert_eq!(
+                        indexed.pop().map(|e| e.timestamp),
+                        plain.pop().map(|e| e.timestamp)
+                    );
+                }
+                prop_assert_eq!(indexed.group_count(), plain.group_count());
+            }
+
+            while let Some(expected) = plain.pop() {
+                prop_assert_eq!(indexed.pop().map(|e| e.timestamp), Some(expected.timestamp));
+            }
+            prop_assert!(indexed.pop().is_none());
+        }
+    }
+}
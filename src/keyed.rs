@@ -0,0 +1,190 @@
+use alloc::{collections::VecDeque, vec::Vec};
+
+use crate::rotation::{self, Rotation};
+
+/// Fair queue grouping items by a key extracted with a closure, without requiring `FairGroup`.
+/// Each group stores its key, so inserting only computes the key of the incoming item.
+/// Usually built through [`FairQueue::new_by`](crate::FairQueue::new_by).
+///
+/// ```
+/// use fairqueue::FairQueue;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Request {
+///     user_id: u32,
+///     path: &'static str,
+/// }
+///
+/// let a1 = Request { user_id: 1, path: "/a" };
+/// let a2 = Request { user_id: 1, path: "/b" };
+/// let b1 = Request { user_id: 2, path: "/c" };
+///
+/// let mut queue = FairQueue::new_by(|req: &Request| req.user_id);
+/// queue.insert(&a1);
+/// queue.insert(&a2);
+/// queue.insert(&b1);
+///
+/// assert_eq!(queue.pop(), Some(&a1));
+/// assert_eq!(queue.pop(), Some(&b1));
+/// assert_eq!(queue.pop(), Some(&a2));
+/// assert!(queue.pop().is_none());
+/// ```
+pub struct KeyedFairQueue<'a, V, K, F> {
+    groups: Vec<(K, VecDeque<&'a V>)>,
+    rotation: Rotation,
+    key_fn: F,
+    pointer: usize,
+    len: usize,
+}
+
+impl<'a, V, K: Eq, F: Fn(&V) -> K> KeyedFairQueue<'a, V, K, F> {
+    #[must_use]
+    pub fn new(key_fn: F) -> Self {
+        Self {
+            groups: Vec::new(),
+            rotation: Rotation::new(),
+            key_fn,
+            pointer: 0,
+            len: 0,
+        }
+    }
+
+    /// Inserts a new item into the group sharing its key, creating the group if needed.
+    pub fn insert(&mut self, value: &'a V) {
+        let key = (self.key_fn)(value);
+        if let Some((_, group)) = self
+            .groups
+            .iter_mut()
+            .find(|(group_key, _)| *group_key == key)
+        {
+            group.push_back(value);
+        } else {
+            let mut new_group = VecDeque::new();
+            new_group.push_back(value);
+            self.groups.push((key, new_group));
+            self.rotation.push();
+        }
+        self.len += 1;
+    }
+
+    /// Retrieves the next item in the queue (FIFO) while maintaining spatial distancing.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<&'a V> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let popped = rotation::pop_ordered(
+            &mut self.groups,
+            &mut self.rotation,
+            &mut self.pointer,
+            false,
+        )?;
+        self.len -= 1;
+        Some(popped.item)
+    }
+
+    /// Peeks at the next item in the queue without removing it.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek(&self) -> Option<&'a V> {
+        self.groups.get(self.pointer)?.1.front().copied()
+    }
+
+    /// Returns the number of enqueued items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when the queue holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct keys tracked by the queue.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Clears all items and resets the round-robin pointer.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.rotation.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FairQueue;
+
+    /// Deliberately not implementing `FairGroup`.
+    #[derive(Debug, PartialEq)]
+    struct Request {
+        user_id: u32,
+        sequence: u32,
+    }
+
+    #[test]
+    fn groups_by_closure_key() {
+        let requests: Vec<Request> = [1, 2, 1, 3, 2, 1]
+            .into_iter()
+            .enumerate()
+            .map(|(sequence, user_id)| Request {
+                user_id,
+                sequence: sequence as u32,
+            })
+            .collect();
+
+        let mut queue = FairQueue::keyed_by(|req: &Request| req.user_id);
+        for request in &requests {
+            queue.insert(request);
+        }
+        assert_eq!(queue.len(), 6);
+        assert_eq!(queue.group_count(), 3);
+        assert_eq!(queue.peek(), Some(&requests[0]));
+
+        let order: Vec<u32> = core::iter::from_fn(|| queue.pop())
+            .map(|req| req.sequence)
+            .collect();
+        assert_eq!(order, vec![0, 1, 3, 2, 4, 5]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+    }
+
+    #[test]
+    fn queues_foreign_type_by_closure() {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+        let addrs = [
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)),
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+            IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3)),
+        ];
+
+        let first_octet = |addr: &IpAddr| match addr {
+            IpAddr::V4(v4) => v4.octets()[0],
+            IpAddr::V6(v6) => v6.octets()[0],
+        };
+        let mut queue = FairQueue::new_by(first_octet);
+        for addr in &addrs {
+            queue.insert(addr);
+        }
+        assert_eq!(queue.group_count(), 3);
+
+        let order: Vec<&IpAddr> = core::iter::from_fn(|| queue.pop()).collect();
+        assert_eq!(
+            order,
+            vec![&addrs[0], &addrs[2], &addrs[3], &addrs[1], &addrs[4]]
+        );
+    }
+}
@@ -1,14 +1,145 @@
 #![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
 
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
 #[cfg(feature = "std")]
 extern crate std;
 
+#[cfg(feature = "alloc")]
+mod deque;
+#[cfg(feature = "alloc")]
+mod drr;
+mod error;
 mod group;
+#[cfg(feature = "alloc")]
+mod hashed;
+#[cfg(feature = "heapless")]
+pub mod heapless;
+#[cfg(feature = "std")]
+mod indexed;
+#[cfg(feature = "alloc")]
+mod keyed;
+#[cfg(feature = "alloc")]
+mod macros;
+#[cfg(feature = "alloc")]
+mod merge;
+#[cfg(feature = "alloc")]
+mod ordered;
+#[cfg(feature = "alloc")]
+mod owned;
+#[cfg(feature = "alloc")]
+mod prehashed;
+#[cfg(feature = "alloc")]
+mod priority;
+#[cfg(feature = "alloc")]
 mod queue;
+#[cfg(feature = "std")]
+mod report;
+#[cfg(any(feature = "alloc", feature = "heapless"))]
+mod rotation;
+#[cfg(feature = "alloc")]
+mod scheduler;
+#[cfg(feature = "alloc")]
 mod stack;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "sync")]
+mod sync;
+#[cfg(feature = "alloc")]
+mod weighted;
 
-pub use group::FairGroup;
-pub use queue::FairQueue;
-pub use stack::FairStack;
+#[cfg(feature = "alloc")]
+pub use deque::FairDeque;
+#[cfg(feature = "alloc")]
+pub use drr::DrrFairQueue;
+pub use error::{CapacityError, FairnessViolation, GroupError, InvariantError, QueueError};
+/// Derives [`FairGroup`] from the fields marked `#[fair_group(key)]` (requires the
+/// `derive` feature). Values are in the same group when all key fields are equal.
+///
+/// ```
+/// use fairqueue::{FairGroup, FairQueue};
+///
+/// #[derive(Debug, PartialEq, FairGroup)]
+/// struct Event {
+///     #[fair_group(key)]
+///     tenant: &'static str,
+///     #[fair_group(key)]
+///     region: u8,
+///     value: u32,
+/// }
+///
+/// let a = Event { tenant: "alice", region: 1, value: 1 };
+/// let b = Event { tenant: "alice", region: 2, value: 2 };
+/// let c = Event { tenant: "alice", region: 1, value: 3 };
+/// assert!(a.is_same_group(&c));
+/// assert!(!a.is_same_group(&b));
+///
+/// let mut queue = FairQueue::new();
+/// queue.extend([&a, &c, &b]);
+/// assert_eq!(queue.group_count(), 2);
+/// ```
+///
+/// At least one key field is required:
+///
+/// ```compile_fail
+/// #[derive(fairqueue::FairGroup)]
+/// struct Event {
+///     tenant: &'static str,
+/// }
+/// ```
+///
+/// and `key` is the only accepted marker:
+///
+/// ```compile_fail
+/// #[derive(fairqueue::FairGroup)]
+/// struct Event {
+///     #[fair_group(id)]
+///     tenant: &'static str,
+/// }
+/// ```
+///
+/// Enums are not supported:
+///
+/// ```compile_fail
+/// #[derive(fairqueue::FairGroup)]
+/// enum Event {
+///     Login(#[fair_group(key)] u32),
+/// }
+/// ```
+#[cfg(feature = "derive")]
+pub use fairqueue_derive::FairGroup;
+pub use group::{FairGroup, GroupId, TryFairGroup, distinct_groups};
+#[cfg(feature = "alloc")]
+pub use hashed::HashedFairQueue;
+#[cfg(feature = "std")]
+pub use indexed::{FairKey, IndexedFairQueue, distinct_group_keys};
+#[cfg(feature = "alloc")]
+pub use keyed::KeyedFairQueue;
+#[cfg(feature = "alloc")]
+pub use merge::{FairMerge, Interleave, interleave, merge_sorted_schedules};
+#[cfg(feature = "alloc")]
+pub use ordered::OrderedFairQueue;
+#[cfg(feature = "alloc")]
+pub use owned::OwnedFairQueue;
+#[cfg(feature = "alloc")]
+pub use prehashed::{FairHash, PrehashedFairQueue};
+#[cfg(feature = "alloc")]
+pub use priority::PriorityFairStack;
+#[cfg(feature = "alloc")]
+pub use queue::{
+    BacklogStats, DrainFilter, FairQueue, GroupEntry, GroupOverflow, GroupView, OccupiedGroupEntry,
+    OverflowPolicy, PopOutcome, StaticFairQueue, VacantGroupEntry,
+};
+#[cfg(feature = "std")]
+pub use report::CapacityReport;
+#[cfg(feature = "alloc")]
+pub use scheduler::{LongestQueueFirst, RoundRobin, Scheduler, SlotView, Strategy};
+#[cfg(feature = "alloc")]
+pub use stack::{FairStack, StaticFairStack};
+#[cfg(feature = "async")]
+pub use stream::FairQueueStream;
+#[cfg(feature = "sync")]
+pub use sync::SyncFairQueue;
+#[cfg(feature = "alloc")]
+pub use weighted::WeightedFairQueue;
@@ -0,0 +1,88 @@
+/// Builds a [`FairQueue`](crate::FairQueue) from a list of references, inserting them in
+/// order. Expands to `FairQueue::new()` followed by one `insert` per element, so it works
+/// without `std`. A trailing comma is accepted.
+///
+/// ```
+/// use fairqueue::{FairGroup, FairQueue, fairqueue};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     user_id: &'static str,
+///     value: u32,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.user_id == other.user_id
+///     }
+/// }
+///
+/// let a1 = Event { user_id: "alice", value: 1 };
+/// let a2 = Event { user_id: "alice", value: 2 };
+/// let b1 = Event { user_id: "bob", value: 10 };
+///
+/// let queue = fairqueue![&a1, &a2, &b1,];
+///
+/// let mut manual = FairQueue::new();
+/// for event in [&a1, &a2, &b1] {
+///     manual.insert(event);
+/// }
+///
+/// assert_eq!(format!("{queue:?}"), format!("{manual:?}"));
+/// assert!(queue.into_iter().eq(manual));
+///
+/// let empty: FairQueue<Event> = fairqueue![];
+/// assert!(empty.is_empty());
+/// ```
+#[macro_export]
+macro_rules! fairqueue {
+    ($($value:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut queue = $crate::FairQueue::new();
+        $(queue.insert($value);)*
+        queue
+    }};
+}
+
+/// Builds a [`FairStack`](crate::FairStack) from a list of references, pushing them in
+/// order. Expands to `FairStack::new()` followed by one `push` per element, so it works
+/// without `std`. A trailing comma is accepted.
+///
+/// ```
+/// use fairqueue::{FairGroup, FairStack, fairstack};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     user_id: &'static str,
+///     value: u32,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.user_id == other.user_id
+///     }
+/// }
+///
+/// let a1 = Event { user_id: "alice", value: 1 };
+/// let a2 = Event { user_id: "alice", value: 2 };
+/// let b1 = Event { user_id: "bob", value: 10 };
+///
+/// let stack = fairstack![&a1, &a2, &b1];
+///
+/// let mut manual = FairStack::new();
+/// for event in [&a1, &a2, &b1] {
+///     manual.push(event);
+/// }
+///
+/// assert_eq!(format!("{stack:?}"), format!("{manual:?}"));
+/// assert!(stack.into_iter().eq(manual));
+/// ```
+#[macro_export]
+macro_rules! fairstack {
+    ($($value:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut stack = $crate::FairStack::new();
+        $(stack.push($value);)*
+        stack
+    }};
+}
@@ -0,0 +1,519 @@
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::{iter::FusedIterator, ptr};
+
+use crate::{FairGroup, FairQueue, RoundRobin, Scheduler};
+
+/// Merges several queues into one fair schedule that also follows a global order key.
+///
+/// Items of the same group coming from different queues are joined into one group,
+/// keeping each queue's FIFO order and concatenating queues in iteration order.
+/// Scheduling then proceeds in rounds: every non-empty group is served once per round,
+/// and among the groups not yet served in the current round the one whose head has
+/// the smallest `order_key` goes next (ties favour the group seen first). The group
+/// served last is never picked first in the following round unless it is the only one
+/// left, so the spacing guarantee of `FairQueue` is preserved.
+///
+/// ```
+/// use fairqueue::{FairGroup, FairQueue, merge_sorted_schedules};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     user_id: &'static str,
+///     timestamp: u64,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.user_id == other.user_id
+///     }
+/// }
+///
+/// let a1 = Event { user_id: "alice", timestamp: 5 };
+/// let a2 = Event { user_id: "alice", timestamp: 6 };
+/// let b1 = Event { user_id: "bob", timestamp: 1 };
+///
+/// let mut first = FairQueue::new();
+/// first.insert(&a1);
+/// first.insert(&a2);
+/// let mut second = FairQueue::new();
+/// second.insert(&b1);
+///
+/// let merged = merge_sorted_schedules([first, second], |event| event.timestamp);
+/// assert_eq!(merged, vec![&b1, &a1, &a2]);
+/// ```
+pub fn merge_sorted_schedules<'a, V, I, F>(queues: I, order_key: F) -> Vec<&'a V>
+where
+    V: FairGroup,
+    I: IntoIterator<Item = FairQueue<'a, V>>,
+    F: Fn(&V) -> u64,
+{
+    let mut groups: Vec<VecDeque<&'a V>> = Vec::new();
+    let mut remaining = 0;
+
+    for mut queue in queues {
+        while let Some(item) = queue.pop() {
+            if let Some(group) = groups.iter_mut().find(|group| {
+                group
+                    .front()
+                    .is_some_and(|v| ptr::eq(*v, item) || (*v).is_same_group(item))
+            }) {
+                group.push_back(item);
+            } else {
+                groups.push(VecDeque::from([item]));
+            }
+            remaining += 1;
+        }
+    }
+
+    let mut schedule = Vec::with_capacity(remaining);
+    let mut served = vec![false; groups.len()];
+    let mut last_served: Option<usize> = None;
+
+    while remaining > 0 {
+        let fresh_round = groups
+            .iter()
+            .zip(&served)
+            .all(|(group, &done)| done || group.is_empty());
+        if fresh_round {
+            served.fill(false);
+        }
+
+        let pick = |skip: Option<usize>| {
+            groups
+                .iter()
+                .enumerate()
+                .filter(|&(slot, _)| !served[slot] && Some(slot) != skip)
+                .filter_map(|(slot, group)| group.front().map(|head| (order_key(head), slot)))
+                .min()
+                .map(|(_, slot)| slot)
+        };
+
+        let slot = if fresh_round {
+            pick(last_served).or_else(|| pick(None))
+        } else {
+            pick(None)
+        };
+        let Some(slot) = slot else {
+            break;
+        };
+
+        if let Some(item) = groups[slot].pop_front() {
+            schedule.push(item);
+            remaining -= 1;
+        }
+        served[slot] = true;
+        last_served = Some(slot);
+    }
+
+    schedule
+}
+
+/// Round-robin over several borrowed queues, composing fairness at two levels: `pop` takes
+/// one item from each non-empty sub-queue in turn, and each sub-queue picks that item with
+/// its own scheduler, exactly as its own `pop` would.
+///
+/// The adapter keeps its own pointer over the sub-queues, independent of the group pointer
+/// inside each of them. After a sub-queue is served the pointer moves to the next one, and
+/// empty sub-queues are skipped without losing their turn order. Nothing is merged or copied:
+/// items stay in their sub-queue until popped, and the queues are usable again once the
+/// adapter is dropped.
+///
+/// ```
+/// use fairqueue::{FairGroup, FairMerge, FairQueue};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Job {
+///     tenant: &'static str,
+///     id: u32,
+/// }
+///
+/// impl FairGroup for Job {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.tenant == other.tenant
+///     }
+/// }
+///
+/// let jobs = [
+///     Job { tenant: "alice", id: 1 },
+///     Job { tenant: "alice", id: 2 },
+///     Job { tenant: "bob", id: 3 },
+///     Job { tenant: "carol", id: 4 },
+/// ];
+///
+/// let mut high = FairQueue::new();
+/// high.insert(&jobs[0]);
+/// high.insert(&jobs[1]);
+/// high.insert(&jobs[2]);
+/// let mut low = FairQueue::new();
+/// low.insert(&jobs[3]);
+///
+/// let order: Vec<u32> = FairMerge::new([&mut high, &mut low]).map(|job| job.id).collect();
+/// assert_eq!(order, vec![1, 4, 3, 2]);
+/// ```
+pub struct FairMerge<'queue, 'value, V: FairGroup, S: Scheduler = RoundRobin> {
+    queues: Vec<&'queue mut FairQueue<'value, V, S>>,
+    pointer: usize,
+}
+
+impl<'queue, 'value, V: FairGroup, S: Scheduler> FairMerge<'queue, 'value, V, S> {
+    /// Rotates across `queues` in iteration order, starting with the first one.
+    #[must_use]
+    pub fn new<I: IntoIterator<Item = &'queue mut FairQueue<'value, V, S>>>(queues: I) -> Self {
+        Self {
+            queues: queues.into_iter().collect(),
+            pointer: 0,
+        }
+    }
+
+    /// Pops from the sub-queue under the pointer, or the next non-empty one after it,
+    /// then moves the pointer past the served sub-queue.
+    pub fn pop(&mut self) -> Option<&'value V> {
+        let count = self.queues.len();
+        for step in 0..count {
+            let index = (self.pointer + step) % count;
+            if let Some(item) = self.queues[index].pop() {
+                self.pointer = (index + 1) % count;
+                return Some(item);
+            }
+        }
+        None
+    }
+
+    /// Returns the number of items left across all sub-queues.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.queues.iter().map(|queue| queue.len()).sum()
+    }
+
+    /// Returns true when every sub-queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.queues.iter().all(|queue| queue.is_empty())
+    }
+}
+
+impl<'value, V: FairGroup, S: Scheduler> Iterator for FairMerge<'_, 'value, V, S> {
+    type Item = &'value V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<V: FairGroup, S: Scheduler> FusedIterator for FairMerge<'_, '_, V, S> {}
+
+/// Alternates strictly between two owned queues: one item from `a`, then one from `b`, and
+/// so on, each queue choosing its item with its own scheduler. Once one side runs dry the
+/// other is drained on its own. Unlike [`FairMerge`], which skips empty sub-queues within
+/// a round over any number of queues, the pair never serves the same side twice in a row
+/// while both still hold items.
+///
+/// ```
+/// use fairqueue::{FairGroup, FairQueue, interleave};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Job {
+///     variant: char,
+///     id: u32,
+/// }
+///
+/// impl FairGroup for Job {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.variant == other.variant
+///     }
+/// }
+///
+/// let jobs = [
+///     Job { variant: 'a', id: 1 },
+///     Job { variant: 'a', id: 2 },
+///     Job { variant: 'a', id: 3 },
+///     Job { variant: 'b', id: 4 },
+/// ];
+///
+/// let a: FairQueue<_> = jobs[..3].iter().collect();
+/// let b: FairQueue<_> = jobs[3..].iter().collect();
+///
+/// let order: Vec<u32> = interleave(a, b).map(|job| job.id).collect();
+/// assert_eq!(order, vec![1, 4, 2, 3]);
+/// ```
+pub fn interleave<'a, V: FairGroup, S: Scheduler>(
+    a: FairQueue<'a, V, S>,
+    b: FairQueue<'a, V, S>,
+) -> Interleave<'a, V, S> {
+    Interleave { a, b, a_next: true }
+}
+
+/// Iterator returned by [`interleave`].
+pub struct Interleave<'a, V: FairGroup, S: Scheduler = RoundRobin> {
+    a: FairQueue<'a, V, S>,
+    b: FairQueue<'a, V, S>,
+    /// Whether `a` is due, flipped after every item its side actually delivered.
+    a_next: bool,
+}
+
+impl<'a, V: FairGroup, S: Scheduler> Interleave<'a, V, S> {
+    /// Returns the number of items left across both queues.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.a.len() + self.b.len()
+    }
+
+    /// Returns true when both queues are empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.a.is_empty() && self.b.is_empty()
+    }
+
+    /// Hands back both queues with whatever they still hold.
+    #[must_use]
+    pub fn into_inner(self) -> (FairQueue<'a, V, S>, FairQueue<'a, V, S>) {
+        (self.a, self.b)
+    }
+}
+
+impl<'a, V: FairGroup, S: Scheduler> Iterator for Interleave<'a, V, S> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (due, other) = if self.a_next {
+            (&mut self.a, &mut self.b)
+        } else {
+            (&mut self.b, &mut self.a)
+        };
+        match due.pop() {
+            Some(item) => {
+                self.a_next = !self.a_next;
+                Some(item)
+            }
+            None => other.pop(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<V: FairGroup, S: Scheduler> FusedIterator for Interleave<'_, V, S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u64,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    #[test]
+    fn rounds_follow_order_key() {
+        let a1 = Event {
+            timestamp: 30,
+            group: 0,
+        };
+        let a2 = Event {
+            timestamp: 31,
+            group: 0,
+        };
+        let b1 = Event {
+            timestamp: 10,
+            group: 1,
+        };
+        let b2 = Event {
+            timestamp: 40,
+            group: 1,
+        };
+        let c1 = Event {
+            timestamp: 20,
+            group: 2,
+        };
+        let a3 = Event {
+            timestamp: 5,
+            group: 0,
+        };
+
+        let mut first = FairQueue::new();
+        first.insert(&a1);
+        first.insert(&b1);
+        first.insert(&a2);
+        let mut second = FairQueue::new();
+        second.insert(&c1);
+        second.insert(&b2);
+        second.insert(&a3);
+
+        let merged = merge_sorted_schedules([first, second], |event| event.timestamp);
+        let timestamps: Vec<u64> = merged.iter().map(|event| event.timestamp).collect();
+
+        // Round one: b1, c1, a1 by timestamp. Round two may not open with
+        // group 0 again, so b2 precedes a2 despite its later timestamp.
+        // Round three: a3 alone.
+        assert_eq!(timestamps, vec![10, 20, 30, 40, 31, 5]);
+    }
+
+    #[test]
+    fn last_served_group_does_not_open_next_round() {
+        let a1 = Event {
+            timestamp: 2,
+            group: 0,
+        };
+        let a2 = Event {
+            timestamp: 3,
+            group: 0,
+        };
+        let b1 = Event {
+            timestamp: 1,
+            group: 1,
+        };
+        let b2 = Event {
+            timestamp: 9,
+            group: 1,
+        };
+
+        let mut queue = FairQueue::new();
+        queue.insert(&a1);
+        queue.insert(&a2);
+        queue.insert(&b1);
+        queue.insert(&b2);
+
+        let merged = merge_sorted_schedules([queue], |event| event.timestamp);
+        assert_eq!(merged, vec![&b1, &a1, &b2, &a2]);
+    }
+
+    #[test]
+    fn fair_merge_serves_every_sub_queue_each_round() {
+        let events: Vec<Event> = (0..18)
+            .map(|timestamp| Event {
+                timestamp,
+                group: timestamp as usize % 2,
+            })
+            .collect();
+
+        // Tiers hold 10, 6 and 2 items.
+        let mut tiers = [FairQueue::new(), FairQueue::new(), FairQueue::new()];
+        for (idx, event) in events.iter().enumerate() {
+            let tier = match idx {
+                0..10 => 0,
+                10..16 => 1,
+                _ => 2,
+            };
+            tiers[tier].insert(event);
+        }
+
+        let [first, second, third] = &mut tiers;
+        let mut merge = FairMerge::new([first, second, third]);
+        assert_eq!(merge.len(), 18);
+
+        let tier_of = |event: &Event| match event.timestamp {
+            0..10 => 0,
+            10..16 => 1,
+            _ => 2,
+        };
+        let order: Vec<usize> = merge.by_ref().map(tier_of).collect();
+        assert!(merge.is_empty());
+        assert_eq!(&order[..6], &[0, 1, 2, 0, 1, 2]);
+        assert_eq!(&order[6..14], &[0, 1, 0, 1, 0, 1, 0, 1]);
+        assert_eq!(&order[14..], &[0, 0, 0, 0]);
+        assert!(tiers.iter().all(FairQueue::is_empty));
+    }
+
+    #[test]
+    fn interleave_alternates_then_drains_the_longer_side() {
+        let events: Vec<Event> = (0..9)
+            .map(|timestamp| Event {
+                timestamp,
+                group: timestamp as usize % 2,
+            })
+            .collect();
+        let a: FairQueue<_> = events[..3].iter().collect();
+        let b: FairQueue<_> = events[3..].iter().collect();
+        let b_alone: Vec<u64> = events[3..]
+            .iter()
+            .collect::<FairQueue<_>>()
+            .into_iter()
+            .map(|event| event.timestamp)
+            .collect();
+
+        let mut mixed = interleave(a, b);
+        assert_eq!(mixed.len(), 9);
+        let order: Vec<u64> = mixed.by_ref().map(|event| event.timestamp).collect();
+        assert!(mixed.is_empty());
+        assert_eq!(mixed.next(), None);
+
+        // A-B-A-B-A-B while `a` lasts, then the rest of `b` in its own fair order.
+        let (from_a, from_b): (Vec<u64>, Vec<u64>) = order.iter().partition(|&&ts| ts < 3);
+        assert_eq!(from_a, vec![0, 1, 2]);
+        assert_eq!(from_b, b_alone);
+        let sides: Vec<bool> = order.iter().map(|&ts| ts < 3).collect();
+        assert_eq!(&sides[..6], &[true, false, true, false, true, false]);
+        assert!(sides[6..].iter().all(|&side| !side));
+
+        let (a, b) = interleave(FairQueue::new(), events.iter().collect()).into_inner();
+        assert!(a.is_empty());
+        assert_eq!(b.len(), 9);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_merge_keeps_spacing(
+            inputs in proptest::collection::vec(
+                proptest::collection::vec((0usize..4, 0u64..100), 0..16),
+                1..4,
+            )
+        ) {
+            let events: Vec<Vec<Event>> = inputs
+                .iter()
+                .map(|queue| {
+                    queue
+                        .iter()
+                        .map(|&(group, timestamp)| Event { timestamp, group })
+                        .collect()
+                })
+                .collect();
+
+            let queues = events.iter().map(|events| {
+                let mut queue = FairQueue::new();
+                for event in events {
+                    queue.insert(event);
+                }
+                queue
+            });
+            let merged = merge_sorted_schedules(queues, |event| event.timestamp);
+
+            let total: usize = events.iter().map(Vec::len).sum();
+            prop_assert_eq!(merged.len(), total);
+
+            let mut remaining = [0usize; 4];
+            for event in events.iter().flatten() {
+                remaining[event.group] += 1;
+            }
+
+            let mut last_group: Option<usize> = None;
+            for event in &merged {
+                let gid = event.group;
+                let other_pending = remaining
+                    .iter()
+                    .enumerate()
+                    .any(|(idx, &count)| idx != gid && count > 0);
+                if other_pending && let Some(prev) = last_group {
+                    prop_assert_ne!(prev, gid);
+                }
+                remaining[gid] -= 1;
+                last_group = Some(gid);
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use alloc::vec::Vec;
+use core::{cmp::Ordering, ptr};
+
+use crate::{
+    FairGroup,
+    rotation::{self, Rotation},
+};
+
+/// Fair queue whose groups hand out their smallest item according to a comparator given at
+/// construction, instead of the oldest one. The rotation across groups is the same as
+/// [`FairQueue`](crate::FairQueue); only the order within a group changes. Items comparing
+/// equal leave their group in insertion order, so `|_, _| Ordering::Equal` behaves like a
+/// plain `FairQueue`.
+///
+/// Each group is a vector kept sorted from largest to smallest, so `pop` takes its last item
+/// in O(1). `insert` finds its position with O(log group_size) comparisons and then shifts
+/// the larger items after it, which is cheap for the short groups fair queues tend to hold.
+///
+/// ```
+/// use fairqueue::{FairGroup, FairQueue};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Job {
+///     tenant: &'static str,
+///     deadline: u32,
+/// }
+///
+/// impl FairGroup for Job {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.tenant == other.tenant
+///     }
+/// }
+///
+/// let late = Job { tenant: "alice", deadline: 30 };
+/// let soon = Job { tenant: "alice", deadline: 10 };
+/// let other = Job { tenant: "bob", deadline: 20 };
+///
+/// let mut queue = FairQueue::new_ordered_by(|a: &Job, b: &Job| a.deadline.cmp(&b.deadline));
+/// queue.insert(&late);
+/// queue.insert(&soon);
+/// queue.insert(&other);
+///
+/// assert_eq!(queue.pop(), Some(&soon));
+/// assert_eq!(queue.pop(), Some(&other));
+/// assert_eq!(queue.pop(), Some(&late));
+/// assert!(queue.pop().is_none());
+/// ```
+pub struct OrderedFairQueue<'a, V: FairGroup, F: Fn(&V, &V) -> Ordering> {
+    groups: Vec<Vec<&'a V>>,
+    rotation: Rotation,
+    cmp: F,
+    pointer: usize,
+    len: usize,
+}
+
+impl<'a, V: FairGroup, F: Fn(&V, &V) -> Ordering> OrderedFairQueue<'a, V, F> {
+    /// Creates an empty queue ordering every group by `cmp`, smallest first.
+    #[must_use]
+    pub fn new(cmp: F) -> Self {
+        Self {
+            groups: Vec::new(),
+            rotation: Rotation::new(),
+            cmp,
+            pointer: 0,
+            len: 0,
+        }
+    }
+
+    /// Inserts an item at its sorted position in its group, creating the group at the end
+    /// of the rotation.
+    pub fn insert(&mut self, value: &'a V) {
+        if let Some(group) = self.groups.iter_mut().find(|group| {
+            group
+                .first()
+                .is_some_and(|v| ptr::eq(*v, value) || (*v).is_same_group(value))
+        }) {
+            // Before every item that is not larger, so equal items pop oldest first.
+            let at = group.partition_point(|v| (self.cmp)(v, value) == Ordering::Greater);
+            group.insert(at, value);
+        } else {
+            self.groups.push(alloc::vec![value]);
+            self.rotation.push();
+        }
+        self.len += 1;
+    }
+
+    /// Pops the smallest item of the group under the pointer, then rotates.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<&'a V> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let popped = rotation::pop_ordered(
+            &mut self.groups,
+            &mut self.rotation,
+            &mut self.pointer,
+            false,
+        )?;
+        self.len -= 1;
+        Some(popped.item)
+    }
+
+    /// Peeks at the item the next `pop` would return.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek(&self) -> Option<&'a V> {
+        self.groups.get(self.pointer)?.last().copied()
+    }
+
+    /// Returns the number of enqueued items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when the queue holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct groups tracked by the queue.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Clears all items and resets the round-robin pointer, keeping the comparator.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.rotation.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FairQueue;
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Job {
+        id: u32,
+        deadline: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Job {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    fn by_deadline(a: &Job, b: &Job) -> Ordering {
+        a.deadline.cmp(&b.deadline)
+    }
+
+    #[test]
+    fn custom_comparator_orders_within_group() {
+        let jobs: Vec<Job> = [(5, 0), (2, 0), (9, 1), (2, 0), (1, 1), (7, 0)]
+            .iter()
+            .enumerate()
+            .map(|(id, &(deadline, group))| Job {
+                id: id as u32,
+                deadline,
+                group,
+            })
+            .collect();
+
+        let mut queue = FairQueue::new_ordered_by(by_deadline);
+        for job in &jobs {
+            queue.insert(job);
+        }
+        assert_eq!(queue.len(), 6);
+        assert_eq!(queue.group_count(), 2);
+        assert_eq!(queue.peek().map(|job| job.id), Some(1));
+
+        // Jobs 1 and 3 tie on their deadline and leave in insertion order.
+        let order: Vec<u32> = core::iter::from_fn(|| queue.pop())
+            .map(|job| job.id)
+            .collect();
+        assert_eq!(order, vec![1, 4, 3, 2, 0, 5]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_equal_keys_match_fair_queue(
+            items in proptest::collection::vec((0usize..4, 0u32..100), 1..48)
+        ) {
+            let jobs: Vec<Job> = items
+                .iter()
+                .enumerate()
+                .map(|(id, &(group, deadline))| Job { id: id as u32, deadline, group })
+                .collect();
+
+            let mut ordered = OrderedFairQueue::new(|_: &Job, _: &Job| Ordering::Equal);
+            let mut sorted = OrderedFairQueue::new(by_deadline);
+            for job in &jobs {
+                ordered.insert(job);
+                sorted.insert(job);
+            }
+            let plain: FairQueue<_> = jobs.iter().collect();
+            prop_assert!(core::iter::from_fn(|| ordered.pop()).eq(plain));
+
+            let mut last_deadline = [0u32; 4];
+            while let Some(job) = sorted.pop() {
+                prop_assert!(job.deadline >= last_deadline[job.group]);
+                last_deadline[job.group] = job.deadline;
+            }
+        }
+    }
+}
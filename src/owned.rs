@@ -0,0 +1,460 @@
+use alloc::{collections::VecDeque, vec::Vec};
+use core::ops::Deref;
+
+use crate::{
+    FairGroup, FairQueue,
+    rotation::{self, Rotation, Walk},
+};
+
+/// Spatially distancing fair queue that owns its values.
+/// Behaves like [`FairQueue`](crate::FairQueue) but takes `V` by value, so no
+/// backing storage has to outlive the queue.
+///
+/// ```
+/// use fairqueue::{FairGroup, OwnedFairQueue};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     user_id: &'static str,
+///     value: u32,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.user_id == other.user_id
+///     }
+/// }
+///
+/// let mut queue = OwnedFairQueue::new();
+/// queue.insert(Event { user_id: "alice", value: 1 });
+/// queue.insert(Event { user_id: "alice", value: 2 });
+/// queue.insert(Event { user_id: "bob", value: 10 });
+///
+/// assert_eq!(queue.pop().map(|e| e.value), Some(1));
+/// assert_eq!(queue.pop().map(|e| e.value), Some(10));
+/// assert_eq!(queue.pop().map(|e| e.value), Some(2));
+/// assert!(queue.pop().is_none());
+/// ```
+pub struct OwnedFairQueue<V: FairGroup> {
+    groups: Vec<VecDeque<V>>,
+    rotation: Rotation,
+    pointer: usize,
+    len: usize,
+}
+
+impl<V: FairGroup> OwnedFairQueue<V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            groups: Vec::new(),
+            rotation: Rotation::new(),
+            pointer: 0,
+            len: 0,
+        }
+    }
+
+    /// Inserts a new item into the queue, ensuring spatial distancing between items of the
+    /// same group.
+    pub fn insert(&mut self, value: V) {
+        if let Some(group) = self
+            .groups
+            .iter_mut()
+            .find(|group| group.front().is_some_and(|v| v.is_same_group(&value)))
+        {
+            group.push_back(value);
+        } else {
+            let mut new_group = VecDeque::new();
+            new_group.push_back(value);
+            self.groups.push(new_group);
+            self.rotation.push();
+        }
+        self.len += 1;
+    }
+
+    /// Moves the next item out of the queue (FIFO) while maintaining spatial distancing.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<V> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let popped = rotation::pop_ordered(
+            &mut self.groups,
+            &mut self.rotation,
+            &mut self.pointer,
+            false,
+        )?;
+        self.len -= 1;
+        Some(popped.item)
+    }
+
+    /// Borrows the next item for processing and removes it only once the guard is committed.
+    /// Dropping the guard (or calling `rollback`) leaves the item and rotation untouched.
+    pub fn pop_guard(&mut self) -> Option<OwnedPopGuard<'_, V>> {
+        self.peek()?;
+        Some(OwnedPopGuard { queue: self })
+    }
+
+    /// Peeks at the next item in the queue without removing it.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek(&self) -> Option<&V> {
+        self.groups.get(self.pointer)?.front()
+    }
+
+    /// Returns the number of enqueued items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when the queue holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct groups tracked by the queue.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Iterates over the current head item of each group without consuming them, in the order
+    /// the groups arrived.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_heads(&self) -> OwnedGroupHeads<'_, V> {
+        OwnedGroupHeads {
+            groups: &self.groups,
+            walk: self.rotation.iter(),
+        }
+    }
+
+    /// Returns a borrowing [`FairQueue`] over the current items, with the same groups, rotation
+    /// order and pointer, e.g. to hand the backlog to code written against `FairQueue`. The
+    /// view borrows this queue's storage and is a snapshot: popping from it leaves this queue
+    /// untouched, and this queue cannot change while the view is alive.
+    #[must_use]
+    pub fn as_ref_queue(&self) -> FairQueue<'_, V> {
+        let groups = self
+            .rotation
+            .iter()
+            .map(|slot| self.groups[slot].iter().collect())
+            .collect();
+        FairQueue::from_groups(groups, self.pointer())
+    }
+
+    /// Lets `f` edit every item in place and keeps only those for which it returns true,
+    /// preserving their order within each group. Only the owned queue can offer this, as the
+    /// borrowing queues never hold `&mut V`. Groups left empty are dropped and the rotation
+    /// resumes at the next surviving group. Edits must not change an item's group: items
+    /// stay in the group they were inserted into.
+    pub fn retain_mut<F: FnMut(&mut V) -> bool>(&mut self, mut f: F) {
+        for group in &mut self.groups {
+            group.retain_mut(&mut f);
+        }
+        self.len = self.groups.iter().map(VecDeque::len).sum();
+        self.pointer = rotation::prune(&mut self.groups, &mut self.rotation, self.pointer);
+    }
+
+    /// Clears all items and resets the round-robin pointer.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.rotation.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+
+    /// Returns the position of the pointer in the rotation.
+    fn pointer(&self) -> usize {
+        if self.pointer < self.groups.len() {
+            self.rotation.position(self.pointer)
+        } else {
+            0
+        }
+    }
+}
+
+/// Emits the groups in rotation order followed by the position of the pointer,
+/// matching the format of [`FairQueue`](crate::FairQueue).
+#[cfg(feature = "serde")]
+impl<V: FairGroup + serde::Serialize> serde::Serialize for OwnedFairQueue<V> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let groups: Vec<&VecDeque<V>> = self
+            .rotation
+            .iter()
+            .map(|slot| &self.groups[slot])
+            .collect();
+        let mut state = serializer.serialize_struct("OwnedFairQueue", 2)?;
+        state.serialize_field("groups", &groups)?;
+        state.serialize_field("pointer", &self.pointer())?;
+        state.end()
+    }
+}
+
+/// Wire layout shared by every serialized queue.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedQueue<V> {
+    groups: Vec<VecDeque<V>>,
+    pointer: usize,
+}
+
+/// Rebuilds the queue from a serialized `FairQueue` or `OwnedFairQueue`.
+/// Empty groups are dropped and an out-of-range `pointer` is clamped to 0,
+/// so the restored queue pops in the same order as the one that was saved.
+#[cfg(feature = "serde")]
+impl<'de, V: FairGroup + serde::Deserialize<'de>> serde::Deserialize<'de> for OwnedFairQueue<V> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let SerializedQueue {
+            mut groups,
+            pointer,
+        } = SerializedQueue::deserialize(deserializer)?;
+        groups.retain(|group| !group.is_empty());
+        let len = groups.iter().map(VecDeque::len).sum();
+        let pointer = if pointer < groups.len() { pointer } else { 0 };
+        let mut rotation = Rotation::with_capacity(groups.len());
+        groups.iter().for_each(|_| rotation.push());
+
+        Ok(Self {
+            groups,
+            rotation,
+            pointer,
+            len,
+        })
+    }
+}
+
+impl<V: FairGroup> Default for OwnedFairQueue<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pending pop returned by [`OwnedFairQueue::pop_guard`].
+/// Dereferences to the item that the next `pop` would move out.
+pub struct OwnedPopGuard<'queue, V: FairGroup> {
+    queue: &'queue mut OwnedFairQueue<V>,
+}
+
+impl<V: FairGroup> OwnedPopGuard<'_, V> {
+    /// Moves the item out of the queue, advancing the rotation as `pop` does.
+    pub fn commit(self) -> V {
+        self.queue
+            .pop()
+            .expect("guarded queue holds the borrowed item")
+    }
+
+    /// Leaves the item at the front of its group, as if it was never borrowed.
+    pub fn rollback(self) {}
+}
+
+impl<V: FairGroup> Deref for OwnedPopGuard<'_, V> {
+    type Target = V;
+
+    fn deref(&self) -> &Self::Target {
+        self.queue
+            .peek()
+            .expect("guarded queue holds the borrowed item")
+    }
+}
+
+/// Iterator over the first element of each group.
+pub struct OwnedGroupHeads<'queue, V: FairGroup> {
+    groups: &'queue [VecDeque<V>],
+    walk: Walk<'queue>,
+}
+
+impl<'queue, V: FairGroup> Iterator for OwnedGroupHeads<'queue, V> {
+    type Item = &'queue V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for slot in &mut self.walk {
+            if let Some(item) = self.groups[slot].front() {
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct Event {
+        timestamp: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    fn event(timestamp: u32, group: usize) -> Event {
+        Event { timestamp, group }
+    }
+
+    #[test]
+    fn owns_values_built_on_the_fly() {
+        let mut queue = OwnedFairQueue::new();
+        assert!(queue.is_empty());
+
+        for (timestamp, group) in [(1, 0), (2, 1), (3, 0), (4, 2)] {
+            queue.insert(event(timestamp, group));
+        }
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.group_count(), 3);
+        assert_eq!(queue.peek(), Some(&event(1, 0)));
+
+        let heads: Vec<u32> = queue.group_heads().map(|e| e.timestamp).collect();
+        assert_eq!(heads, vec![1, 2, 4]);
+
+        assert_eq!(queue.pop(), Some(event(1, 0)));
+        assert_eq!(queue.pop(), Some(event(2, 1)));
+        assert_eq!(queue.peek(), Some(&event(4, 2)));
+
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn pop_guard_leaves_item_unless_committed() {
+        let mut queue = OwnedFairQueue::new();
+        assert!(queue.pop_guard().is_none());
+
+        queue.insert(event(1, 0));
+        queue.insert(event(2, 1));
+
+        {
+            let guard = queue.pop_guard().unwrap();
+            assert_eq!(guard.timestamp, 1);
+        }
+        assert_eq!(queue.len(), 2);
+        queue.pop_guard().unwrap().rollback();
+        assert_eq!(queue.peek(), Some(&event(1, 0)));
+
+        assert_eq!(queue.pop_guard().unwrap().commit(), event(1, 0));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop(), Some(event(2, 1)));
+    }
+
+    #[test]
+    fn retain_mut_edits_survivors() {
+        let mut queue = OwnedFairQueue::new();
+        for (timestamp, group) in [(1, 0), (2, 1), (3, 2), (4, 0), (5, 1), (6, 0)] {
+            queue.insert(event(timestamp, group));
+        }
+        assert_eq!(queue.pop(), Some(event(1, 0)));
+
+        // Group 1 disappears under the pointer, so the rotation resumes at group 2.
+        queue.retain_mut(|e| {
+            e.timestamp *= 10;
+            e.group != 1
+        });
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.group_count(), 2);
+
+        let order: Vec<Event> = core::iter::from_fn(|| queue.pop()).collect();
+        assert_eq!(order, vec![event(30, 2), event(40, 0), event(60, 0)]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_keeps_pop_order() {
+        let events: Vec<Event> = [0, 1, 0, 2, 1, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        let mut reference: crate::FairQueue<_> = events.iter().collect();
+        reference.pop();
+        let json = serde_json::to_string(&reference).unwrap();
+
+        let mut restored: OwnedFairQueue<Event> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.len(), reference.len());
+        assert_eq!(restored.group_count(), reference.group_count());
+
+        let json = serde_json::to_string(&restored).unwrap();
+        let mut again: OwnedFairQueue<Event> = serde_json::from_str(&json).unwrap();
+
+        while let Some(expected) = reference.pop() {
+            assert_eq!(restored.pop().as_ref(), Some(expected));
+            assert_eq!(again.pop().as_ref(), Some(expected));
+        }
+        assert!(restored.pop().is_none());
+        assert!(again.pop().is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_clamps_pointer_and_drops_empty_groups() {
+        let json = r#"{"groups":[[{"timestamp":1,"group":0}],[],[{"timestamp":2,"group":1}]],"pointer":7}"#;
+        let mut queue: OwnedFairQueue<Event> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.group_count(), 2);
+        assert_eq!(queue.pop(), Some(event(1, 0)));
+        assert_eq!(queue.pop(), Some(event(2, 1)));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_reference_queue(groups in proptest::collection::vec(0usize..4, 1..32)) {
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, group)| event(idx as u32, *group))
+                .collect();
+
+            let mut reference = crate::FairQueue::new();
+            let mut owned = OwnedFairQueue::new();
+            for item in &events {
+                reference.insert(item);
+                owned.insert(event(item.timestamp, item.group));
+            }
+
+            while let Some(expected) = reference.pop() {
+                prop_assert_eq!(owned.pop().map(|e| e.timestamp), Some(expected.timestamp));
+            }
+            prop_assert!(owned.pop().is_none());
+        }
+
+        #[test]
+        fn prop_as_ref_queue_drains_in_owned_order(
+            groups in proptest::collection::vec(0usize..4, 1..32),
+            skip in 0usize..8,
+        ) {
+            let mut owned = OwnedFairQueue::new();
+            for (idx, group) in groups.iter().enumerate() {
+                owned.insert(event(idx as u32, *group));
+            }
+            for _ in 0..skip {
+                owned.pop();
+            }
+
+            let mut view = owned.as_ref_queue();
+            prop_assert_eq!(view.len(), owned.len());
+            prop_assert_eq!(view.group_count(), owned.group_count());
+            let borrowed: Vec<u32> = view.drain().map(|e| e.timestamp).collect();
+            prop_assert_eq!(owned.len(), groups.len().saturating_sub(skip));
+
+            let moved: Vec<u32> = core::iter::from_fn(|| owned.pop()).map(|e| e.timestamp).collect();
+            prop_assert_eq!(borrowed, moved);
+        }
+    }
+}
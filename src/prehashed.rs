@@ -0,0 +1,253 @@
+use alloc::{collections::VecDeque, vec::Vec};
+use core::ptr;
+
+use crate::{
+    FairGroup,
+    rotation::{self, Rotation},
+};
+
+/// Grouping with a precomputed hash of the group identity.
+/// Values of the same group must report the same `group_hash`; values of
+/// different groups may collide, in which case `is_same_group` decides.
+pub trait FairHash: FairGroup {
+    fn group_hash(&self) -> u64;
+}
+
+/// Value reference paired with its group hash, computed once at insertion.
+struct Hashed<'a, V> {
+    hash: u64,
+    value: &'a V,
+}
+
+/// Fair queue that compares cached group hashes before falling back to `is_same_group`.
+/// Useful when `is_same_group` is expensive, since most group scans only compare integers.
+///
+/// ```
+/// use fairqueue::{FairGroup, FairHash, PrehashedFairQueue};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     tenant: String,
+///     value: u32,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.tenant == other.tenant
+///     }
+/// }
+///
+/// impl FairHash for Event {
+///     fn group_hash(&self) -> u64 {
+///         self.tenant.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+///             (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+///         })
+///     }
+/// }
+///
+/// let a1 = Event { tenant: "alice".into(), value: 1 };
+/// let a2 = Event { tenant: "alice".into(), value: 2 };
+/// let b1 = Event { tenant: "bob".into(), value: 10 };
+///
+/// let mut queue = PrehashedFairQueue::new();
+/// queue.insert(&a1);
+/// queue.insert(&a2);
+/// queue.insert(&b1);
+///
+/// assert_eq!(queue.pop(), Some(&a1));
+/// assert_eq!(queue.pop(), Some(&b1));
+/// assert_eq!(queue.pop(), Some(&a2));
+/// assert!(queue.pop().is_none());
+/// ```
+pub struct PrehashedFairQueue<'a, V: FairHash> {
+    groups: Vec<VecDeque<Hashed<'a, V>>>,
+    rotation: Rotation,
+    pointer: usize,
+    len: usize,
+}
+
+impl<'a, V: FairHash> PrehashedFairQueue<'a, V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            groups: Vec::new(),
+            rotation: Rotation::new(),
+            pointer: 0,
+            len: 0,
+        }
+    }
+
+    /// Inserts a new item, hashing its group once and reusing that hash for every comparison.
+    pub fn insert(&mut self, value: &'a V) {
+        let hash = value.group_hash();
+        let item = Hashed { hash, value };
+
+        if let Some(group) = self.groups.iter_mut().find(|group| {
+            group.front().is_some_and(|head| {
+                head.hash == hash && (ptr::eq(head.value, value) || head.value.is_same_group(value))
+            })
+        }) {
+            group.push_back(item);
+        } else {
+            let mut new_group = VecDeque::new();
+            new_group.push_back(item);
+            self.groups.push(new_group);
+            self.rotation.push();
+        }
+        self.len += 1;
+    }
+
+    /// Retrieves the next item in the queue (FIFO) while maintaining spatial distancing.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<&'a V> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let popped = rotation::pop_ordered(
+            &mut self.groups,
+            &mut self.rotation,
+            &mut self.pointer,
+            false,
+        )?;
+        self.len -= 1;
+        Some(popped.item.value)
+    }
+
+    /// Peeks at the next item in the queue without removing it.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek(&self) -> Option<&'a V> {
+        self.groups
+            .get(self.pointer)?
+            .front()
+            .map(|item| item.value)
+    }
+
+    /// Returns the number of enqueued items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when the queue holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct groups tracked by the queue.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Clears all items and resets the round-robin pointer.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.rotation.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+}
+
+impl<V: FairHash> Default for PrehashedFairQueue<'_, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FairQueue;
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u32,
+        group: usize,
+        /// Deliberately coarse hash so distinct groups collide.
+        bucket: u64,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    impl FairHash for Event {
+        fn group_hash(&self) -> u64 {
+            self.bucket
+        }
+    }
+
+    #[test]
+    fn colliding_hashes_fall_back_to_is_same_group() {
+        let a1 = Event {
+            timestamp: 1,
+            group: 0,
+            bucket: 7,
+        };
+        let b1 = Event {
+            timestamp: 2,
+            group: 1,
+            bucket: 7,
+        };
+        let a2 = Event {
+            timestamp: 3,
+            group: 0,
+            bucket: 7,
+        };
+
+        let mut queue = PrehashedFairQueue::new();
+        queue.insert(&a1);
+        queue.insert(&b1);
+        queue.insert(&a2);
+
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.group_count(), 2);
+        assert_eq!(queue.peek(), Some(&a1));
+        assert_eq!(queue.pop(), Some(&a1));
+        assert_eq!(queue.pop(), Some(&b1));
+        assert_eq!(queue.pop(), Some(&a2));
+        assert_eq!(queue.pop(), None);
+
+        queue.insert(&a1);
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_plain_queue(groups in proptest::collection::vec(0usize..6, 1..48)) {
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, group)| Event {
+                    timestamp: idx as u32,
+                    group: *group,
+                    bucket: (*group % 3) as u64,
+                })
+                .collect();
+
+            let mut hashed = PrehashedFairQueue::new();
+            let mut plain = FairQueue::new();
+            for event in &events {
+                hashed.insert(event);
+                plain.insert(event);
+            }
+
+            prop_assert_eq!(hashed.group_count(), plain.group_count());
+            while let Some(expected) = plain.pop() {
+                prop_assert_eq!(hashed.pop().map(|e| e.timestamp), Some(expected.timestamp));
+            }
+            prop_assert!(hashed.pop().is_none());
+        }
+    }
+}
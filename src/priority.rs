@@ -0,0 +1,229 @@
+use alloc::{collections::BinaryHeap, vec::Vec};
+use core::ptr;
+
+use crate::{FairGroup, rotation};
+
+/// Fair stack whose groups hand out their highest-priority item instead of the last pushed one.
+/// Each group is a binary heap ordered by `V: Ord`; the rotation across groups is the same as
+/// [`FairStack`](crate::FairStack). Items of equal priority within a group come out in
+/// unspecified order.
+///
+/// ```
+/// use core::cmp::Ordering;
+/// use fairqueue::{FairGroup, PriorityFairStack};
+///
+/// #[derive(Debug, PartialEq, Eq)]
+/// struct Job {
+///     tenant: &'static str,
+///     priority: u8,
+/// }
+///
+/// impl FairGroup for Job {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.tenant == other.tenant
+///     }
+/// }
+///
+/// impl Ord for Job {
+///     fn cmp(&self, other: &Self) -> Ordering {
+///         self.priority.cmp(&other.priority)
+///     }
+/// }
+///
+/// impl PartialOrd for Job {
+///     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+///         Some(self.cmp(other))
+///     }
+/// }
+///
+/// let low = Job { tenant: "alice", priority: 1 };
+/// let high = Job { tenant: "alice", priority: 9 };
+/// let other = Job { tenant: "bob", priority: 5 };
+///
+/// let mut stack = PriorityFairStack::new();
+/// stack.push(&high);
+/// stack.push(&low);
+/// stack.push(&other);
+///
+/// assert_eq!(stack.pop(), Some(&high));
+/// assert_eq!(stack.pop(), Some(&other));
+/// assert_eq!(stack.pop(), Some(&low));
+/// assert!(stack.pop().is_none());
+/// ```
+pub struct PriorityFairStack<'a, V: FairGroup + Ord> {
+    groups: Vec<BinaryHeap<&'a V>>,
+    pointer: usize,
+    len: usize,
+}
+
+impl<'a, V: FairGroup + Ord> PriorityFairStack<'a, V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            groups: Vec::new(),
+            pointer: 0,
+            len: 0,
+        }
+    }
+
+    /// Pushes an item into its group's heap, creating the group at the end of the rotation.
+    pub fn push(&mut self, value: &'a V) {
+        if let Some(group) = self.groups.iter_mut().find(|group| {
+            group
+                .peek()
+                .is_some_and(|v| ptr::eq(*v, value) || (*v).is_same_group(value))
+        }) {
+            group.push(value);
+        } else {
+            let mut new_group = BinaryHeap::new();
+            new_group.push(value);
+            self.groups.push(new_group);
+        }
+        self.len += 1;
+    }
+
+    /// Pops the highest-priority item of the group under the pointer, then rotates.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<&'a V> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let popped = rotation::pop(&mut self.groups, &mut self.pointer, false)?;
+        self.len -= 1;
+        Some(popped.item)
+    }
+
+    /// Peeks at the item the next `pop` would return.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek(&self) -> Option<&'a V> {
+        self.groups.get(self.pointer)?.peek().copied()
+    }
+
+    /// Returns the number of stacked items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when the stack holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct groups tracked by the stack.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Clears all items and resets the round-robin pointer.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+}
+
+impl<V: FairGroup + Ord> Default for PriorityFairStack<'_, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cmp::Ordering;
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Job {
+        priority: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Job {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    impl Ord for Job {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.priority.cmp(&other.priority)
+        }
+    }
+
+    impl PartialOrd for Job {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    #[test]
+    fn highest_priority_first_within_group() {
+        let jobs: Vec<Job> = [(3, 0), (7, 0), (1, 1), (5, 0), (4, 1)]
+            .iter()
+            .map(|&(priority, group)| Job { priority, group })
+            .collect();
+
+        let mut stack = PriorityFairStack::new();
+        for job in &jobs {
+            stack.push(job);
+        }
+        assert_eq!(stack.len(), 5);
+        assert_eq!(stack.group_count(), 2);
+        assert_eq!(stack.peek().map(|job| job.priority), Some(7));
+
+        let order: Vec<u32> = core::iter::from_fn(|| stack.pop())
+            .map(|job| job.priority)
+            .collect();
+        assert_eq!(order, vec![7, 4, 5, 1, 3]);
+        assert!(stack.is_empty());
+        assert_eq!(stack.group_count(), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_priority_and_spacing(
+            items in proptest::collection::vec((0usize..4, 0u32..100), 1..48)
+        ) {
+            let jobs: Vec<Job> = items
+                .iter()
+                .map(|&(group, priority)| Job { priority, group })
+                .collect();
+
+            let mut stack = PriorityFairStack::new();
+            let mut remaining = [0usize; 4];
+            for job in &jobs {
+                stack.push(job);
+                remaining[job.group] += 1;
+            }
+
+            let mut last_priority = [u32::MAX; 4];
+            let mut last_group: Option<usize> = None;
+            while let Some(job) = stack.pop() {
+                let gid = job.group;
+                prop_assert!(job.priority <= last_priority[gid]);
+                last_priority[gid] = job.priority;
+
+                let other_pending = remaining
+                    .iter()
+                    .enumerate()
+                    .any(|(idx, &count)| idx != gid && count > 0);
+                if other_pending && let Some(prev) = last_group {
+                    prop_assert_ne!(prev, gid);
+                }
+                remaining[gid] -= 1;
+                last_group = Some(gid);
+            }
+            prop_assert_eq!(remaining, [0; 4]);
+        }
+    }
+}
@@ -1,7 +1,22 @@
-use alloc::{collections::VecDeque, vec::Vec};
-use core::{ptr, slice};
+use alloc::{
+    collections::{VecDeque, vec_deque},
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Debug},
+    hash::Hash,
+    iter::FusedIterator,
+    mem,
+    ops::Deref,
+    ptr,
+};
 
-use crate::FairGroup;
+use crate::{
+    CapacityError, FairGroup, FairStack, FairnessViolation, GroupError, GroupId, HashedFairQueue,
+    InvariantError, KeyedFairQueue, OrderedFairQueue, QueueError, RoundRobin, Scheduler, Strategy,
+    TryFairGroup,
+    rotation::{self, Rotation, Slots, Walk},
+};
 
 /// Spatially distancing fair queue.
 /// First in, first out, ensuring that each group of similar values
@@ -36,92 +51,504 @@ use crate::FairGroup;
 /// assert_eq!(queue.pop(), Some(&user_a_second));
 /// assert!(queue.pop().is_none());
 /// ```
-pub struct FairQueue<'a, V: FairGroup> {
+///
+/// The order in which groups are served is decided by the [`Scheduler`] `S`, strict
+/// round-robin by default; see [`with_scheduler`](Self::with_scheduler).
+///
+/// Inspection methods such as `peek`, `peek_group`, `group_heads` and `iter` take `&self`
+/// and never move the pointer, so calling them between pops leaves the pop sequence as is.
+///
+/// Groups take turns in the order they arrived. An emptied group leaves that order without
+/// disturbing the others, so `group_heads`, `iter` and the positions reported by methods such
+/// as `pointer` and `pop_with_group_index` count groups in arrival order.
+pub struct FairQueue<'a, V: ?Sized, S = RoundRobin> {
+    /// Group storage; an emptied group is replaced by the last one with `swap_remove`.
     groups: Vec<VecDeque<&'a V>>,
+    /// `GroupId` of every group, slot-aligned with `groups`.
+    ids: Vec<GroupId>,
+    /// Arrival order of the slots, kept intact by the `swap_remove` of emptied groups.
+    rotation: Rotation,
+    next_id: u64,
+    /// Slot, not position, of the group the next pop starts from.
     pointer: usize,
+    /// Position the first pop starts at, set by `with_start_pointer` until rotation begins.
+    start_pointer: Option<usize>,
     len: usize,
+    last_served: Option<usize>,
+    group_capacity: usize,
+    /// Whether `pop` keeps serving a group until it empties before rotating.
+    burst: bool,
+    max_len: usize,
+    max_per_group: usize,
+    max_groups: usize,
+    group_overflow: GroupOverflow,
+    /// What `insert` does once `len` reaches `max_len`; `None` lets it grow unbounded.
+    overflow_policy: Option<OverflowPolicy>,
+    /// Emptied group buffers kept by `recycle` for reuse by the next new groups.
+    spare: Vec<VecDeque<&'a V>>,
+    scheduler: S,
 }
 
-impl<'a, V: FairGroup> FairQueue<'a, V> {
+/// What an insert does with an item of an unseen group once the queue already tracks the
+/// maximum number of groups given to [`FairQueue::with_group_overflow`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupOverflow {
+    /// Rejects the item: `try_insert` returns a [`CapacityError`] and `insert` drops it.
+    /// The default.
+    #[default]
+    Reject,
+    /// Appends the item to the most recently created group still queued, which from then on
+    /// holds items of several groups and is served as one. No item is dropped, so `len`
+    /// still counts every inserted item.
+    MergeIntoNewest,
+}
+
+/// What `insert` does with a new item once the queue holds the capacity given to
+/// [`FairQueue::with_capacity_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discards the item the next `pop` would return, then inserts the new one. The dropped
+    /// item is the oldest of the group under the rotation, not the oldest in the whole queue,
+    /// and the rotation advances past that group as after a real pop.
+    DropOldest,
+    /// Discards the incoming item and leaves the queue untouched.
+    DropNewest,
+}
+
+/// Fair queue over `'static` data such as interned or leaked events.
+/// Carries no lifetime parameter, so it embeds cleanly in long-lived structs.
+///
+/// ```
+/// use fairqueue::{FairGroup, StaticFairQueue};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     user_id: &'static str,
+///     value: u32,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.user_id == other.user_id
+///     }
+/// }
+///
+/// static EVENTS: [Event; 3] = [
+///     Event { user_id: "alice", value: 1 },
+///     Event { user_id: "alice", value: 2 },
+///     Event { user_id: "bob", value: 10 },
+/// ];
+///
+/// struct Dispatcher {
+///     pending: StaticFairQueue<Event>,
+/// }
+///
+/// let mut dispatcher = Dispatcher {
+///     pending: StaticFairQueue::from_slice(&EVENTS),
+/// };
+///
+/// assert_eq!(dispatcher.pending.pop(), Some(&EVENTS[0]));
+/// assert_eq!(dispatcher.pending.pop(), Some(&EVENTS[2]));
+/// assert_eq!(dispatcher.pending.pop(), Some(&EVENTS[1]));
+/// ```
+pub type StaticFairQueue<V> = FairQueue<'static, V>;
+
+impl<'a, V> FairQueue<'a, V> {
+    /// Creates a queue that groups items by the key `key_fn` extracts, for types that
+    /// do not implement `FairGroup`, such as foreign types. See [`KeyedFairQueue`].
+    #[must_use]
+    pub fn new_by<K: Eq, F: Fn(&V) -> K>(key_fn: F) -> KeyedFairQueue<'a, V, K, F> {
+        KeyedFairQueue::new(key_fn)
+    }
+
+    /// Same as [`new_by`](Self::new_by).
+    #[must_use]
+    pub fn keyed_by<K: Eq, F: Fn(&V) -> K>(key_fn: F) -> KeyedFairQueue<'a, V, K, F> {
+        Self::new_by(key_fn)
+    }
+
+    /// Creates a closure-keyed queue that finds groups through a hash index instead of
+    /// scanning their keys. See [`HashedFairQueue`].
+    #[must_use]
+    pub fn hashed_by<K: Eq + Hash + Clone, F: Fn(&V) -> K>(
+        key_fn: F,
+    ) -> HashedFairQueue<'a, V, K, F> {
+        HashedFairQueue::new(key_fn)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, V: crate::FairKey> FairQueue<'a, V> {
+    /// Creates a queue that locates groups through a `FairKey` index instead of a linear
+    /// scan (requires the `std` feature). See [`IndexedFairQueue`](crate::IndexedFairQueue).
+    #[must_use]
+    pub fn new_indexed() -> crate::IndexedFairQueue<'a, V> {
+        crate::IndexedFairQueue::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<V: crate::FairKey, S: Scheduler> FairQueue<'_, V, S> {
+    /// Iterates over the key of every group holding items, in the order of
+    /// `group_heads` (requires the `std` feature). For types without a `FairKey`,
+    /// `group_heads` yields one head reference per group instead.
+    pub fn group_keys(&self) -> impl Iterator<Item = V::Key> + '_ {
+        self.group_heads().map(crate::FairKey::group_key)
+    }
+}
+
+impl<V: ?Sized> FairQueue<'_, V> {
     #[must_use]
     pub fn new() -> Self {
+        Self::with_scheduler(RoundRobin)
+    }
+}
+
+impl<'a, V: FairGroup + ?Sized> FairQueue<'a, V> {
+    /// Creates a queue with room for `groups` groups before the group list reallocates.
+    /// The hint counts groups, not items.
+    #[must_use]
+    pub fn with_capacity(groups: usize) -> Self {
+        Self::with_group_capacity(groups, 0)
+    }
+
+    /// Creates a queue with room for `groups` groups, where every group created later
+    /// reserves space for `per_group` items up front.
+    #[must_use]
+    pub fn with_group_capacity(groups: usize, per_group: usize) -> Self {
         Self {
-            groups: Vec::new(),
-            pointer: 0,
-            len: 0,
+            groups: Vec::with_capacity(groups),
+            ids: Vec::with_capacity(groups),
+            rotation: Rotation::with_capacity(groups),
+            group_capacity: per_group,
+            ..Self::with_scheduler(RoundRobin)
         }
     }
 
-    /// Inserts a new item into the queue, ensuring spatial distancing between items of the same group.
-    pub fn insert(&mut self, value: &'a V) {
-        if let Some(group) = self.groups.iter_mut().find(|group| {
-            group
-                .front()
-                .is_some_and(|v| ptr::eq(*v, value) || (*v).is_same_group(value))
-        }) {
-            group.push_back(value);
-        } else {
-            let mut new_group = VecDeque::new();
-            new_group.push_back(value);
-            self.groups.push(new_group);
+    /// Rebuilds a queue from groups in rotation order and the position of the pointer, e.g.
+    /// as split by `into_groups`. Empty groups are skipped; an out-of-range pointer restarts
+    /// at the first group.
+    pub(crate) fn from_groups(groups: Vec<VecDeque<&'a V>>, pointer: usize) -> Self {
+        let mut queue = Self::with_capacity(groups.len());
+        for group in groups.into_iter().filter(|group| !group.is_empty()) {
+            queue.len += group.len();
+            queue.push_group(group);
         }
-        self.len += 1;
+        if pointer < queue.groups.len() {
+            queue.pointer = pointer;
+        }
+        queue
     }
 
-    /// Retrieves the next item in the queue (FIFO) while maintaining spatial distancing.
-    #[inline(always)]
-    pub fn pop(&mut self) -> Option<&'a V> {
-        if self.len == 0 {
-            return None;
+    /// Creates a queue whose `try_insert` rejects items once `cap` items are queued.
+    #[must_use]
+    pub fn with_max_len(cap: usize) -> Self {
+        Self::with_limits(cap, usize::MAX)
+    }
+
+    /// Creates a queue whose `try_insert` rejects items for a group already holding `cap` items.
+    #[must_use]
+    pub fn with_max_per_group(cap: usize) -> Self {
+        Self::with_limits(usize::MAX, cap)
+    }
+
+    /// Creates a queue bounding both the total backlog and each group's backlog for `try_insert`.
+    /// `insert` ignores the bounds, so mixing both calls can exceed them.
+    #[must_use]
+    pub fn with_limits(max_len: usize, max_per_group: usize) -> Self {
+        Self {
+            max_len,
+            max_per_group,
+            ..Self::new()
         }
+    }
 
-        loop {
-            if self.groups.is_empty() {
-                self.pointer = 0;
-                return None;
-            }
+    /// Creates a queue that rejects items which would open a group beyond the first
+    /// `max_groups`. Items joining an existing group are still accepted.
+    #[must_use]
+    pub fn with_max_groups(max_groups: usize) -> Self {
+        Self::with_group_overflow(max_groups, GroupOverflow::Reject)
+    }
 
-            if self.pointer >= self.groups.len() {
-                self.pointer = 0;
-            }
+    /// Creates a queue that tracks at most `max_groups` groups, applying `overflow` to items
+    /// of unseen groups once that many exist. Unlike the limits of
+    /// [`with_limits`](Self::with_limits), the bound holds for every insert, not only
+    /// `try_insert`.
+    #[must_use]
+    pub fn with_group_overflow(max_groups: usize, overflow: GroupOverflow) -> Self {
+        Self {
+            max_groups,
+            group_overflow: overflow,
+            ..Self::new()
+        }
+    }
 
-            let group = &mut self.groups[self.pointer];
+    /// Creates a queue holding at most `cap` items through `insert`, which applies `policy`
+    /// to each item arriving at the cap so `len` never exceeds it. `try_insert` keeps
+    /// rejecting items at the cap whatever the policy. A `cap` of 0 drops every item.
+    ///
+    /// ```
+    /// use fairqueue::{FairGroup, FairQueue, OverflowPolicy};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Sample {
+    ///     sensor: u8,
+    ///     value: u32,
+    /// }
+    ///
+    /// impl FairGroup for Sample {
+    ///     fn is_same_group(&self, other: &Self) -> bool {
+    ///         self.sensor == other.sensor
+    ///     }
+    /// }
+    ///
+    /// let samples: Vec<Sample> = (0..4).map(|value| Sample { sensor: 0, value }).collect();
+    /// let mut queue = FairQueue::with_capacity_policy(2, OverflowPolicy::DropOldest);
+    /// for sample in &samples {
+    ///     queue.insert(sample);
+    /// }
+    ///
+    /// assert_eq!(queue.len(), 2);
+    /// assert_eq!(queue.pop(), Some(&samples[2]));
+    /// assert_eq!(queue.pop(), Some(&samples[3]));
+    /// ```
+    #[must_use]
+    pub fn with_capacity_policy(cap: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            max_len: cap,
+            overflow_policy: Some(policy),
+            ..Self::new()
+        }
+    }
 
-            if let Some(item) = group.pop_front() {
-                self.len -= 1;
+    /// Creates a queue whose `pop`, with `burst` set, serves the group under the pointer until
+    /// it empties and only then rotates to the next group, instead of rotating after every
+    /// item. Groups still take turns in round-robin order, but each turn now drains a whole
+    /// group, so spacing holds per burst rather than per item: consecutive pops repeat a
+    /// group whenever it has more than one item, and `check_fairness` reports exactly that.
+    /// Items joining the group being drained are served within the same burst. `pop_round`
+    /// ignores the setting and still takes one item per group. With `burst` unset this is
+    /// [`new`](Self::new).
+    ///
+    /// ```
+    /// use fairqueue::{FairGroup, FairQueue};
+    ///
+    /// struct Job(char, u32);
+    ///
+    /// impl FairGroup for Job {
+    ///     fn is_same_group(&self, other: &Self) -> bool {
+    ///         self.0 == other.0
+    ///     }
+    /// }
+    ///
+    /// let jobs = [Job('a', 1), Job('b', 2), Job('a', 3), Job('b', 4)];
+    /// let mut queue = FairQueue::with_burst(true);
+    /// jobs.iter().for_each(|job| queue.insert(job));
+    ///
+    /// let order: Vec<u32> = std::iter::from_fn(|| queue.pop()).map(|job| job.1).collect();
+    /// assert_eq!(order, vec![1, 3, 2, 4]);
+    /// ```
+    #[must_use]
+    pub fn with_burst(burst: bool) -> Self {
+        Self {
+            burst,
+            ..Self::new()
+        }
+    }
 
-                if group.is_empty() {
-                    self.groups.swap_remove(self.pointer);
-                    if self.groups.is_empty() || self.pointer >= self.groups.len() {
-                        self.pointer = 0;
-                    }
-                } else if !self.groups.is_empty() {
-                    self.pointer = (self.pointer + 1) % self.groups.len();
-                }
+    /// Creates a queue whose first pop starts at group `pointer` instead of the first group,
+    /// e.g. to vary the starting group across many instances. Until the rotation starts, the
+    /// pointer follows `pointer` clamped to the groups present so far. Only the initial phase
+    /// changes: `pop` still rotates one group per turn, so the spacing guarantees hold
+    /// unchanged.
+    #[must_use]
+    pub fn with_start_pointer(pointer: usize) -> Self {
+        Self {
+            start_pointer: Some(pointer),
+            ..Self::new()
+        }
+    }
 
-                return Some(item);
-            }
+    /// Reserves room for at least `additional_groups` more groups without inserting anything.
+    pub fn reserve(&mut self, additional_groups: usize) {
+        self.groups.reserve(additional_groups);
+        self.ids.reserve(additional_groups);
+        self.rotation.reserve(additional_groups);
+    }
+}
 
-            self.groups.swap_remove(self.pointer);
-            if self.groups.is_empty() {
-                self.pointer = 0;
-                return None;
-            }
-            if self.pointer >= self.groups.len() {
-                self.pointer = 0;
+impl<'a, V: FairGroup> FairQueue<'a, V> {
+    /// Creates a queue whose groups pop their smallest item according to `cmp` rather than
+    /// the oldest, keeping the rotation across groups. See [`OrderedFairQueue`].
+    #[must_use]
+    pub fn new_ordered_by<F: Fn(&V, &V) -> core::cmp::Ordering>(
+        cmp: F,
+    ) -> OrderedFairQueue<'a, V, F> {
+        OrderedFairQueue::new(cmp)
+    }
+
+    /// Builds a queue holding every item of `items`, inserted in slice order.
+    #[must_use]
+    pub fn from_slice(items: &'a [V]) -> Self {
+        let mut queue = Self::new();
+        for item in items {
+            queue.insert(item);
+        }
+        queue
+    }
+
+    /// Builds a queue from a slice already sorted (or at least grouped) by `key` in a single
+    /// pass: each run of equal keys becomes one group, with no scanning of existing groups.
+    /// `key` must agree with `is_same_group`. A key that reappears after a different one
+    /// opens a second slot for the same group; [`compact`](Self::compact) repairs that.
+    #[must_use]
+    pub fn from_sorted_by_key<K: Eq, F: Fn(&V) -> K>(items: &'a [V], key: F) -> Self {
+        let mut queue = Self::new();
+        let mut run = VecDeque::new();
+        let mut run_key = None;
+        for item in items {
+            let item_key = key(item);
+            if run_key.as_ref().is_some_and(|run_key| *run_key != item_key) {
+                queue.push_group(mem::take(&mut run));
             }
+            run.push_back(item);
+            run_key = Some(item_key);
+        }
+        if !run.is_empty() {
+            queue.push_group(run);
+        }
+        queue.len = items.len();
+        queue
+    }
+
+    /// Builds a queue from pre-bucketed items (requires the `std` feature): every non-empty
+    /// bucket becomes one group, keeping its order, and the pointer starts at the first slot.
+    /// Buckets are taken in the map's iteration order, which therefore decides the initial
+    /// rotation order and varies between maps. Each bucket should hold exactly one group, as
+    /// `FairGroup` sees it; buckets are not merged or compared.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn from_hashmap<K: Eq + core::hash::Hash>(
+        map: std::collections::HashMap<K, VecDeque<&'a V>>,
+    ) -> Self {
+        let mut queue = Self::with_capacity(map.len());
+        for bucket in map.into_values().filter(|bucket| !bucket.is_empty()) {
+            queue.len += bucket.len();
+            queue.push_group(bucket);
+        }
+        queue
+    }
+}
+
+impl<'a, V: FairGroup + ?Sized> FairQueue<'a, V, Strategy> {
+    /// Creates an empty queue driven by one of the built-in [`Strategy`] values, which can
+    /// be picked at runtime. `Strategy::RoundRobin` behaves exactly like `new`.
+    #[must_use]
+    pub fn with_strategy(strategy: Strategy) -> Self {
+        Self::with_scheduler(strategy)
+    }
+}
+
+impl<'a, V: ?Sized, S: Scheduler> FairQueue<'a, V, S> {
+    /// Creates an empty queue that lets `scheduler` pick the group each `pop` serves.
+    ///
+    /// ```
+    /// use fairqueue::{FairGroup, FairQueue, LongestQueueFirst};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Event {
+    ///     user_id: &'static str,
+    ///     value: u32,
+    /// }
+    ///
+    /// impl FairGroup for Event {
+    ///     fn is_same_group(&self, other: &Self) -> bool {
+    ///         self.user_id == other.user_id
+    ///     }
+    /// }
+    ///
+    /// let b1 = Event { user_id: "bob", value: 1 };
+    /// let a1 = Event { user_id: "alice", value: 1 };
+    /// let a2 = Event { user_id: "alice", value: 2 };
+    ///
+    /// let mut queue = FairQueue::with_scheduler(LongestQueueFirst);
+    /// queue.insert(&b1);
+    /// queue.insert(&a1);
+    /// queue.insert(&a2);
+    ///
+    /// assert_eq!(queue.pop(), Some(&a1));
+    /// assert_eq!(queue.pop(), Some(&b1));
+    /// assert_eq!(queue.pop(), Some(&a2));
+    /// ```
+    #[must_use]
+    pub fn with_scheduler(scheduler: S) -> Self {
+        Self {
+            groups: Vec::new(),
+            ids: Vec::new(),
+            rotation: Rotation::new(),
+            next_id: 0,
+            pointer: 0,
+            start_pointer: None,
+            len: 0,
+            last_served: None,
+            group_capacity: 0,
+            burst: false,
+            max_len: usize::MAX,
+            max_per_group: usize::MAX,
+            max_groups: usize::MAX,
+            group_overflow: GroupOverflow::Reject,
+            overflow_policy: None,
+            spare: Vec::new(),
+            scheduler,
         }
     }
 
+    /// Returns the scheduler choosing the served group.
+    #[inline(always)]
+    #[must_use]
+    pub fn scheduler(&self) -> &S {
+        &self.scheduler
+    }
+
+    /// Retrieves the next item in the queue (FIFO) while maintaining spatial distancing.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<&'a V> {
+        self.pop_scheduled(false).map(|(item, _)| item)
+    }
+
+    /// Pops like `pop` and also returns the position the served group held at that moment,
+    /// counted in the order of `group_heads`. The position is a point-in-time index, not a
+    /// stable id: once a group empties, the groups behind it move up by one. Use [`GroupId`]
+    /// for durable handles. O(group_count) to find the position.
+    pub fn pop_with_group_index(&mut self) -> Option<(&'a V, usize)> {
+        let position = self.rotation.position(self.next_slot()?);
+        let (item, _) = self.pop_scheduled(false)?;
+        Some((item, position))
+    }
+
     /// Peeks at the next item in the queue without removing it.
     #[inline(always)]
     #[must_use]
     pub fn peek(&self) -> Option<&'a V> {
-        if self.groups.is_empty() {
-            return None;
-        }
+        self.groups[self.next_slot()?].front().copied()
+    }
+
+    /// Pops like `pop` and returns a clone of the item, for cheap `Clone` types that are
+    /// queued by reference.
+    pub fn pop_cloned(&mut self) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.pop().cloned()
+    }
 
-        self.groups.get(self.pointer)?.front().copied()
+    /// Returns a clone of the item the next `pop` would return, without removing it.
+    #[must_use]
+    pub fn peek_cloned(&self) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.peek().cloned()
     }
 
     /// Returns the number of enqueued items.
@@ -145,283 +572,5117 @@ impl<'a, V: FairGroup> FairQueue<'a, V> {
         self.groups.len()
     }
 
-    /// Iterates over the current head item of each group without consuming them.
-    #[inline(always)]
-    #[must_use]
-    pub fn group_heads(&self) -> QueueGroupHeads<'_, 'a, V> {
-        QueueGroupHeads {
-            iter: self.groups.iter(),
+    /// Clears all items and resets the round-robin pointer, freeing the group buffers.
+    /// Ids handed out earlier stay invalid; new groups get fresh ids.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.ids.clear();
+        self.rotation.clear();
+        self.spare.clear();
+        self.pointer = 0;
+        self.start_pointer = None;
+        self.len = 0;
+        self.last_served = None;
+    }
+
+    /// Empties the queue like [`clear`](Self::clear) while keeping every allocation for the
+    /// next epoch. `clear` frees the buffer of each group; `recycle` empties those buffers and
+    /// parks them, and the groups created afterwards take them over before allocating. A
+    /// batch loop that refills the queue with a similar set of groups each round therefore
+    /// stops allocating after the first round.
+    ///
+    /// ```
+    /// use fairqueue::{FairGroup, FairQueue};
+    ///
+    /// struct Job(u8);
+    ///
+    /// impl FairGroup for Job {
+    ///     fn is_same_group(&self, other: &Self) -> bool {
+    ///         self.0 == other.0
+    ///     }
+    /// }
+    ///
+    /// let jobs = [Job(1), Job(2), Job(1)];
+    /// let mut queue = FairQueue::new();
+    /// for round in 0..3 {
+    ///     jobs.iter().for_each(|job| queue.insert(job));
+    ///     assert_eq!(queue.len(), 3);
+    ///     queue.recycle();
+    ///     assert!(queue.is_empty());
+    /// }
+    /// ```
+    pub fn recycle(&mut self) {
+        for mut group in self.groups.drain(..) {
+            group.clear();
+            self.spare.push(group);
         }
+        self.ids.clear();
+        self.rotation.clear();
+        self.pointer = 0;
+        self.start_pointer = None;
+        self.len = 0;
+        self.last_served = None;
     }
 
-    /// Collects group heads into a vector (requires the `std` feature).
-    #[cfg(feature = "std")]
-    #[must_use]
-    pub fn group_heads_vec(&self) -> std::vec::Vec<&'a V> {
-        self.group_heads().collect()
+    /// Hands out an id no group has used yet.
+    fn fresh_id(&mut self) -> GroupId {
+        let id = GroupId(self.next_id);
+        self.next_id += 1;
+        id
     }
 
-    /// Clears all items and resets the round-robin pointer.
-    pub fn clear(&mut self) {
-        self.groups.clear();
-        self.pointer = 0;
-        self.len = 0;
+    /// Finds the slot of the group `id` refers to.
+    fn slot_of_id(&self, id: GroupId) -> Option<usize> {
+        self.ids.iter().position(|&held| held == id)
     }
-}
 
-impl<V: FairGroup> Default for FairQueue<'_, V> {
-    fn default() -> Self {
-        Self::new()
+    /// Adds a group at the end of the rotation under a fresh id.
+    fn push_group(&mut self, group: VecDeque<&'a V>) -> GroupId {
+        let id = self.fresh_id();
+        self.groups.push(group);
+        self.ids.push(id);
+        self.rotation.push();
+        if let Some(start) = self.start_pointer {
+            self.pointer = self.rotation.slot_at(start.min(self.groups.len() - 1));
+        }
+        id
     }
-}
 
-/// Iterator over the first element of each group.
-pub struct QueueGroupHeads<'queue, 'value, V: FairGroup> {
-    iter: slice::Iter<'queue, VecDeque<&'value V>>,
-}
+    /// Opens a group holding `value`, reusing a buffer left by `recycle` when there is one.
+    fn push_new_group(&mut self, value: &'a V) -> GroupId {
+        let mut group = self
+            .spare
+            .pop()
+            .unwrap_or_else(|| VecDeque::with_capacity(self.group_capacity));
+        group.push_back(value);
+        self.push_group(group)
+    }
 
-impl<'queue, 'value, V: FairGroup> Iterator for QueueGroupHeads<'queue, 'value, V> {
-    type Item = &'value V;
+    /// Stores `value` at the back, or with `front` at the head, of the group in `slot`, or
+    /// opens a new group when `slot` is `None`. Every single-item insert ends here, so the
+    /// limits apply first. A new group beyond `max_groups` is rejected or merged into the
+    /// newest group per [`GroupOverflow`]. Then the [`OverflowPolicy`] of a full queue
+    /// applies: `DropNewest` drops `value`, while `DropOldest` pops the next item in fair
+    /// order and looks the group up again, since that pop may have emptied it. Returns the
+    /// id of the group `value` joined, or `None` when it was dropped.
+    fn push_item(&mut self, mut slot: Option<usize>, value: &'a V, front: bool) -> Option<GroupId> {
+        if slot.is_none() && self.groups.len() >= self.max_groups {
+            let merged = match self.group_overflow {
+                GroupOverflow::Reject => None,
+                GroupOverflow::MergeIntoNewest => self.newest_slot(),
+            };
+            slot = Some(merged?);
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        for group in &mut self.iter {
-            if let Some(item) = group.front() {
-                return Some(*item);
+        if let Some(policy) = self.overflow_policy
+            && self.len >= self.max_len
+        {
+            let id = slot.map(|slot| self.ids[slot]);
+            if policy == OverflowPolicy::DropNewest || self.pop().is_none() {
+                return None;
             }
+            slot = id.and_then(|id| self.slot_of_id(id));
+        }
+
+        self.len += 1;
+        let Some(slot) = slot else {
+            return Some(self.push_new_group(value));
+        };
+        if front {
+            self.groups[slot].push_front(value);
+        } else {
+            self.groups[slot].push_back(value);
         }
-        None
+        Some(self.ids[slot])
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use proptest::prelude::*;
+    /// Finds the slot of the most recently created group.
+    fn newest_slot(&self) -> Option<usize> {
+        (0..self.ids.len()).max_by_key(|&slot| self.ids[slot])
+    }
 
-    #[derive(Debug, PartialEq)]
-    struct Event {
-        timestamp: u32,
-        user_id: &'static str,
-        group: usize,
+    /// Returns the slot the next pop serves, or `None` when there are no groups.
+    fn next_slot(&self) -> Option<usize> {
+        if self.groups.is_empty() || self.pointer >= self.groups.len() {
+            return None;
+        }
+        Some(self.scheduled_slot())
     }
 
-    impl FairGroup for Event {
-        fn is_same_group(&self, other: &Self) -> bool {
-            self.group == other.group
+    /// Asks the scheduler for the slot the next pop serves.
+    /// Requires at least one group and the pointer to address one of them.
+    fn scheduled_slot(&self) -> usize {
+        let slot = self
+            .scheduler
+            .next_slot(self.pointer, self.last_served, &self.groups);
+        debug_assert!(
+            slot < self.groups.len(),
+            "scheduler returned slot {slot} out of range"
+        );
+        if slot < self.groups.len() {
+            slot
+        } else {
+            self.pointer
         }
     }
 
-    #[test]
-    fn test_spaced_fairness() {
-        let event1 = Event {
-            timestamp: 1,
-            user_id: "user1",
-            group: 0,
-        };
-        let event2 = Event {
-            timestamp: 2,
-            user_id: "user2",
-            group: 1,
-        };
-        let event3 = Event {
-            timestamp: 3,
-            user_id: "user1",
-            group: 0,
+    /// Pops the front, or with `back` the back, of the group the scheduler picks, and returns
+    /// it with the slot of that group.
+    fn pop_scheduled(&mut self, back: bool) -> Option<(&'a V, usize)> {
+        if self.len == 0 {
+            return None;
+        }
+
+        loop {
+            if self.groups.is_empty() {
+                self.pointer = 0;
+                return None;
+            }
+
+            if self.pointer >= self.groups.len() {
+                self.pointer = 0;
+            }
+
+            let slot = self.scheduled_slot();
+            self.pointer = slot;
+            if let Some(item) = self.pop_pointed_end(back) {
+                return Some((item, slot));
+            }
+        }
+    }
+
+    /// Pops the head of the group under the pointer and advances the rotation. A group found
+    /// empty there is dropped instead and `None` is returned, leaving `pop` to try again.
+    fn pop_pointed(&mut self) -> Option<&'a V> {
+        self.pop_pointed_end(false)
+    }
+
+    /// Pops the front, or with `back` the back, of the group under the pointer and rotates.
+    fn pop_pointed_end(&mut self, back: bool) -> Option<&'a V> {
+        self.start_pointer = None;
+        let mut slots = PointedEnd {
+            groups: &mut self.groups,
+            ids: &mut self.ids,
+            back,
         };
-        let event4 = Event {
-            timestamp: 4,
-            user_id: "user3",
-            group: 2,
+        let popped = rotation::pop_ordered(
+            &mut slots,
+            &mut self.rotation,
+            &mut self.pointer,
+            self.burst,
+        );
+        let Some(popped) = popped else {
+            self.last_served = None;
+            return None;
         };
-        let event5 = Event {
-            timestamp: 5,
-            user_id: "user2",
-            group: 1,
+        // Only reached after an item actually left a group, so `len` is at least 1.
+        debug_assert!(self.len > 0, "len out of sync with the groups");
+        self.len -= 1;
+        self.scheduler.served(popped.slot);
+        self.last_served = popped.kept.then_some(popped.slot);
+        Some(popped.item)
+    }
+}
+
+/// Groups of a [`FairQueue`] with their ids, served from one end.
+struct PointedEnd<'q, 'a, V: ?Sized> {
+    groups: &'q mut Vec<VecDeque<&'a V>>,
+    ids: &'q mut Vec<GroupId>,
+    back: bool,
+}
+
+impl<'a, V: ?Sized> Slots for PointedEnd<'_, 'a, V> {
+    type Item = &'a V;
+
+    fn count(&self) -> usize {
+        self.groups.len()
+    }
+
+    fn take(&mut self, slot: usize) -> Option<&'a V> {
+        let group = &mut self.groups[slot];
+        if self.back {
+            group.pop_back()
+        } else {
+            group.pop_front()
+        }
+    }
+
+    fn is_drained(&self, slot: usize) -> bool {
+        self.groups[slot].is_empty()
+    }
+
+    fn remove(&mut self, slot: usize) {
+        self.groups.swap_remove(slot);
+        self.ids.swap_remove(slot);
+    }
+}
+
+impl<'a, V: TryFairGroup + ?Sized, S: Scheduler> FairQueue<'a, V, S> {
+    /// Inserts an item like `insert`, grouping it through `try_is_same_group`. The first
+    /// failing comparison is returned and the queue is left untouched. Types implementing
+    /// only `TryFairGroup` can be queued this way and consumed with `pop`, `peek` and the
+    /// other methods that never compare items; `group_tag` is not consulted.
+    pub fn try_insert_grouped(&mut self, value: &'a V) -> Result<(), GroupError> {
+        let mut found = None;
+        for (slot, group) in self.groups.iter().enumerate() {
+            let Some(&head) = group.front() else {
+                continue;
+            };
+            if ptr::eq(head, value) || head.try_is_same_group(value)? {
+                found = Some(slot);
+                break;
+            }
+        }
+
+        self.push_item(found, value, false);
+        Ok(())
+    }
+}
+
+impl<'a, V: FairGroup + ?Sized, S: Scheduler> FairQueue<'a, V, S> {
+    /// Inserts a new item into the queue, ensuring spatial distancing between items of the
+    /// same group.
+    /// Groups are matched by `group_tag` when both values report one, otherwise
+    /// by pointer identity or `is_same_group`.
+    ///
+    /// On a queue built with [`with_capacity_policy`](Self::with_capacity_policy) that is
+    /// full, the [`OverflowPolicy`] decides which item is dropped to stay at the cap. On one
+    /// built with [`with_group_overflow`](Self::with_group_overflow), the [`GroupOverflow`]
+    /// decides what happens to an item that would open one group too many.
+    pub fn insert(&mut self, value: &'a V) {
+        self.insert_with_id(value);
+    }
+
+    /// Inserts like `insert`, but returns the item in a `CapacityError` instead of wrapping
+    /// the length counter. Each queued item occupies a pointer-sized slot, so the counter
+    /// cannot realistically overflow; this makes the guarantee explicit for 32-bit targets.
+    pub fn checked_insert(&mut self, value: &'a V) -> Result<(), CapacityError<&'a V>> {
+        if self.len == usize::MAX {
+            return Err(CapacityError::new(value));
+        }
+        self.insert(value);
+        Ok(())
+    }
+
+    /// Inserts like `insert`, but first checks the item against every member of the group
+    /// whose head it matches, not just the head. Returns `QueueError::NonTransitiveGroup`
+    /// and leaves the queue untouched when any member disagrees, which means the
+    /// `FairGroup` relation is not transitive. O(group length) per insert.
+    pub fn try_insert_strict(&mut self, value: &'a V) -> Result<(), QueueError> {
+        let tag = value.group_tag();
+        if let Some(slot) = self.slot_of(value) {
+            let group = &mut self.groups[slot];
+            if !group.iter().all(|member| same_group(*member, value, tag)) {
+                return Err(QueueError::NonTransitiveGroup);
+            }
+            self.push_item(Some(slot), value, false);
+        } else {
+            self.insert(value);
+        }
+        Ok(())
+    }
+
+    /// Inserts like `insert` and returns true when the item opened a new group,
+    /// false when it joined an existing one or a limit dropped it.
+    pub fn insert_tracked(&mut self, value: &'a V) -> bool {
+        let slot = self.slot_of(value);
+        let first_new = GroupId(self.next_id);
+        self.push_item(slot, value, false)
+            .is_some_and(|id| id >= first_new)
+    }
+
+    /// Inserts like `insert` and returns the [`GroupId`] of the group the item joined.
+    /// The id stays valid while the group holds items, however other groups move. When a
+    /// limit drops the item, the id of its group is returned if that group holds items, and
+    /// otherwise an id that matches no group.
+    pub fn insert_with_id(&mut self, value: &'a V) -> GroupId {
+        let slot = self.slot_of(value);
+        match self.push_item(slot, value, false) {
+            Some(id) => id,
+            None => match slot {
+                Some(slot) => self.ids[slot],
+                None => self.fresh_id(),
+            },
+        }
+    }
+
+    /// Inserts an item at the head of its group, so it is served the next time the rotation
+    /// reaches that group. A new group is added at the end of the rotation; the pointer is kept.
+    pub fn insert_front(&mut self, value: &'a V) {
+        let slot = self.slot_of(value);
+        self.push_item(slot, value, true);
+    }
+
+    /// Inserts an item unless that would exceed the bounds given at construction,
+    /// returning the rejected reference inside the error. A rejected insert changes nothing.
+    pub fn try_insert(&mut self, value: &'a V) -> Result<(), CapacityError<&'a V>> {
+        if self.len >= self.max_len {
+            return Err(CapacityError::new(value));
+        }
+
+        let tag = value.group_tag();
+        let mut slot = self.groups.iter().position(|group| {
+            group
+                .front()
+                .is_some_and(|head| same_group(*head, value, tag))
+        });
+        if slot.is_none() && self.groups.len() >= self.max_groups {
+            slot = match self.group_overflow {
+                GroupOverflow::Reject => None,
+                GroupOverflow::MergeIntoNewest => self.newest_slot(),
+            };
+            if slot.is_none() {
+                return Err(CapacityError::new(value));
+            }
+        }
+
+        if let Some(slot) = slot {
+            let group = &mut self.groups[slot];
+            if group.len() >= self.max_per_group {
+                return Err(CapacityError::new(value));
+            }
+            group.push_back(value);
+        } else {
+            if self.max_per_group == 0 {
+                return Err(CapacityError::new(value));
+            }
+            self.push_new_group(value);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Moves every item of `other` into `self`, leaving `other` empty.
+    /// Groups are concatenated rather than interleaved: each group of `other` is appended
+    /// behind the matching group of `self`, or added as a new group after the existing ones,
+    /// so every group keeps the FIFO order of both sources. The pointer of `self` is kept.
+    /// When the items would overflow a queue built with
+    /// [`with_capacity_policy`](Self::with_capacity_policy), or a group would open beyond
+    /// the bound of [`with_group_overflow`](Self::with_group_overflow), they are inserted
+    /// one by one in that same order, so the [`OverflowPolicy`] or [`GroupOverflow`]
+    /// applies to each.
+    pub fn append(&mut self, other: &mut FairQueue<'a, V, S>) {
+        let order: Vec<usize> = other.rotation.iter().collect();
+        for slot in order {
+            let incoming = mem::take(&mut other.groups[slot]);
+            let Some(&head) = incoming.front() else {
+                continue;
+            };
+            let slot = self.slot_of(head);
+            let overflows =
+                self.overflow_policy.is_some() && self.len + incoming.len() > self.max_len;
+            if overflows || (slot.is_none() && self.groups.len() >= self.max_groups) {
+                for item in incoming {
+                    let slot = self.slot_of(item);
+                    self.push_item(slot, item, false);
+                }
+                continue;
+            }
+            self.len += incoming.len();
+            match slot {
+                Some(slot) => self.groups[slot].extend(incoming),
+                None => {
+                    self.push_group(incoming);
+                }
+            }
+        }
+        other.clear();
+    }
+
+    /// Pops every item of `self` in fair order and inserts it into `dst`, leaving `self`
+    /// empty for reuse with its group list capacity retained. Unlike `append`, which
+    /// concatenates groups, `dst` receives the items in `self`'s dispatch order.
+    /// O(len * group_count) for the inserts.
+    pub fn drain_into<T: Scheduler>(&mut self, dst: &mut FairQueue<'a, V, T>) {
+        while let Some(item) = self.pop() {
+            dst.insert(item);
+        }
+    }
+
+    /// Pops the most recently queued item of the group the next `pop` would serve, e.g. to
+    /// undo a speculative insert, instead of that group's oldest one. It takes the group's
+    /// turn exactly like `pop`: the rotation and scheduler advance the same way, so spacing
+    /// between groups is unaffected, and only the FIFO order inside the group is bypassed.
+    /// The item returned is the newest of the group up next, not of the whole queue.
+    pub fn pop_newest(&mut self) -> Option<&'a V> {
+        self.pop_scheduled(true).map(|(item, _)| item)
+    }
+
+    /// Pops like `pop` and reports whether the item comes from the same group as the item
+    /// popped just before. Under [`RoundRobin`] that only happens when no other group had
+    /// items, so a set flag signals degraded spacing; other schedulers may also repeat a
+    /// group by choice. A group that emptied and was later recreated counts as a new group.
+    pub fn pop_detailed(&mut self) -> Option<PopOutcome<'a, V>> {
+        let previous = self.last_served;
+        let (item, slot) = self.pop_scheduled(false)?;
+        Some(PopOutcome {
+            item,
+            repeated_group: previous == Some(slot),
+        })
+    }
+
+    /// Pops up to `n` items in fair order into `out`, returning how many were popped.
+    /// Equivalent to calling `pop` `n` times, but reuses the caller's buffer.
+    pub fn pop_n(&mut self, n: usize, out: &mut Vec<&'a V>) -> usize {
+        let start = out.len();
+        out.reserve(n.min(self.len));
+        out.extend((0..n).map_while(|_| self.pop()));
+        out.len() - start
+    }
+
+    /// Returns the head of every group in rotation order starting at the pointer: exactly
+    /// the items the next `pop_round` would pop, without consuming them. Like `group_heads`,
+    /// but rotated to begin at the group the pointer addresses.
+    #[must_use]
+    pub fn peek_round(&self) -> Vec<&'a V> {
+        self.round()
+            .filter_map(|slot| self.groups[slot].front().copied())
+            .collect()
+    }
+
+    /// Pops up to `out.len()` items in fair order into `out` and returns how many were
+    /// written; the unused trailing slots are set to `None`. Does not allocate, so a stack
+    /// buffer is enough.
+    pub fn pop_into_slice(&mut self, out: &mut [Option<&'a V>]) -> usize {
+        let mut filled = 0;
+        for slot in out.iter_mut() {
+            *slot = self.pop();
+            filled += usize::from(slot.is_some());
+        }
+        filled
+    }
+
+    /// Pops exactly one item from every non-empty group, in rotation order starting at the
+    /// pointer, and appends them to `out`. Returns the number of items popped, which equals
+    /// the group count before the call. Unlike `pop_n` no group is served twice, whatever
+    /// the scheduler or burst mode; groups that empty during the round are removed and the
+    /// pointer ends up back at the group the round started with.
+    pub fn pop_round(&mut self, out: &mut Vec<&'a V>) -> usize {
+        let count = self.groups.len();
+        if count == 0 {
+            return 0;
+        }
+        if self.pointer >= count {
+            self.pointer = 0;
+        }
+
+        self.start_pointer = None;
+        let before = out.len();
+        out.reserve(count);
+        for slot in self.rotation.walk(self.pointer) {
+            if let Some(item) = self.groups[slot].pop_front() {
+                out.push(item);
+                self.scheduler.served(slot);
+            }
+        }
+        let popped = out.len() - before;
+        self.len -= popped;
+        self.last_served = Some(self.rotation.prev(self.pointer));
+        self.prune_empty_groups();
+        popped
+    }
+
+    /// Consumes the queue round by round: each [`pop_round`](Self::pop_round) batch, one item
+    /// per group, is stably sorted by `key` before it is emitted. Every group still appears
+    /// exactly once per round, but two items of one group may meet at a round boundary,
+    /// so the spacing of plain `pop` is relaxed to once per round.
+    #[must_use]
+    pub fn drain_rounds_by_key<K: Ord, F: Fn(&V) -> K>(mut self, key: F) -> Vec<&'a V> {
+        let mut out = Vec::with_capacity(self.len);
+        let mut start = 0;
+        while self.pop_round(&mut out) > 0 {
+            out[start..].sort_by_key(|value| key(value));
+            start = out.len();
+        }
+        out
+    }
+
+    /// Validates the internal state before popping, returning `QueueError::Inconsistent`
+    /// instead of risking a wrong result when the queue has been corrupted.
+    pub fn checked_pop(&mut self) -> Result<Option<&'a V>, QueueError> {
+        if !self.is_consistent() {
+            return Err(QueueError::Inconsistent);
+        }
+        Ok(self.pop())
+    }
+
+    /// Pops the head of the group matching `sample`, then advances the round-robin pointer
+    /// exactly as `pop` would have if the rotation had reached that group on its own.
+    /// The following `pop` continues with the group after the served one. Returns `None`
+    /// without touching the pointer when no group matches.
+    pub fn pop_from(&mut self, sample: &V) -> Option<&'a V> {
+        self.pointer = self.slot_of(sample)?;
+        self.pop_pointed()
+    }
+
+    /// Pops the head of the group matching `sample` without touching the rotation, e.g. for
+    /// a priority flush of one tenant. This deliberately bypasses round-robin: the pointer,
+    /// the scheduler and the last served group stay as they were, so the served group does
+    /// not lose its regular turn. An emptied group is dropped and the pointer resumes at the
+    /// group it referenced, or the next surviving one. Returns `None` when no group matches.
+    pub fn try_pop_group(&mut self, sample: &V) -> Option<&'a V> {
+        let slot = self.slot_of(sample)?;
+        let item = self.groups[slot].pop_front()?;
+        self.len -= 1;
+        if self.groups[slot].is_empty() {
+            self.prune_empty_groups();
+        }
+        Some(item)
+    }
+
+    /// Points the rotation at the group matching `sample`, so the next `peek` and `pop`
+    /// target it. This deliberately skips or repeats groups in the current pass, breaking
+    /// strict round-robin until the rotation comes around again. Returns false and leaves
+    /// the pointer untouched when no group matches.
+    pub fn rotate_to(&mut self, sample: &V) -> bool {
+        match self.slot_of(sample) {
+            Some(slot) => {
+                self.pointer = slot;
+                self.start_pointer = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the group matching `sample` in front of the group under the pointer. Under
+    /// round-robin its head is served by the next pop, and the other groups keep their
+    /// relative rotation order behind it, so afterwards the promoted group takes one turn per
+    /// round like any other. Unlike `rotate_to`, no group loses its turn in the current pass.
+    /// Returns false when no group matches.
+    pub fn promote_group(&mut self, sample: &V) -> bool {
+        let Some(slot) = self.slot_of(sample) else {
+            return false;
         };
-        let event6 = Event {
-            timestamp: 6,
-            user_id: "user1",
-            group: 0,
+        if self.pointer >= self.groups.len() {
+            self.pointer = 0;
+        }
+
+        self.start_pointer = None;
+        if slot != self.pointer {
+            self.rotation.move_before(slot, self.pointer);
+            self.pointer = slot;
+        }
+        true
+    }
+
+    /// Pops every item in fair order through an iterator.
+    /// The queue is left empty with its pointer reset once the iterator is dropped,
+    /// even if iteration stopped early.
+    pub fn drain(&mut self) -> QueueDrain<'_, 'a, V, S> {
+        QueueDrain { queue: self }
+    }
+
+    /// Detaches the group matching `sample` and yields its items front to back.
+    /// The group is removed immediately; the rotation of the remaining groups is kept,
+    /// resuming at the next surviving group if the pointer referenced the drained one.
+    /// The iterator is empty when no group matches.
+    pub fn drain_group(&mut self, sample: &V) -> QueueDrainGroup<'a, V> {
+        let items = match self.slot_of(sample) {
+            Some(slot) => {
+                let items = mem::take(&mut self.groups[slot]);
+                self.len -= items.len();
+                self.prune_empty_groups();
+                items
+            }
+            None => VecDeque::new(),
         };
-        let event7 = Event {
-            timestamp: 7,
-            user_id: "user1",
-            group: 0,
+        QueueDrainGroup {
+            iter: items.into_iter(),
+        }
+    }
+
+    /// Discards the group matching `sample` and returns how many items it held (0 if none).
+    /// Like `drain_group`, the other groups keep their rotation order.
+    pub fn clear_group(&mut self, sample: &V) -> usize {
+        let Some(slot) = self.slot_of(sample) else {
+            return 0;
         };
-        let event8 = Event {
-            timestamp: 8,
-            user_id: "user3",
-            group: 2,
+        let removed = mem::take(&mut self.groups[slot]).len();
+        self.len -= removed;
+        self.prune_empty_groups();
+        removed
+    }
+
+    /// Pops the head of the group `id` refers to, advancing the rotation like `pop_from`.
+    /// Returns `None` when the group has emptied since the id was handed out.
+    pub fn pop_group_by_id(&mut self, id: GroupId) -> Option<&'a V> {
+        self.pointer = self.slot_of_id(id)?;
+        self.pop_pointed()
+    }
+
+    /// Returns how many items the group `id` refers to holds, or 0 once it has emptied.
+    #[must_use]
+    pub fn len_of_group_by_id(&self, id: GroupId) -> usize {
+        self.slot_of_id(id)
+            .map_or(0, |slot| self.groups[slot].len())
+    }
+
+    /// Discards the group `id` refers to and returns how many items it held (0 if none).
+    /// Behaves like `clear_group`.
+    pub fn clear_group_by_id(&mut self, id: GroupId) -> usize {
+        let Some(slot) = self.slot_of_id(id) else {
+            return 0;
+        };
+        let removed = mem::take(&mut self.groups[slot]).len();
+        self.len -= removed;
+        self.prune_empty_groups();
+        removed
+    }
+
+    /// Borrows the next item for processing and removes it only once the guard is committed.
+    /// Dropping the guard (or calling `rollback`) leaves the item and rotation untouched.
+    pub fn pop_guard(&mut self) -> Option<PopGuard<'_, 'a, V, S>> {
+        let item = self.peek()?;
+        Some(PopGuard { queue: self, item })
+    }
+
+    /// Looks up the group of `sample` once and returns an entry to inspect or extend it,
+    /// like `HashMap::entry`: `Occupied` when the group holds items, `Vacant` otherwise.
+    pub fn group_entry(&mut self, sample: &V) -> GroupEntry<'_, 'a, V, S> {
+        match self.slot_of(sample) {
+            Some(slot) => GroupEntry::Occupied(OccupiedGroupEntry { queue: self, slot }),
+            None => GroupEntry::Vacant(VacantGroupEntry { queue: self }),
+        }
+    }
+
+    /// Returns the head of the group the next `pop` serves, e.g. to prefetch resources for
+    /// that group. The same item as `peek`.
+    #[inline(always)]
+    #[must_use]
+    pub fn next_group_head(&self) -> Option<&'a V> {
+        self.peek()
+    }
+
+    /// Returns how many items the group the next `pop` serves still holds, its head included.
+    #[must_use]
+    pub fn next_group_len(&self) -> Option<usize> {
+        Some(self.groups[self.next_slot()?].len())
+    }
+
+    /// Peeks at the next item for a given group without disturbing rotation.
+    #[must_use]
+    pub fn peek_group(&self, sample: &V) -> Option<&'a V> {
+        self.groups[self.slot_of(sample)?].front().copied()
+    }
+
+    /// Returns the `n`-th item (0 is the head) of the group `sample` belongs to, or `None`
+    /// when no group matches or the group holds `n` items or fewer.
+    #[must_use]
+    pub fn peek_group_nth(&self, sample: &V, n: usize) -> Option<&'a V> {
+        self.groups[self.slot_of(sample)?].get(n).copied()
+    }
+
+    /// Returns true when a group matching `sample` holds items, without fetching its head.
+    #[must_use]
+    pub fn is_group_present(&self, sample: &V) -> bool {
+        self.slot_of(sample).is_some()
+    }
+
+    /// Iterates over every item of the group of `sample` front to back, the order that group
+    /// is served in. Yields nothing when no group matches.
+    pub fn iter_group(&self, sample: &V) -> impl Iterator<Item = &'a V> + '_ {
+        self.slot_of(sample)
+            .into_iter()
+            .flat_map(|slot| self.groups[slot].iter().copied())
+    }
+
+    /// Returns how many items the group of `sample` holds, or 0 when it has none queued.
+    #[must_use]
+    pub fn len_of_group(&self, sample: &V) -> usize {
+        self.slot_of(sample)
+            .map_or(0, |slot| self.groups[slot].len())
+    }
+
+    /// Returns the position in rotation order of the group served by the most recent pop, if
+    /// that group still holds items. Positions are point-in-time indices, not stable
+    /// identifiers. O(group_count).
+    #[must_use]
+    pub fn last_served_group(&self) -> Option<usize> {
+        self.last_served.map(|slot| self.rotation.position(slot))
+    }
+
+    /// Returns false when the next pop would serve the group that was served last
+    /// even though other groups have items waiting, i.e. it would break spacing.
+    #[must_use]
+    pub fn is_fair_now(&self) -> bool {
+        if self.groups.len() < 2 {
+            return true;
+        }
+
+        if self.pointer >= self.groups.len() {
+            return self.last_served != Some(0);
+        }
+        self.last_served != Some(self.scheduled_slot())
+    }
+
+    /// Returns true if any queued item satisfies `f`, stopping at the first match.
+    #[must_use]
+    pub fn contains<F: FnMut(&V) -> bool>(&self, mut f: F) -> bool {
+        self.groups.iter().flatten().any(|value| f(value))
+    }
+
+    /// Returns the number of enqueued items as a `u64`, independent of the target's
+    /// pointer width.
+    #[inline(always)]
+    #[must_use]
+    pub fn len64(&self) -> u64 {
+        self.len as u64
+    }
+
+    /// Returns the round-robin pointer: the position, in the order of `group_heads`, of the
+    /// group the next pop starts from. Positions shift as groups ahead of the pointer empty,
+    /// so a saved pointer is only meaningful for the same groups, e.g. a `Clone` taken at the
+    /// same moment. O(group_count).
+    #[must_use]
+    pub fn pointer(&self) -> usize {
+        if self.pointer < self.groups.len() {
+            self.rotation.position(self.pointer)
+        } else {
+            0
+        }
+    }
+
+    /// Returns the position the pointer addresses and how many items that group holds, or
+    /// `None` when the queue is empty. Reports the pointer itself, not the group a
+    /// non-round-robin scheduler would pick.
+    #[must_use]
+    pub fn group_at_pointer(&self) -> Option<(usize, usize)> {
+        let group = self.groups.get(self.pointer)?;
+        Some((self.pointer(), group.len()))
+    }
+
+    /// Moves the round-robin pointer to position `pointer`, clamped to `0..group_count`.
+    /// Positions follow the current groups; see [`pointer`](Self::pointer).
+    pub fn set_pointer(&mut self, pointer: usize) {
+        self.pointer = self
+            .rotation
+            .slot_at(pointer.min(self.groups.len().saturating_sub(1)));
+        self.start_pointer = None;
+    }
+
+    /// Advances the pointer by `n` groups, modulo `group_count`, without popping: after
+    /// `rotate_left(1)` the next `pop` serves the group after the one it would have served.
+    /// Does nothing on an empty queue.
+    pub fn rotate_left(&mut self, n: usize) {
+        let count = self.groups.len();
+        if count > 0 {
+            let from = self.pointer.min(count - 1);
+            self.pointer = self.rotation.walk(from).nth(n % count).unwrap_or(from);
+            self.start_pointer = None;
+        }
+    }
+
+    /// Moves the pointer back by `n` groups, modulo `group_count`, undoing `rotate_left(n)`.
+    /// Does nothing on an empty queue.
+    pub fn rotate_right(&mut self, n: usize) {
+        let count = self.groups.len();
+        if count > 0 {
+            let from = self.pointer.min(count - 1);
+            self.pointer = self
+                .rotation
+                .walk(from)
+                .nth(count - n % count)
+                .unwrap_or(from);
+            self.start_pointer = None;
+        }
+    }
+
+    /// Iterates over the current head item of each group without consuming them, in the order
+    /// the groups arrived. An emptied group drops out without moving the others.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_heads(&self) -> QueueGroupHeads<'_, 'a, V> {
+        QueueGroupHeads {
+            groups: &self.groups,
+            walk: self.rotation.iter(),
+        }
+    }
+
+    /// Iterates over a read-only view of every group, in the order of
+    /// [`group_heads`](Self::group_heads), without cloning.
+    pub fn groups_view(&self) -> impl ExactSizeIterator<Item = GroupView<'_, 'a, V>> {
+        self.rotation.iter().map(|slot| GroupView {
+            items: &self.groups[slot],
+        })
+    }
+
+    /// Counts the groups whose head item (the front of the group, as in `group_heads`)
+    /// satisfies `f`.
+    #[must_use]
+    pub fn group_count_matching<F: FnMut(&V) -> bool>(&self, mut f: F) -> usize {
+        self.group_heads().filter(|head| f(head)).count()
+    }
+
+    /// Iterates over the item count of each group, in the same order as `group_heads`.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_lens(&self) -> QueueGroupLens<'_, 'a, V> {
+        QueueGroupLens {
+            groups: &self.groups,
+            walk: self.rotation.iter(),
+        }
+    }
+
+    /// Summarises the per-group backlog in one pass over `group_lens`, without allocating.
+    /// All statistics are 0 on an empty queue.
+    #[must_use]
+    pub fn backlog_stats(&self) -> BacklogStats {
+        let mut lens = self.group_lens();
+        let Some(first) = lens.next() else {
+            return BacklogStats::default();
+        };
+        let (min, max) = lens.fold((first, first), |(min, max), len| {
+            (min.min(len), max.max(len))
+        });
+        BacklogStats {
+            group_count: self.groups.len(),
+            min,
+            max,
+            mean: self.len as f64 / self.groups.len() as f64,
+        }
+    }
+
+    /// Returns, for every group in the order of `group_heads`, its creation rank among the
+    /// groups currently queued: 0 for the group created first. The ranks ascend unless
+    /// `promote_group`, `append` or `partition` placed a group out of creation order; sorting
+    /// by rank lists the groups in first-seen order. Ranks are ordinals within one queue,
+    /// only comparable to each other, and restart at 0 after `clear`.
+    /// O(group_count log group_count).
+    #[must_use]
+    pub fn group_order(&self) -> Vec<usize> {
+        let order: Vec<usize> = self.rotation.iter().collect();
+        let mut by_age: Vec<usize> = (0..order.len()).collect();
+        by_age.sort_unstable_by_key(|&position| self.ids[order[position]]);
+        let mut ranks = alloc::vec![0; by_age.len()];
+        for (rank, position) in by_age.into_iter().enumerate() {
+            ranks[position] = rank;
+        }
+        ranks
+    }
+
+    /// Folds every group front to back, starting each from a fresh `init()`, and returns one
+    /// accumulator per group in the order of `group_heads`.
+    pub fn fold_groups<B, F>(&self, init: impl Fn() -> B, mut f: F) -> Vec<B>
+    where
+        F: FnMut(B, &V) -> B,
+    {
+        self.groups_view()
+            .map(|group| group.iter().fold(init(), &mut f))
+            .collect()
+    }
+
+    /// Collects group heads into a vector (requires the `std` feature).
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn group_heads_vec(&self) -> std::vec::Vec<&'a V> {
+        self.group_heads().collect()
+    }
+
+    /// Consumes the queue, returning every item in the exact order `pop` would yield them
+    /// (requires the `std` feature).
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn into_pop_order_vec(self) -> std::vec::Vec<&'a V> {
+        self.into_iter().collect()
+    }
+
+    /// Returns the fraction of queued items that belong to the group of `sample`
+    /// (requires the `std` feature). Yields 0.0 when the group is absent or the queue is empty.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn service_share(&self, sample: &V) -> f64 {
+        if self.len == 0 {
+            return 0.0;
+        }
+
+        self.slot_of(sample)
+            .map_or(0.0, |slot| self.groups[slot].len() as f64 / self.len as f64)
+    }
+
+    /// Tallies every queued item under the key `key` extracts (requires the `std` feature).
+    /// Unlike `group_lens`, the buckets need not match the groups. The counts sum to `len`.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn count_by<K: Eq + core::hash::Hash, F: Fn(&V) -> K>(
+        &self,
+        key: F,
+    ) -> std::collections::HashMap<K, usize> {
+        let mut counts = std::collections::HashMap::new();
+        for item in self.groups.iter().flatten() {
+            *counts.entry(key(item)).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Pops up to `total` items with each group contributing in proportion to its backlog
+    /// (requires the `std` feature). Deeper groups give more items, while the returned batch
+    /// still interleaves groups round-robin starting at the pointer.
+    ///
+    /// Each group's share is `total * group_len / len` rounded down; the items left over
+    /// after rounding go one each to the groups with the largest fractional remainders,
+    /// ties favouring groups earlier in the rotation. A group never contributes more
+    /// items than it holds. The pointer stays on the group it referenced before the call,
+    /// or on the next surviving group if that one emptied.
+    #[cfg(feature = "std")]
+    pub fn pop_proportional(&mut self, total: usize) -> std::vec::Vec<&'a V> {
+        let take = total.min(self.len);
+        let mut batch = std::vec::Vec::with_capacity(take);
+        if take == 0 {
+            return batch;
+        }
+
+        let group_count = self.groups.len();
+        let pointer = if self.pointer < group_count {
+            self.pointer
+        } else {
+            0
         };
+        let rotation = || self.rotation.walk(pointer);
+
+        let share = |slot: usize| take as u128 * self.groups[slot].len() as u128;
+        let mut quotas: std::vec::Vec<usize> = (0..group_count)
+            .map(|slot| (share(slot) / self.len as u128) as usize)
+            .collect();
+
+        let mut leftover = take - quotas.iter().sum::<usize>();
+        let mut by_remainder: std::vec::Vec<usize> = rotation().collect();
+        by_remainder.sort_by_key(|&slot| core::cmp::Reverse(share(slot) % self.len as u128));
+        for slot in by_remainder {
+            if leftover == 0 {
+                break;
+            }
+            quotas[slot] += 1;
+            leftover -= 1;
+        }
+
+        while batch.len() < take {
+            for slot in rotation() {
+                if quotas[slot] > 0
+                    && let Some(item) = self.groups[slot].pop_front()
+                {
+                    quotas[slot] -= 1;
+                    batch.push(item);
+                    self.last_served = Some(slot);
+                }
+            }
+        }
+
+        self.len -= batch.len();
+        self.pointer = pointer;
+        self.prune_empty_groups();
+        batch
+    }
+
+    /// Keeps only the items for which `f` returns true, preserving their order within each group.
+    /// Groups left empty are dropped and the rotation resumes at the next surviving group.
+    pub fn retain<F: FnMut(&V) -> bool>(&mut self, mut f: F) {
+        for group in &mut self.groups {
+            group.retain(|value| f(value));
+        }
+        self.len = self.groups.iter().map(VecDeque::len).sum();
+        self.prune_empty_groups();
+    }
+
+    /// Replaces the backlog of the group `sample` belongs to with `items`, in order, and
+    /// returns how many items were removed. The group keeps its slot and id; an absent group
+    /// is created at the end of the rotation. The items are not checked against the group,
+    /// so they should all be in the group of `sample`. Replacing with nothing removes the
+    /// group, as if it had been drained. On a full queue built with
+    /// [`with_capacity_policy`](Self::with_capacity_policy), `DropNewest` keeps only the
+    /// leading items that fit and `DropOldest` pops in fair order until the queue is back at
+    /// its cap.
+    pub fn replace_group(&mut self, sample: &V, items: impl IntoIterator<Item = &'a V>) -> usize {
+        let Some(slot) = self.slot_of(sample) else {
+            let group: VecDeque<&'a V> = items.into_iter().collect();
+            if !group.is_empty() {
+                self.len += group.len();
+                self.push_group(group);
+                self.shed_overflow(self.groups.len() - 1);
+            }
+            return 0;
+        };
+
+        let group = &mut self.groups[slot];
+        let removed = group.len();
+        group.clear();
+        group.extend(items);
+        self.len = self.len - removed + group.len();
+        if group.is_empty() {
+            self.prune_empty_groups();
+        } else {
+            self.shed_overflow(slot);
+        }
+        removed
+    }
+
+    /// Swaps the item the next `pop` would return for `value` in place and returns the old
+    /// reference, e.g. to publish an updated version of a pending event. `value` must be in
+    /// the same group as the item it replaces, so it keeps that item's group, position and
+    /// turn; nothing else moves. When it is not, or the queue is empty, `value` is handed
+    /// back as `Err` and the queue is left untouched.
+    pub fn replace_next(&mut self, value: &'a V) -> Result<&'a V, &'a V> {
+        let Some(slot) = self.next_slot() else {
+            return Err(value);
+        };
+        match self.groups[slot].front_mut() {
+            Some(head) if same_group(*head, value, value.group_tag()) => {
+                Ok(mem::replace(head, value))
+            }
+            _ => Err(value),
+        }
+    }
+
+    /// Removes every item for which `f` returns true and returns how many were removed.
+    /// Emptied groups are dropped; the rotation resumes at the group the pointer referenced,
+    /// or the next surviving one, so no group is skipped on the following `pop`.
+    pub fn remove<F: FnMut(&V) -> bool>(&mut self, mut f: F) -> usize {
+        let before = self.len;
+        self.retain(|value| !f(value));
+        before - self.len
+    }
+
+    /// Repairs split groups: merges every group whose head is in the same group as the head
+    /// of an earlier group in rotation order, appending its items behind that group's items.
+    /// Only inconsistent `FairGroup` impls or head changes can leave such splits behind.
+    /// Absorbed groups are dropped and the rotation resumes at the group the pointer
+    /// referenced, or the next surviving one. O(group_count²) head comparisons.
+    pub fn compact(&mut self) {
+        let order: Vec<usize> = self.rotation.iter().collect();
+        for (position, &slot) in order.iter().enumerate() {
+            let Some(&head) = self.groups[slot].front() else {
+                continue;
+            };
+            let tag = head.group_tag();
+            for &other in &order[position + 1..] {
+                if self.groups[other]
+                    .front()
+                    .is_some_and(|&value| same_group(head, value, tag))
+                {
+                    let absorbed = mem::take(&mut self.groups[other]);
+                    self.groups[slot].extend(absorbed);
+                }
+            }
+        }
+        self.prune_empty_groups();
+    }
+
+    /// Collapses runs of adjacent items within each group, dropping every item for which
+    /// `same(prev, curr)` holds against the last kept item before it. Items are never
+    /// compared across groups, and each group keeps its head, so the rotation is unchanged.
+    pub fn dedup_by<F: FnMut(&V, &V) -> bool>(&mut self, mut same: F) {
+        for group in &mut self.groups {
+            let mut prev: Option<&'a V> = None;
+            group.retain(|&curr| {
+                let duplicate = prev.is_some_and(|prev| same(prev, curr));
+                if !duplicate {
+                    prev = Some(curr);
+                }
+                !duplicate
+            });
+        }
+        self.len = self.groups.iter().map(VecDeque::len).sum();
+    }
+
+    /// Reduces every group to its most recently inserted item, dropping all older ones.
+    /// Group slots and the rotation are kept, so only the backlog shrinks.
+    pub fn keep_last_per_group(&mut self) {
+        for group in &mut self.groups {
+            let keep_from = group.len().saturating_sub(1);
+            group.drain(..keep_from);
+        }
+        self.len = self.groups.len();
+    }
+
+    /// Summarises reserved versus used capacity of the group storage (requires the `std` feature).
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn capacity_report(&self) -> crate::CapacityReport {
+        let mut report = crate::CapacityReport {
+            groups_len: self.groups.len(),
+            groups_capacity: self.groups.capacity(),
+            items: self.len,
+            min_group_capacity: if self.groups.is_empty() {
+                0
+            } else {
+                usize::MAX
+            },
+            ..Default::default()
+        };
+
+        for group in &self.groups {
+            let capacity = group.capacity();
+            report.min_group_capacity = report.min_group_capacity.min(capacity);
+            report.max_group_capacity = report.max_group_capacity.max(capacity);
+            report.total_group_capacity += capacity;
+            report.wasted_capacity += capacity - group.len();
+        }
+
+        report
+    }
+
+    /// Estimates the heap bytes the queue holds: the reserved group list, id and rotation
+    /// slots, plus every group's reserved reference slots, including buffers parked by
+    /// `recycle`. Only the storage the queue owns is counted, not the items behind the
+    /// references, the queue struct itself or allocator overhead, so the figure is a lower
+    /// bound meant for dashboards rather than an exact measure.
+    #[must_use]
+    pub fn estimate_memory(&self) -> usize {
+        let list = self.groups.capacity() * mem::size_of::<VecDeque<&'a V>>()
+            + self.ids.capacity() * mem::size_of::<GroupId>()
+            + self.rotation.allocated_bytes();
+        let items: usize = self
+            .groups
+            .iter()
+            .chain(&self.spare)
+            .map(VecDeque::capacity)
+            .sum();
+        list + items * mem::size_of::<&'a V>()
+    }
+
+    /// Releases spare capacity of the group list and of every remaining group, and drops the
+    /// buffers parked by `recycle`. Items, their order and the rotation are left untouched.
+    pub fn shrink_to_fit(&mut self) {
+        self.spare = Vec::new();
+        self.groups.shrink_to_fit();
+        self.ids.shrink_to_fit();
+        self.rotation.shrink_to(0);
+        for group in &mut self.groups {
+            group.shrink_to_fit();
+        }
+    }
+
+    /// Shrinks the capacity of the group list toward `max.max(group_count)`, keeping a
+    /// steady-state buffer for churny groups. Unlike `shrink_to_fit` the groups themselves
+    /// are left alone, and the capacity is never grown.
+    pub fn shrink_groups_to(&mut self, max: usize) {
+        self.groups.shrink_to(max);
+        self.ids.shrink_to(max);
+        self.rotation.shrink_to(max);
+    }
+
+    /// Checks the internal invariants and reports the first one that does not hold: no
+    /// tracked group is empty, every group has an id, `len` matches the stored items, and
+    /// the pointer addresses a group (or is 0 when there are none). Every public method
+    /// keeps these; the check is meant for tests and for state rebuilt by hand through
+    /// constructors such as `from_hashmap`.
+    ///
+    /// ```
+    /// use fairqueue::{FairGroup, FairQueue};
+    ///
+    /// struct Job(u8);
+    ///
+    /// impl FairGroup for Job {
+    ///     fn is_same_group(&self, other: &Self) -> bool {
+    ///         self.0 == other.0
+    ///     }
+    /// }
+    ///
+    /// let jobs = [Job(1), Job(2)];
+    /// let mut queue: FairQueue<_> = jobs.iter().collect();
+    /// queue.set_pointer(7);
+    /// assert_eq!(queue.validate(), Ok(()));
+    /// ```
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let group_count = self.groups.len();
+        if let Some(slot) = self.groups_view().position(|group| group.is_empty()) {
+            return Err(InvariantError::EmptyGroup { slot });
+        }
+        if self.ids.len() != group_count {
+            return Err(InvariantError::IdCountMismatch {
+                ids: self.ids.len(),
+                group_count,
+            });
+        }
+        let stored: usize = self.groups.iter().map(VecDeque::len).sum();
+        if stored != self.len {
+            return Err(InvariantError::LenMismatch {
+                len: self.len,
+                stored,
+            });
+        }
+        if self.pointer >= group_count && self.pointer != 0 {
+            return Err(InvariantError::PointerOutOfRange {
+                pointer: self.pointer,
+                group_count,
+            });
+        }
+        Ok(())
+    }
+
+    fn is_consistent(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Drops emptied groups while keeping the pointer on the group it referenced,
+    /// or on the next surviving group when that one was dropped.
+    /// The last served group keeps being tracked if it survives.
+    fn prune_empty_groups(&mut self) {
+        let count = self.groups.len();
+        let kept = |slot: &usize| !self.groups[*slot].is_empty();
+        let pointed = if self.pointer < count {
+            self.rotation.walk(self.pointer).find(kept)
+        } else {
+            None
+        };
+        let pointed = pointed.map(|slot| self.ids[slot]);
+        let last_served = self
+            .last_served
+            .filter(|&slot| slot < count && kept(&slot))
+            .map(|slot| self.ids[slot]);
+
+        // Going down, every slot moved in by `swap_remove` has already been kept.
+        for slot in (0..count).rev() {
+            if self.groups[slot].is_empty() {
+                self.groups.swap_remove(slot);
+                self.ids.swap_remove(slot);
+                self.rotation.remove(slot);
+            }
+        }
+
+        self.pointer = pointed
+            .and_then(|id| self.slot_of_id(id))
+            .unwrap_or_else(|| self.rotation.first());
+        self.last_served = last_served.and_then(|id| self.slot_of_id(id));
+    }
+
+    /// Applies the [`OverflowPolicy`] after a bulk change left more than `max_len` items:
+    /// `DropNewest` trims the back of the group in `slot`, which received the new items,
+    /// and `DropOldest` pops in fair order.
+    fn shed_overflow(&mut self, slot: usize) {
+        let Some(policy) = self.overflow_policy else {
+            return;
+        };
+        while self.len > self.max_len {
+            if policy == OverflowPolicy::DropOldest {
+                self.pop();
+            } else if self.groups[slot].pop_back().is_some() {
+                self.len -= 1;
+            } else {
+                break;
+            }
+        }
+        self.prune_empty_groups();
+    }
+
+    /// Finds the slot of the group `sample` belongs to.
+    fn slot_of(&self, sample: &V) -> Option<usize> {
+        let tag = sample.group_tag();
+        self.groups.iter().position(|group| {
+            group
+                .front()
+                .is_some_and(|head| same_group(*head, sample, tag))
+        })
+    }
+
+    /// Splits the queue into its groups, front first, in rotation order, and the position
+    /// of the pointer.
+    pub(crate) fn into_groups(mut self) -> (Vec<VecDeque<&'a V>>, usize) {
+        let pointer = self.pointer();
+        let order: Vec<usize> = self.rotation.iter().collect();
+        let groups = order
+            .into_iter()
+            .map(|slot| mem::take(&mut self.groups[slot]))
+            .collect();
+        (groups, pointer)
+    }
+
+    /// Iterates over the slots in rotation order, starting at the pointer.
+    fn round(&self) -> Walk<'_> {
+        let from = if self.pointer < self.groups.len() {
+            self.pointer
+        } else {
+            self.rotation.first()
+        };
+        self.rotation.walk(from)
+    }
+}
+
+impl<'a, V: FairGroup + ?Sized, S: Scheduler + Clone> FairQueue<'a, V, S> {
+    /// Iterates over every item in the exact order `pop` would return them.
+    /// The walk is replayed over per-group cursors with a clone of the scheduler;
+    /// the queue is left untouched.
+    #[must_use]
+    pub fn iter(&self) -> QueueIter<'_, 'a, V, S> {
+        QueueIter::new(self)
+    }
+
+    /// Collects every item in the order `pop` would return them, leaving the queue untouched
+    /// (requires the `std` feature). A snapshot of `iter`.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn peek_all(&self) -> std::vec::Vec<&'a V> {
+        self.iter().collect()
+    }
+
+    /// Iterates over every item paired with the 0-based position `pop` would return it at.
+    /// The queue itself is left untouched.
+    pub fn scheduled(&self) -> impl Iterator<Item = (usize, &'a V)> + '_ {
+        self.iter().enumerate()
+    }
+
+    /// Iterates over `f` applied to every item in pop order, a read-only projection of
+    /// `iter` that leaves the queue untouched.
+    pub fn map_view<'b, U, F>(&'b self, f: F) -> impl Iterator<Item = U> + 'b
+    where
+        F: FnMut(&'a V) -> U + 'b,
+    {
+        self.iter().map(f)
+    }
+
+    /// Returns the item the `n`-th upcoming `pop` would return (0-indexed), simulating
+    /// the rotation, group removal and pointer wrap-around without mutating the queue.
+    #[must_use]
+    pub fn peek_nth(&self, n: usize) -> Option<&'a V> {
+        if n >= self.len {
+            return None;
+        }
+        self.iter().nth(n)
+    }
+
+    /// Removes and returns the item the `n`-th upcoming `pop` would return (0-indexed).
+    /// Every group keeps the order of its remaining items and an emptied group is dropped
+    /// without reordering the others. The rotation is not replayed around the gap: the
+    /// affected group now runs one item shorter, so later pops can interleave differently
+    /// than the original order minus the item. Under round-robin the first `n` pops are
+    /// unchanged. O(n + group_count).
+    pub fn take(&mut self, n: usize) -> Option<&'a V> {
+        let item = self.peek_nth(n)?;
+        let slot = self.slot_of(item)?;
+        let tag = item.group_tag();
+        let depth = self
+            .iter()
+            .take(n)
+            .filter(|&value| same_group(self.groups[slot][0], value, tag))
+            .count();
+        let removed = self.groups[slot].remove(depth)?;
+        debug_assert!(ptr::eq(removed, item));
+        self.len -= 1;
+        if self.groups[slot].is_empty() {
+            self.prune_empty_groups();
+        }
+        Some(removed)
+    }
+
+    /// Lazily removes the items for which `f` returns true: each `next` finds the first
+    /// matching item in the order `pop` would return them and removes it as
+    /// [`take`](Self::take) does. Non-matching items stay queued. Dropping the iterator early
+    /// leaves every item not yet yielded in place. Each step rescans from the front, so `f`
+    /// may see a surviving item several times; O(len) per yielded item.
+    pub fn drain_filter<F: FnMut(&V) -> bool>(&mut self, f: F) -> DrainFilter<'_, 'a, V, F, S> {
+        DrainFilter {
+            queue: self,
+            pred: f,
+        }
+    }
+
+    /// Drains a clone of the queue and verifies spacing: no pop may return an item in the
+    /// same group as the previous pop while an item of another group is still pending.
+    /// Groups are judged by the `FairGroup` relation itself, so an inconsistent, e.g.
+    /// non-transitive, `is_same_group` shows up as a violation at the offending pop
+    /// position. Quadratic in `len`; meant for tests.
+    pub fn check_fairness(&self) -> Result<(), FairnessViolation> {
+        let popped: Vec<&V> = self.clone().into_iter().collect();
+        for (position, pair) in popped.windows(2).enumerate() {
+            let (prev, curr) = (pair[0], pair[1]);
+            let same = |value: &V| same_group(prev, value, value.group_tag());
+            if same(curr) && !popped[position + 2..].iter().all(|value| same(value)) {
+                return Err(FairnessViolation::new(position + 1));
+            }
+        }
+        Ok(())
+    }
+
+    /// Panics with the reported violation when [`check_fairness`](Self::check_fairness)
+    /// fails. Does nothing in release builds.
+    #[track_caller]
+    pub fn debug_assert_fair(&self) {
+        if cfg!(debug_assertions)
+            && let Err(violation) = self.check_fairness()
+        {
+            panic!("{violation}");
+        }
+    }
+
+    /// Consumes the queue, moving the items for which `f` returns true into the first queue
+    /// and the rest into the second, in one pass. Both keep the order within each group and
+    /// the rotation order of the groups, starting at the group under the pointer, along with
+    /// the scheduler and burst mode; limits and ids are not carried over.
+    pub fn partition<F: FnMut(&V) -> bool>(mut self, mut f: F) -> (Self, Self) {
+        let mut matches = Self::with_scheduler(self.scheduler.clone());
+        let mut rest = Self::with_scheduler(self.scheduler.clone());
+        matches.burst = self.burst;
+        rest.burst = self.burst;
+
+        let order: Vec<usize> = self.round().collect();
+        for slot in order {
+            let group = mem::take(&mut self.groups[slot]);
+            let (hit, miss): (VecDeque<&'a V>, VecDeque<&'a V>) =
+                group.into_iter().partition(|value| f(value));
+            for (queue, part) in [(&mut matches, hit), (&mut rest, miss)] {
+                if !part.is_empty() {
+                    queue.len += part.len();
+                    queue.push_group(part);
+                }
+            }
+        }
+        (matches, rest)
+    }
+}
+
+/// Compares by `group_tag` when both sides report one, otherwise by identity or `is_same_group`.
+/// `tag` is the precomputed tag of `value`.
+#[inline(always)]
+fn same_group<V: FairGroup + ?Sized>(head: &V, value: &V, tag: Option<u64>) -> bool {
+    match (tag, head.group_tag()) {
+        (Some(tag), Some(head_tag)) => tag == head_tag,
+        _ => ptr::eq(head, value) || head.is_same_group(value),
+    }
+}
+
+/// Snapshots the scheduling state. Only references are copied, so `V` need not be `Clone`.
+impl<V: ?Sized, S: Clone> Clone for FairQueue<'_, V, S> {
+    fn clone(&self) -> Self {
+        Self {
+            groups: self.groups.clone(),
+            ids: self.ids.clone(),
+            rotation: self.rotation.clone(),
+            next_id: self.next_id,
+            pointer: self.pointer,
+            start_pointer: self.start_pointer,
+            len: self.len,
+            last_served: self.last_served,
+            group_capacity: self.group_capacity,
+            burst: self.burst,
+            max_len: self.max_len,
+            max_per_group: self.max_per_group,
+            max_groups: self.max_groups,
+            group_overflow: self.group_overflow,
+            overflow_policy: self.overflow_policy,
+            spare: Vec::new(),
+            scheduler: self.scheduler.clone(),
+        }
+    }
+}
+
+/// Compares the dispatch sequences, not the layout: two queues are equal when their `pop`
+/// calls would yield equal items in the same order, even if their groups sit in different
+/// slots or their pointers differ. Limits and ids are ignored.
+impl<V: FairGroup + PartialEq + ?Sized, S: Scheduler + Clone> PartialEq for FairQueue<'_, V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<V: FairGroup + Eq + ?Sized, S: Scheduler + Clone> Eq for FairQueue<'_, V, S> {}
+
+/// Shows `len`, `group_count`, the `pointer` position and every group in rotation order.
+/// The group the next `pop` serves is prefixed with `> `.
+impl<V: FairGroup + Debug + ?Sized, S: Scheduler> Debug for FairQueue<'_, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let current = self.next_slot().unwrap_or(0);
+        f.debug_struct("FairQueue")
+            .field("len", &self.len)
+            .field("group_count", &self.groups.len())
+            .field("pointer", &self.pointer())
+            .field(
+                "groups",
+                &DebugGroups(self.rotation.iter().map(|slot| DebugGroup {
+                    current: slot == current,
+                    items: self.groups[slot].iter(),
+                })),
+            )
+            .finish()
+    }
+}
+
+/// Formats a group list lazily without collecting it.
+pub(crate) struct DebugGroups<I>(pub(crate) I);
+
+impl<I, G> Debug for DebugGroups<I>
+where
+    I: Iterator<Item = G> + Clone,
+    G: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.clone()).finish()
+    }
+}
+
+/// One group in a `Debug` dump, marked when the pointer references it.
+pub(crate) struct DebugGroup<I> {
+    pub(crate) current: bool,
+    pub(crate) items: I,
+}
+
+impl<I, T> Debug for DebugGroup<I>
+where
+    I: Iterator<Item = T> + Clone,
+    T: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.current {
+            f.write_str("> ")?;
+        }
+        f.debug_list().entries(self.items.clone()).finish()
+    }
+}
+
+/// Emits the groups in rotation order followed by the `pointer` position.
+/// Deserialize the result into an [`OwnedFairQueue`](crate::OwnedFairQueue).
+#[cfg(feature = "serde")]
+impl<V: serde::Serialize + ?Sized, S> serde::Serialize for FairQueue<'_, V, S> {
+    fn serialize<Ser: serde::Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        use serde::ser::SerializeStruct;
+
+        /// The groups in rotation order, serialized as one sequence.
+        struct InRotation<'q, 'a, V: ?Sized>(&'q [VecDeque<&'a V>], &'q Rotation);
+
+        impl<V: serde::Serialize + ?Sized> serde::Serialize for InRotation<'_, '_, V> {
+            fn serialize<Ser: serde::Serializer>(
+                &self,
+                serializer: Ser,
+            ) -> Result<Ser::Ok, Ser::Error> {
+                serializer.collect_seq(self.1.iter().map(|slot| &self.0[slot]))
+            }
+        }
+
+        let pointer = if self.pointer < self.groups.len() {
+            self.rotation.position(self.pointer)
+        } else {
+            0
+        };
+        let mut state = serializer.serialize_struct("FairQueue", 2)?;
+        state.serialize_field("groups", &InRotation(&self.groups, &self.rotation))?;
+        state.serialize_field("pointer", &pointer)?;
+        state.end()
+    }
+}
+
+impl<V: FairGroup + ?Sized, S: Scheduler + Default> Default for FairQueue<'_, V, S> {
+    fn default() -> Self {
+        Self::with_scheduler(S::default())
+    }
+}
+
+/// Most groups `extend` reserves room for from the iterator's `size_hint`. The hint counts
+/// items, which only bound the number of new groups from above, so reserving the whole hint
+/// would waste one slot per item on bulk loads with few groups.
+const EXTEND_GROUP_HINT: usize = 256;
+
+impl<'a, V: FairGroup + ?Sized, S: Scheduler> Extend<&'a V> for FairQueue<'a, V, S> {
+    /// Inserts every item in iteration order, exactly like repeated `insert` calls. The group
+    /// list is first reserved from the iterator's lower size bound, capped at a few hundred
+    /// groups; the hint only affects capacity, never the resulting order.
+    fn extend<I: IntoIterator<Item = &'a V>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let hint = iter.size_hint().0.min(EXTEND_GROUP_HINT);
+        self.groups.reserve(hint);
+        self.ids.reserve(hint);
+        self.rotation.reserve(hint);
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'a, V: FairGroup + ?Sized, S: Scheduler + Default> FromIterator<&'a V>
+    for FairQueue<'a, V, S>
+{
+    fn from_iter<I: IntoIterator<Item = &'a V>>(iter: I) -> Self {
+        let mut queue = Self::default();
+        queue.extend(iter);
+        queue
+    }
+}
+
+/// Moves the groups of a stack over, keeping their slots, the pointer and `len`.
+/// Within each group the bottom of the stack becomes the front of the queue and the top
+/// becomes the back, so items keep their insertion order: the stack would have served a
+/// group newest first, the queue serves it oldest first.
+impl<'a, V: FairGroup> From<FairStack<'a, V>> for FairQueue<'a, V> {
+    fn from(stack: FairStack<'a, V>) -> Self {
+        let (groups, pointer) = stack.into_groups();
+        Self::from_groups(groups.into_iter().map(VecDeque::from).collect(), pointer)
+    }
+}
+
+/// Iterator over the first element of each group, in rotation order.
+pub struct QueueGroupHeads<'queue, 'value, V: FairGroup + ?Sized> {
+    groups: &'queue [VecDeque<&'value V>],
+    walk: Walk<'queue>,
+}
+
+impl<'value, V: FairGroup + ?Sized> Iterator for QueueGroupHeads<'_, 'value, V> {
+    type Item = &'value V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walk
+            .next()
+            .and_then(|slot| self.groups[slot].front().copied())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.walk.size_hint()
+    }
+}
+
+impl<V: FairGroup + ?Sized> DoubleEndedIterator for QueueGroupHeads<'_, '_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.walk
+            .next_back()
+            .and_then(|slot| self.groups[slot].front().copied())
+    }
+}
+
+/// Exact because tracked groups are never empty, so every group yields one head.
+impl<V: FairGroup + ?Sized> ExactSizeIterator for QueueGroupHeads<'_, '_, V> {}
+
+/// Iterator over the item count of each group, in rotation order.
+pub struct QueueGroupLens<'queue, 'value, V: FairGroup + ?Sized> {
+    groups: &'queue [VecDeque<&'value V>],
+    walk: Walk<'queue>,
+}
+
+impl<V: FairGroup + ?Sized> Iterator for QueueGroupLens<'_, '_, V> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walk.next().map(|slot| self.groups[slot].len())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.walk.size_hint()
+    }
+}
+
+impl<V: FairGroup + ?Sized> ExactSizeIterator for QueueGroupLens<'_, '_, V> {}
+
+impl<'a, V: FairGroup + ?Sized, S: Scheduler> IntoIterator for FairQueue<'a, V, S> {
+    type Item = &'a V;
+    type IntoIter = QueueIntoIter<'a, V, S>;
+
+    /// Consumes the queue, yielding items in the order `pop` would return them.
+    fn into_iter(self) -> Self::IntoIter {
+        QueueIntoIter { queue: self }
+    }
+}
+
+/// Owning iterator over a [`FairQueue`] in fair pop order.
+pub struct QueueIntoIter<'a, V: FairGroup + ?Sized, S: Scheduler = RoundRobin> {
+    queue: FairQueue<'a, V, S>,
+}
+
+impl<'a, V: FairGroup + ?Sized, S: Scheduler> Iterator for QueueIntoIter<'a, V, S> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.queue.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.len, Some(self.queue.len))
+    }
+}
+
+impl<V: FairGroup + ?Sized, S: Scheduler> ExactSizeIterator for QueueIntoIter<'_, V, S> {}
+
+impl<V: FairGroup + ?Sized, S: Scheduler> FusedIterator for QueueIntoIter<'_, V, S> {}
+
+/// Filtering iterator returned by [`FairQueue::drain_filter`].
+pub struct DrainFilter<'queue, 'value, V: FairGroup + ?Sized, F, S: Scheduler = RoundRobin> {
+    queue: &'queue mut FairQueue<'value, V, S>,
+    pred: F,
+}
+
+impl<'value, V, F, S> Iterator for DrainFilter<'_, 'value, V, F, S>
+where
+    V: FairGroup + ?Sized,
+    F: FnMut(&V) -> bool,
+    S: Scheduler + Clone,
+{
+    type Item = &'value V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.queue.iter().position(|value| (self.pred)(value))?;
+        self.queue.take(position)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.queue.len))
+    }
+}
+
+impl<V, F, S> FusedIterator for DrainFilter<'_, '_, V, F, S>
+where
+    V: FairGroup + ?Sized,
+    F: FnMut(&V) -> bool,
+    S: Scheduler + Clone,
+{
+}
+
+/// Draining iterator returned by [`FairQueue::drain`].
+pub struct QueueDrain<'queue, 'value, V: FairGroup + ?Sized, S: Scheduler = RoundRobin> {
+    queue: &'queue mut FairQueue<'value, V, S>,
+}
+
+impl<'value, V: FairGroup + ?Sized, S: Scheduler> Iterator for QueueDrain<'_, 'value, V, S> {
+    type Item = &'value V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.queue.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.len, Some(self.queue.len))
+    }
+}
+
+impl<V: FairGroup + ?Sized, S: Scheduler> ExactSizeIterator for QueueDrain<'_, '_, V, S> {}
+
+impl<V: FairGroup + ?Sized, S: Scheduler> FusedIterator for QueueDrain<'_, '_, V, S> {}
+
+impl<V: FairGroup + ?Sized, S: Scheduler> Drop for QueueDrain<'_, '_, V, S> {
+    fn drop(&mut self) {
+        self.queue.clear();
+    }
+}
+
+/// Iterator over a detached group, returned by [`FairQueue::drain_group`].
+pub struct QueueDrainGroup<'value, V: FairGroup + ?Sized> {
+    iter: vec_deque::IntoIter<&'value V>,
+}
+
+impl<'value, V: FairGroup + ?Sized> Iterator for QueueDrainGroup<'value, V> {
+    type Item = &'value V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<V: FairGroup + ?Sized> ExactSizeIterator for QueueDrainGroup<'_, V> {}
+
+impl<V: FairGroup + ?Sized> FusedIterator for QueueDrainGroup<'_, V> {}
+
+/// Pending pop returned by [`FairQueue::pop_guard`].
+/// Dereferences to the item that the next `pop` would return.
+pub struct PopGuard<'queue, 'value, V: FairGroup + ?Sized, S: Scheduler = RoundRobin> {
+    queue: &'queue mut FairQueue<'value, V, S>,
+    item: &'value V,
+}
+
+impl<'value, V: FairGroup + ?Sized, S: Scheduler> PopGuard<'_, 'value, V, S> {
+    /// Removes the item from the queue, advancing the rotation as `pop` does.
+    pub fn commit(self) -> &'value V {
+        let popped = self.queue.pop();
+        debug_assert!(popped.is_some_and(|item| ptr::eq(item, self.item)));
+        self.item
+    }
+
+    /// Leaves the item at the front of its group, as if it was never borrowed.
+    pub fn rollback(self) {}
+}
+
+impl<V: FairGroup + ?Sized, S: Scheduler> Deref for PopGuard<'_, '_, V, S> {
+    type Target = V;
+
+    fn deref(&self) -> &Self::Target {
+        self.item
+    }
+}
+
+/// Item returned by [`FairQueue::pop_detailed`] with the spacing flag of that pop.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PopOutcome<'a, V: ?Sized> {
+    /// The popped item.
+    pub item: &'a V,
+    /// True when the previous pop served the same group.
+    pub repeated_group: bool,
+}
+
+impl<V: ?Sized> Clone for PopOutcome<'_, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V: ?Sized> Copy for PopOutcome<'_, V> {}
+
+/// Per-group backlog summary returned by [`FairQueue::backlog_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BacklogStats {
+    /// Number of groups the statistics cover.
+    pub group_count: usize,
+    /// Item count of the shortest group.
+    pub min: usize,
+    /// Item count of the longest group.
+    pub max: usize,
+    /// Average item count per group.
+    pub mean: f64,
+}
+
+/// Read-only view of one group, yielded by [`FairQueue::groups_view`].
+pub struct GroupView<'queue, 'value, V: ?Sized> {
+    items: &'queue VecDeque<&'value V>,
+}
+
+impl<'queue, 'value, V: ?Sized> GroupView<'queue, 'value, V> {
+    /// Returns how many items the group holds; never 0 for a tracked group.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns true when the group holds no items.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the item the group serves next.
+    #[must_use]
+    pub fn head(&self) -> Option<&'value V> {
+        self.items.front().copied()
+    }
+
+    /// Iterates over the group's items front to back, the order they are served in.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'value V> + ExactSizeIterator + 'queue {
+        self.items.iter().copied()
+    }
+}
+
+impl<V: ?Sized> Clone for GroupView<'_, '_, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V: ?Sized> Copy for GroupView<'_, '_, V> {}
+
+/// Group lookup returned by [`FairQueue::group_entry`].
+pub enum GroupEntry<'queue, 'value, V: FairGroup + ?Sized, S: Scheduler = RoundRobin> {
+    /// The group holds items.
+    Occupied(OccupiedGroupEntry<'queue, 'value, V, S>),
+    /// No group matches; inserting opens one.
+    Vacant(VacantGroupEntry<'queue, 'value, V, S>),
+}
+
+/// Existing group found by [`FairQueue::group_entry`].
+pub struct OccupiedGroupEntry<'queue, 'value, V: FairGroup + ?Sized, S: Scheduler = RoundRobin> {
+    queue: &'queue mut FairQueue<'value, V, S>,
+    slot: usize,
+}
+
+impl<'value, V: FairGroup + ?Sized, S: Scheduler> OccupiedGroupEntry<'_, 'value, V, S> {
+    /// Returns how many items the group holds.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.queue.groups[self.slot].len()
+    }
+
+    /// Always false: an occupied group holds at least one item.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the item the group serves next.
+    #[must_use]
+    pub fn head(&self) -> &'value V {
+        self.queue.groups[self.slot][0]
+    }
+
+    /// Appends `value` to the back of the group. `value` must belong to the group.
+    /// The queue's overflow policy applies as for `insert`.
+    pub fn push_back(&mut self, value: &'value V) {
+        debug_assert!(same_group(self.head(), value, value.group_tag()));
+        if let Some(id) = self.queue.push_item(Some(self.slot), value, false) {
+            // A `DropOldest` pop may have moved or emptied the group.
+            self.slot = self.queue.slot_of_id(id).unwrap_or(self.slot);
+        }
+    }
+}
+
+/// Missing group found by [`FairQueue::group_entry`].
+pub struct VacantGroupEntry<'queue, 'value, V: FairGroup + ?Sized, S: Scheduler = RoundRobin> {
+    queue: &'queue mut FairQueue<'value, V, S>,
+}
+
+impl<'value, V: FairGroup + ?Sized, S: Scheduler> VacantGroupEntry<'_, 'value, V, S> {
+    /// Opens the group with `value` at the end of the rotation and returns its id.
+    /// `value` must belong to the group that was looked up. When a limit drops `value`, the
+    /// returned id matches no group; when `GroupOverflow::MergeIntoNewest` applies, it is the
+    /// id of the group `value` was merged into.
+    pub fn insert(self, value: &'value V) -> GroupId {
+        match self.queue.push_item(None, value, false) {
+            Some(id) => id,
+            None => self.queue.fresh_id(),
+        }
+    }
+}
+
+/// Non-consuming iterator returned by [`FairQueue::iter`].
+/// Replays the walk of `pop` over borrowed groups without mutating the queue.
+pub struct QueueIter<'queue, 'value, V: FairGroup + ?Sized, S: Scheduler = RoundRobin> {
+    groups: Vec<vec_deque::Iter<'queue, &'value V>>,
+    rotation: Rotation,
+    pointer: usize,
+    last_served: Option<usize>,
+    burst: bool,
+    remaining: usize,
+    scheduler: S,
+}
+
+impl<'queue, 'value, V: FairGroup + ?Sized, S: Scheduler + Clone> QueueIter<'queue, 'value, V, S> {
+    fn new(queue: &'queue FairQueue<'value, V, S>) -> Self {
+        Self {
+            groups: queue.groups.iter().map(VecDeque::iter).collect(),
+            rotation: queue.rotation.clone(),
+            pointer: queue.pointer,
+            last_served: queue.last_served,
+            burst: queue.burst,
+            remaining: queue.len,
+            scheduler: queue.scheduler.clone(),
+        }
+    }
+}
+
+impl<'queue, 'value, V: FairGroup + ?Sized, S: Scheduler> Iterator
+    for QueueIter<'queue, 'value, V, S>
+{
+    type Item = &'value V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.groups.is_empty() {
+            return None;
+        }
+
+        if self.pointer >= self.groups.len() {
+            self.pointer = 0;
+        }
+
+        let slot = self
+            .scheduler
+            .next_slot(self.pointer, self.last_served, &self.groups);
+        if slot < self.groups.len() {
+            self.pointer = slot;
+        }
+        let popped = rotation::pop_ordered(
+            &mut self.groups,
+            &mut self.rotation,
+            &mut self.pointer,
+            self.burst,
+        );
+        self.remaining = self.remaining.saturating_sub(1);
+        let popped = popped?;
+        self.scheduler.served(popped.slot);
+        self.last_served = popped.kept.then_some(popped.slot);
+        Some(*popped.item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<V: FairGroup + ?Sized, S: Scheduler> ExactSizeIterator for QueueIter<'_, '_, V, S> {}
+
+impl<V: FairGroup + ?Sized, S: Scheduler> FusedIterator for QueueIter<'_, '_, V, S> {}
+
+impl<'queue, 'value, V: FairGroup + ?Sized, S: Scheduler + Clone> IntoIterator
+    for &'queue FairQueue<'value, V, S>
+{
+    type Item = &'value V;
+    type IntoIter = QueueIter<'queue, 'value, V, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GroupOverflow, LongestQueueFirst, OverflowPolicy, Strategy};
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u32,
+        user_id: &'static str,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    fn event(timestamp: u32, group: usize) -> Event {
+        const IDS: [&str; 4] = ["user1", "user2", "user3", "user4"];
+        Event {
+            timestamp,
+            user_id: IDS[group % IDS.len()],
+            group,
+        }
+    }
+
+    #[test]
+    fn test_spaced_fairness() {
+        let event1 = Event {
+            timestamp: 1,
+            user_id: "user1",
+            group: 0,
+        };
+        let event2 = Event {
+            timestamp: 2,
+            user_id: "user2",
+            group: 1,
+        };
+        let event3 = Event {
+            timestamp: 3,
+            user_id: "user1",
+            group: 0,
+        };
+        let event4 = Event {
+            timestamp: 4,
+            user_id: "user3",
+            group: 2,
+        };
+        let event5 = Event {
+            timestamp: 5,
+            user_id: "user2",
+            group: 1,
+        };
+        let event6 = Event {
+            timestamp: 6,
+            user_id: "user1",
+            group: 0,
+        };
+        let event7 = Event {
+            timestamp: 7,
+            user_id: "user1",
+            group: 0,
+        };
+        let event8 = Event {
+            timestamp: 8,
+            user_id: "user3",
+            group: 2,
+        };
+
+        let mut queue = FairQueue::new();
+
+        queue.insert(&event1);
+        queue.insert(&event2);
+        queue.insert(&event3);
+        queue.insert(&event4);
+        queue.insert(&event5);
+        queue.insert(&event6);
+        queue.insert(&event7);
+        queue.insert(&event8);
+
+        assert_eq!(queue.pop(), Some(&event1));
+        assert_eq!(queue.pop(), Some(&event2));
+        assert_eq!(queue.pop(), Some(&event4));
+        assert_eq!(queue.pop(), Some(&event3));
+        assert_eq!(queue.pop(), Some(&event5));
+        assert_eq!(queue.pop(), Some(&event8));
+        assert_eq!(queue.pop(), Some(&event6));
+        assert_eq!(queue.pop(), Some(&event7));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_len_and_group_count() {
+        let event = Event {
+            timestamp: 0,
+            user_id: "user1",
+            group: 0,
+        };
+        let other = Event {
+            timestamp: 1,
+            user_id: "user2",
+            group: 1,
+        };
+
+        let mut queue = FairQueue::new();
+        assert!(queue.is_empty());
+        assert_eq!(queue.len(), 0);
+        assert_eq!(queue.group_count(), 0);
+
+        queue.insert(&event);
+        assert!(!queue.is_empty());
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.group_count(), 1);
+
+        queue.insert(&other);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.group_count(), 2);
+
+        assert_eq!(queue.pop(), Some(&event));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.group_count(), 1);
+
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+    }
+
+    #[test]
+    fn test_peek_matches_pop() {
+        let event = Event {
+            timestamp: 1,
+            user_id: "user1",
+            group: 0,
+        };
+        let other = Event {
+            timestamp: 2,
+            user_id: "user2",
+            group: 1,
+        };
+
+        let mut queue = FairQueue::new();
+        queue.insert(&event);
+        queue.insert(&other);
+
+        assert_eq!(queue.peek(), Some(&event));
+        assert_eq!(queue.peek(), Some(&event));
+        assert_eq!(queue.pop(), Some(&event));
+        assert_eq!(queue.peek(), Some(&other));
+    }
+
+    #[test]
+    fn test_backlog_stats_of_known_sizes() {
+        assert_eq!(
+            FairQueue::<Event>::new().backlog_stats(),
+            BacklogStats::default()
+        );
+
+        // Groups of 4, 1, 2 and 1 items.
+        let events: Vec<Event> = [0, 1, 0, 2, 0, 3, 2, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        let stats = queue.backlog_stats();
+        assert_eq!(stats.group_count, 4);
+        assert_eq!(stats.min, 1);
+        assert_eq!(stats.max, 4);
+        assert_eq!(stats.mean, 2.0);
+
+        // Serves groups 0 and 1 once each; group 1 disappears.
+        queue.pop();
+        queue.pop();
+        queue.insert(&events[3]);
+        let stats = queue.backlog_stats();
+        assert_eq!((stats.group_count, stats.min, stats.max), (3, 1, 3));
+        assert_eq!(stats.mean, 7.0 / 3.0);
+    }
+
+    /// Forwards items while reporting an arbitrary lower size bound.
+    struct Hinted<I>(I, usize);
+
+    impl<I: Iterator> Iterator for Hinted<I> {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<I::Item> {
+            self.0.next()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.1, None)
+        }
+    }
+
+    #[test]
+    fn test_group_lens_sum_to_len() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 0),
+            event(4, 2),
+            event(5, 0),
+        ];
+        let mut queue: FairQueue<_> = events.iter().collect();
+
+        let lens = queue.group_lens();
+        assert_eq!(lens.len(), queue.group_count());
+        assert_eq!(lens.collect::<Vec<_>>(), vec![3, 1, 1]);
+
+        queue.pop();
+        queue.pop();
+        let heads: Vec<u32> = queue.group_heads().map(|e| e.timestamp).collect();
+        let lens: Vec<usize> = queue.group_lens().collect();
+        assert_eq!(heads.len(), lens.len());
+        assert_eq!(lens.iter().sum::<usize>(), queue.len());
+        for (head, len) in heads.into_iter().zip(lens) {
+            assert_eq!(queue.len_of_group(&events[head as usize - 1]), len);
+        }
+    }
+
+    #[test]
+    fn test_contains_scans_all_groups() {
+        let events = [event(1, 0), event(2, 1), event(3, 0), event(4, 2)];
+        let mut queue: FairQueue<_> = events.iter().collect();
+
+        assert!(queue.contains(|e| e.timestamp == 3));
+        assert!(queue.contains(|e| e.group == 2));
+        assert!(!queue.contains(|e| e.timestamp == 9));
+
+        let mut visited = 0;
+        assert!(queue.contains(|_| {
+            visited += 1;
+            true
+        }));
+        assert_eq!(visited, 1);
+
+        queue.pop();
+        assert!(!queue.contains(|e| e.timestamp == 1));
+    }
+
+    #[test]
+    fn test_len_of_group_tracks_backlog() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 0),
+            event(4, 0),
+            event(5, 2),
+        ];
+        let mut queue = FairQueue::new();
+        for event in &events[..4] {
+            queue.insert(event);
+        }
+        assert_eq!(queue.len_of_group(&event(0, 0)), 3);
+        assert_eq!(queue.len_of_group(&event(0, 1)), 1);
+        assert_eq!(queue.len_of_group(&event(0, 2)), 0);
+
+        queue.pop();
+        queue.pop();
+        queue.insert(&events[4]);
+        assert_eq!(queue.len_of_group(&events[0]), 2);
+        assert_eq!(queue.len_of_group(&events[1]), 0);
+        assert_eq!(queue.len_of_group(&events[4]), 1);
+    }
+
+    #[test]
+    fn test_peek_group_leaves_rotation() {
+        let events = [event(1, 0), event(2, 1), event(3, 0), event(4, 1)];
+        let mut queue: FairQueue<_> = events.iter().collect();
+
+        assert_eq!(queue.peek_group(&event(0, 1)), Some(&events[1]));
+        assert_eq!(queue.peek_group(&events[2]), Some(&events[0]));
+        assert_eq!(queue.peek_group(&event(0, 2)), None);
+        assert_eq!(queue.pop(), Some(&events[0]));
+        assert_eq!(queue.peek_group(&event(0, 0)), Some(&events[2]));
+        assert_eq!(queue.pop(), Some(&events[1]));
+    }
+
+    #[test]
+    fn test_insert_tracked_reports_new_groups() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 0),
+            event(4, 2),
+            event(5, 1),
+        ];
+
+        let mut queue = FairQueue::new();
+        let created: Vec<bool> = events.iter().map(|e| queue.insert_tracked(e)).collect();
+        assert_eq!(created, vec![true, true, false, true, false]);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.group_count(), 3);
+
+        // A cleared group is forgotten, so its next item opens it again.
+        assert_eq!(queue.clear_group(&events[3]), 1);
+        assert!(queue.insert_tracked(&events[3]));
+        assert!(!queue.insert_tracked(&events[0]));
+    }
+
+    #[test]
+    fn test_group_ids_survive_reordering() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 2),
+            event(5, 2),
+            event(6, 2),
+        ];
+
+        let mut queue = FairQueue::new();
+        let first = queue.insert_with_id(&events[0]);
+        let second = queue.insert_with_id(&events[1]);
+        let held = queue.insert_with_id(&events[2]);
+        assert_eq!(queue.insert_with_id(&events[3]), held);
+        assert_eq!(queue.insert_with_id(&events[4]), held);
+        assert!(first < second && second < held);
+
+        // Group 0 empties and the held group is moved into its slot.
+        assert_eq!(queue.pop(), Some(&events[0]));
+        assert_eq!(queue.len_of_group_by_id(held), 3);
+        assert_eq!(queue.pop_group_by_id(first), None);
+        assert_eq!(queue.len_of_group_by_id(first), 0);
+
+        assert_eq!(queue.pop_group_by_id(held), Some(&events[2]));
+        assert_eq!(queue.pop(), Some(&events[1]));
+        assert_eq!(queue.len_of_group_by_id(held), 2);
+
+        assert_eq!(queue.clear_group_by_id(held), 2);
+        assert_eq!(queue.clear_group_by_id(held), 0);
+        assert!(queue.is_empty());
+
+        let reopened = queue.insert_with_id(&events[5]);
+        assert_ne!(reopened, held);
+        assert_eq!(queue.len_of_group_by_id(reopened), 1);
+        assert_eq!(queue.checked_pop(), Ok(Some(&events[5])));
+    }
+
+    #[test]
+    fn test_group_count_matching() {
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 3]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.group_count_matching(|e| e.timestamp >= 2), 2);
+        assert_eq!(queue.group_count_matching(|_| true), queue.group_count());
+
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(0));
+        assert_eq!(queue.group_count_matching(|e| e.timestamp >= 2), 3);
+        assert_eq!(queue.group_count_matching(|e| e.timestamp > 5), 0);
+    }
+
+    #[test]
+    fn test_group_heads_snapshot() {
+        let a1 = Event {
+            timestamp: 1,
+            user_id: "user1",
+            group: 0,
+        };
+        let b1 = Event {
+            timestamp: 2,
+            user_id: "user2",
+            group: 1,
+        };
+        let b2 = Event {
+            timestamp: 3,
+            user_id: "user2",
+            group: 1,
+        };
+        let c1 = Event {
+            timestamp: 4,
+            user_id: "user3",
+            group: 2,
+        };
+
+        let mut queue = FairQueue::new();
+        queue.insert(&a1);
+        queue.insert(&b1);
+        queue.insert(&b2);
+        queue.insert(&c1);
+
+        assert_eq!(queue.group_heads().len(), queue.group_count());
+        let reversed: Vec<&Event> = queue.group_heads().rev().collect();
+        assert_eq!(reversed, [&c1, &b1, &a1]);
+
+        let mut heads = queue.group_heads();
+        assert_eq!(heads.len(), 3);
+        assert_eq!(heads.next(), Some(&a1));
+        assert_eq!(heads.next(), Some(&b1));
+        assert_eq!(heads.next(), Some(&c1));
+        assert_eq!(heads.next(), None);
+
+        #[cfg(feature = "std")]
+        {
+            let collected = queue.group_heads_vec();
+            assert_eq!(collected, vec![&a1, &b1, &c1]);
+        }
+
+        // Ensure borrowing state not consumed.
+        assert_eq!(queue.peek(), Some(&a1));
+    }
+
+    #[test]
+    fn test_pointer_wraps_after_group_removal() {
+        let a1 = Event {
+            timestamp: 1,
+            user_id: "user1",
+            group: 0,
+        };
+        let b1 = Event {
+            timestamp: 2,
+            user_id: "user2",
+            group: 1,
+        };
+        let c1 = Event {
+            timestamp: 3,
+            user_id: "user3",
+            group: 2,
+        };
+        let d1 = Event {
+            timestamp: 4,
+            user_id: "user4",
+            group: 3,
+        };
+
+        let mut queue = FairQueue::new();
+        queue.insert(&a1);
+        queue.insert(&b1);
+        queue.insert(&c1);
+
+        assert_eq!(queue.pop(), Some(&a1));
+        queue.insert(&d1);
+
+        let mut groups = Vec::new();
+        while let Some(item) = queue.pop() {
+            groups.push(item.group);
+        }
+
+        groups.sort_unstable();
+        assert_eq!(groups, vec![1, 2, 3]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_pop_from_advances_rotation() {
+        let a1 = Event {
+            timestamp: 1,
+            user_id: "user1",
+            group: 0,
+        };
+        let a2 = Event {
+            timestamp: 2,
+            user_id: "user1",
+            group: 0,
+        };
+        let b1 = Event {
+            timestamp: 3,
+            user_id: "user2",
+            group: 1,
+        };
+        let b2 = Event {
+            timestamp: 4,
+            user_id: "user2",
+            group: 1,
+        };
+        let c1 = Event {
+            timestamp: 5,
+            user_id: "user3",
+            group: 2,
+        };
+        let missing = Event {
+            timestamp: 6,
+            user_id: "user4",
+            group: 3,
+        };
+
+        let mut queue = FairQueue::new();
+        queue.insert(&a1);
+        queue.insert(&a2);
+        queue.insert(&b1);
+        queue.insert(&b2);
+        queue.insert(&c1);
+
+        assert_eq!(queue.pop_from(&missing), None);
+        assert_eq!(queue.peek(), Some(&a1));
+
+        // Serving group 1 directly moves the rotation on to group 2.
+        assert_eq!(queue.pop_from(&b2), Some(&b1));
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.peek(), Some(&c1));
+
+        assert_eq!(queue.pop(), Some(&c1));
+        assert_eq!(queue.pop(), Some(&a1));
+        assert_eq!(queue.pop(), Some(&b2));
+        assert_eq!(queue.pop(), Some(&a2));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_rotate_to_targets_group() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 0),
+            event(5, 1),
+        ];
+        let mut queue: FairQueue<_> = events.iter().collect();
+
+        assert!(queue.rotate_to(&event(0, 2)));
+        assert_eq!(queue.peek(), Some(&events[2]));
+        assert_eq!(queue.pop(), Some(&events[2]));
+        assert_eq!(queue.pop(), Some(&events[0]));
+
+        assert!(!queue.rotate_to(&event(0, 3)));
+        assert_eq!(queue.peek(), Some(&events[1]));
+        assert!(queue.rotate_to(&events[3]));
+        assert_eq!(queue.pop(), Some(&events[3]));
+    }
+
+    #[test]
+    fn test_promote_group_serves_it_next() {
+        let events: Vec<Event> = [0, 1, 2, 3, 0, 1, 2, 3, 2]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+        let mut queue = FairQueue::new();
+        queue.extend(&events);
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(0));
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(1));
+
+        // The pointer is on group 2; promoting group 0 (behind it) leaves 2 and 3 their turns.
+        assert!(queue.promote_group(&event(0, 0)));
+        assert!(queue.is_consistent());
+        let order: Vec<u32> = queue.clone().into_iter().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![4, 2, 3, 5, 6, 7, 8]);
+
+        // Promoting a group ahead of the pointer moves it back to the pointer.
+        assert!(queue.promote_group(&event(0, 3)));
+        assert_eq!(queue.peek(), Some(&events[3]));
+        let order: Vec<u32> = core::iter::from_fn(|| queue.pop())
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(order, vec![3, 4, 2, 5, 7, 6, 8]);
+
+        assert!(!queue.promote_group(&event(0, 0)));
+    }
+
+    #[test]
+    fn test_scheduled_matches_pop_sequence() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 0),
+            event(4, 2),
+            event(5, 1),
+            event(6, 0),
+            event(7, 0),
+            event(8, 2),
+        ];
+
+        let mut queue = FairQueue::new();
+        let mut replay = FairQueue::new();
+        for event in &events {
+            queue.insert(event);
+            replay.insert(event);
+        }
+        assert_eq!(replay.pop(), Some(&events[0]));
+        assert_eq!(queue.pop(), Some(&events[0]));
+
+        let scheduled: Vec<_> = queue.scheduled().collect();
+        assert_eq!(scheduled.len(), queue.len());
+        for (expected_index, (index, item)) in scheduled.into_iter().enumerate() {
+            assert_eq!(index, expected_index);
+            assert_eq!(Some(item), replay.pop());
+        }
+        assert!(replay.pop().is_none());
+
+        // Scheduling is a pure observation.
+        assert_eq!(queue.len(), 7);
+        assert_eq!(queue.peek(), Some(&events[1]));
+    }
+
+    #[test]
+    fn test_insert_front_jumps_group_queue() {
+        let events = [event(1, 0), event(2, 1), event(3, 1), event(4, 2)];
+        let mut queue = FairQueue::new();
+        queue.insert(&events[0]);
+        queue.insert(&events[1]);
+        queue.pop();
+
+        let pointer = queue.pointer;
+        queue.insert_front(&events[2]);
+        queue.insert_front(&events[3]);
+        assert_eq!(queue.pointer, pointer);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.group_count(), 2);
+
+        assert_eq!(queue.pop(), Some(&events[2]));
+        assert_eq!(queue.pop(), Some(&events[3]));
+        assert_eq!(queue.pop(), Some(&events[1]));
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn test_try_insert_respects_bounds() {
+        let events = [event(1, 0), event(2, 1), event(3, 0), event(4, 2)];
+
+        let mut queue = FairQueue::with_max_len(2);
+        assert_eq!(queue.try_insert(&events[0]), Ok(()));
+        assert_eq!(queue.try_insert(&events[1]), Ok(()));
+        let rejected = queue.try_insert(&events[2]).unwrap_err();
+        assert_eq!(rejected.into_inner(), &events[2]);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.group_count(), 2);
+
+        queue.pop();
+        assert_eq!(queue.try_insert(&events[3]), Ok(()));
+        assert_eq!(queue.len(), 2);
+
+        let mut queue = FairQueue::with_max_per_group(1);
+        assert_eq!(queue.try_insert(&events[0]), Ok(()));
+        assert!(queue.try_insert(&events[2]).is_err());
+        assert_eq!(queue.try_insert(&events[1]), Ok(()));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.len_of_group(&events[0]), 1);
+
+        let mut closed = FairQueue::with_limits(4, 0);
+        assert!(closed.try_insert(&events[0]).is_err());
+        assert_eq!(closed.group_count(), 0);
+
+        let mut unbounded = FairQueue::new();
+        for event in &events {
+            assert_eq!(unbounded.try_insert(event), Ok(()));
+        }
+        assert_eq!(unbounded.len(), 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_peek_all_matches_draining_clone() {
+        let events: Vec<Event> = [0, 1, 0, 2, 1, 0, 3, 2]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        let mut queue = FairQueue::new();
+        queue.extend(&events);
+        queue.pop();
+        queue.rotate_to(&events[3]);
+
+        let snapshot = queue.peek_all();
+        let drained: Vec<&Event> = queue.clone().drain().collect();
+        assert_eq!(snapshot, drained);
+        assert_eq!(queue.len(), events.len() - 1);
+        assert!(FairQueue::<Event>::new().peek_all().is_empty());
+    }
+
+    #[test]
+    fn test_iter_group_follows_dispatch_order() {
+        let events: Vec<Event> = [0, 1, 0, 2, 0, 1]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        let mut queue: FairQueue<_> = events.iter().collect();
+        queue.insert_front(&events[4]);
+        let group: Vec<&Event> = queue.iter_group(&event(0, 0)).collect();
+        assert_eq!(group.len(), queue.len_of_group(&events[0]));
+        assert_eq!(queue.iter_group(&event(0, 3)).count(), 0);
+
+        let dispatched: Vec<&Event> = core::iter::from_fn(|| queue.pop())
+            .filter(|e| e.group == 0)
+            .collect();
+        assert_eq!(group, dispatched);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_group_keys_lists_active_groups() {
+        use std::collections::HashSet;
+
+        #[derive(Debug, PartialEq)]
+        struct Job(&'static str, u32);
+
+        impl FairGroup for Job {
+            fn is_same_group(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl crate::FairKey for Job {
+            type Key = &'static str;
+
+            fn group_key(&self) -> &'static str {
+                self.0
+            }
+        }
+
+        let jobs = [
+            Job("a", 1),
+            Job("b", 2),
+            Job("a", 3),
+            Job("c", 4),
+            Job("b", 5),
+        ];
+        let mut queue = FairQueue::new();
+        queue.extend(&jobs);
+
+        let keys: Vec<&str> = queue.group_keys().collect();
+        assert_eq!(keys.len(), queue.group_count());
+        let distinct: HashSet<&str> = keys.iter().copied().collect();
+        assert_eq!(distinct, jobs.iter().map(|job| job.0).collect());
+        assert!(queue.group_keys().eq(queue.group_heads().map(|job| job.0)));
+
+        queue.clear_group(&jobs[3]);
+        assert_eq!(
+            queue.group_keys().collect::<HashSet<_>>(),
+            HashSet::from(["a", "b"])
+        );
+    }
+
+    #[test]
+    fn test_start_pointer_keeps_spacing() {
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 2, 0, 1, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        let mut seeded = FairQueue::with_start_pointer(1);
+        assert!(seeded.peek().is_none());
+        seeded.insert(&events[0]);
+        // Clamped while only one group exists.
+        assert_eq!(seeded.peek(), Some(&events[0]));
+        seeded.extend(&events[1..]);
+        assert_eq!(seeded.peek(), Some(&events[1]));
+
+        let beyond: FairQueue<_> = {
+            let mut queue = FairQueue::with_start_pointer(7);
+            queue.extend(&events);
+            queue
+        };
+        assert_eq!(beyond.peek(), Some(&events[2]));
+
+        let plain: FairQueue<_> = events.iter().collect();
+        let firsts: Vec<usize> = [plain, seeded, beyond]
+            .into_iter()
+            .map(|mut queue| {
+                assert_eq!(queue.check_fairness(), Ok(()));
+                queue.pop().map_or(usize::MAX, |e| e.group)
+            })
+            .collect();
+        assert_eq!(firsts, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_set_pointer_restores_schedule() {
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 2]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        queue.pop();
+
+        let saved = queue.pointer();
+        assert_eq!(saved, 1);
+        let expected = queue.peek();
+        queue.set_pointer(0);
+        assert_eq!(queue.peek(), Some(&events[3]));
+        queue.set_pointer(saved);
+        assert_eq!(queue.pop(), expected);
+
+        queue.set_pointer(99);
+        assert_eq!(queue.pointer(), queue.group_count() - 1);
+        let mut empty: FairQueue<Event> = FairQueue::new();
+        empty.set_pointer(5);
+        assert_eq!(empty.pointer(), 0);
+    }
+
+    #[test]
+    fn test_group_entry_deduplicates_per_group() {
+        let events: Vec<Event> = [0, 1, 0, 0, 2, 1]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        // Keep at most two items per group, with a single lookup per incoming item.
+        let mut queue = FairQueue::new();
+        for event in &events {
+            match queue.group_entry(event) {
+                GroupEntry::Occupied(mut entry) => {
+                    if entry.len() < 2 {
+                        entry.push_back(event);
+                    }
+                    assert_eq!(entry.head().group, event.group);
+                }
+                GroupEntry::Vacant(entry) => {
+                    let id = entry.insert(event);
+                    assert_eq!(queue.len_of_group_by_id(id), 1);
+                }
+            }
+        }
+
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.group_count(), 3);
+        assert!(queue.is_consistent());
+        let order: Vec<u32> = queue.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![0, 1, 4, 2, 5]);
+    }
+
+    #[test]
+    fn test_checked_insert_refuses_to_wrap_len() {
+        let events = [event(1, 0), event(2, 1)];
+        let mut queue = FairQueue::new();
+        assert_eq!(queue.checked_insert(&events[0]), Ok(()));
+
+        queue.len = usize::MAX;
+        let err = queue.checked_insert(&events[1]).unwrap_err();
+        assert_eq!(err.into_inner(), &events[1]);
+        assert_eq!(queue.len64(), usize::MAX as u64);
+        assert_eq!(queue.group_count(), 1);
+    }
+
+    #[test]
+    fn test_peek_round_rotates_with_pointer() {
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 2]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert!(queue.peek_round().into_iter().eq(queue.group_heads()));
+
+        queue.pop();
+        let round: Vec<u32> = queue.peek_round().iter().map(|e| e.timestamp).collect();
+        assert_eq!(round, vec![1, 2, 3]);
+        queue.pop();
+        let round: Vec<u32> = queue.peek_round().iter().map(|e| e.timestamp).collect();
+        assert_eq!(round, vec![2, 3, 4]);
+
+        let mut popped = Vec::new();
+        let preview = queue.peek_round();
+        queue.pop_round(&mut popped);
+        assert_eq!(preview, popped);
+        assert!(FairQueue::<Event>::new().peek_round().is_empty());
+    }
+
+    #[test]
+    fn test_pop_into_slice() {
+        let events: Vec<Event> = [0, 1, 0, 2, 1]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        let mut expected = queue.clone();
+
+        let mut short = [None; 3];
+        assert_eq!(queue.pop_into_slice(&mut short), 3);
+        for slot in short {
+            assert_eq!(slot, expected.pop());
+        }
+
+        let mut long = [Some(&events[0]); 4];
+        assert_eq!(queue.pop_into_slice(&mut long), 2);
+        assert_eq!(long[..2], [expected.pop(), expected.pop()]);
+        assert_eq!(long[2..], [None, None]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_map_view_matches_pop_and_map() {
+        let events: Vec<Event> = [0, 1, 0, 2, 1, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        queue.pop();
+
+        let projected: Vec<usize> = queue.map_view(|e| e.group).collect();
+        let mut fork = queue.clone();
+        let manual: Vec<usize> = core::iter::from_fn(|| fork.pop().map(|e| e.group)).collect();
+        assert_eq!(projected, manual);
+        assert_eq!(queue.len(), 5);
+    }
+
+    #[test]
+    fn test_is_group_present() {
+        let events = [event(1, 0), event(2, 1), event(3, 0)];
+
+        let mut queue = FairQueue::new();
+        assert!(!queue.is_group_present(&events[0]));
+        queue.insert(&events[0]);
+        assert!(queue.is_group_present(&events[2]));
+        assert!(!queue.is_group_present(&events[1]));
+        queue.insert(&events[1]);
+        assert!(queue.is_group_present(&event(9, 1)));
+
+        queue.pop();
+        assert!(!queue.is_group_present(&events[0]));
+        assert!(queue.is_group_present(&events[1]));
+    }
+
+    #[test]
+    fn test_pop_with_group_index_reports_served_slot() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 1),
+            event(5, 2),
+        ];
+
+        let mut queue: FairQueue<_> = events.iter().collect();
+        let mut served = Vec::new();
+        while !queue.is_empty() {
+            let heads: Vec<&Event> = queue.group_heads().collect();
+            let (item, slot) = queue.pop_with_group_index().unwrap();
+            assert_eq!(heads[slot], item);
+            served.push((item.timestamp, slot));
+        }
+        // Group 0 empties first and the others move up a position.
+        assert_eq!(served, vec![(1, 0), (2, 0), (3, 1), (4, 0), (5, 0)]);
+        assert_eq!(queue.pop_with_group_index(), None);
+    }
+
+    #[test]
+    fn test_eq_compares_pop_order() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 1),
+            event(5, 2),
+        ];
+
+        // Popping the only item of group 0 swaps group 2 into slot 0.
+        let mut swapped: FairQueue<_> = events.iter().collect();
+        assert_eq!(swapped.pop(), Some(&events[0]));
+
+        let mut rotated = FairQueue::new();
+        for &idx in &[2, 1, 4, 3] {
+            rotated.insert(&events[idx]);
+        }
+        assert_ne!(swapped, rotated);
+        assert!(rotated.rotate_to(&events[1]));
+        assert_eq!(swapped, rotated);
+
+        let mut reinserted = FairQueue::new();
+        for event in &events[1..] {
+            reinserted.insert(event);
+        }
+        assert_eq!(swapped, reinserted);
+
+        reinserted.pop();
+        assert_ne!(swapped, reinserted);
+        assert_eq!(FairQueue::<Event>::new(), FairQueue::new());
+    }
+
+    #[test]
+    fn test_max_groups_policies() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 0),
+            event(5, 3),
+        ];
+
+        let mut rejecting = FairQueue::with_max_groups(2);
+        assert_eq!(rejecting.try_insert(&events[0]), Ok(()));
+        assert_eq!(rejecting.try_insert(&events[1]), Ok(()));
+        assert_eq!(
+            rejecting.try_insert(&events[2]).unwrap_err().into_inner(),
+            &events[2]
+        );
+        assert_eq!(rejecting.try_insert(&events[3]), Ok(()));
+        assert_eq!(rejecting.len(), 3);
+        assert_eq!(rejecting.group_count(), 2);
+
+        // Group 1 becomes the overflow group: it is the newest one when group 2 arrives.
+        let mut merging = FairQueue::with_group_overflow(2, GroupOverflow::MergeIntoNewest);
+        for event in &events {
+            assert_eq!(merging.try_insert(event), Ok(()));
+        }
+        assert_eq!(merging.len(), events.len());
+        assert_eq!(merging.group_count(), 2);
+        assert_eq!(merging.len_of_group(&events[1]), 3);
+        let order: Vec<u32> = merging.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![1, 2, 4, 3, 5]);
+
+        let mut closed = FairQueue::with_group_overflow(0, GroupOverflow::MergeIntoNewest);
+        assert!(closed.try_insert(&events[0]).is_err());
+        assert!(closed.is_empty());
+    }
+
+    #[test]
+    fn test_max_groups_bound_every_insert() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 0),
+            event(5, 3),
+        ];
+
+        let mut rejecting = FairQueue::with_max_groups(2);
+        for event in &events {
+            rejecting.insert(event);
+        }
+        assert_eq!(rejecting.len(), 3);
+        assert_eq!(rejecting.group_count(), 2);
+        assert!(!rejecting.insert_tracked(&events[2]));
+        rejecting.insert_front(&events[4]);
+        assert_eq!(rejecting.try_insert_strict(&events[2]), Ok(()));
+        assert_eq!(rejecting.try_insert_grouped(&events[4]), Ok(()));
+        let id = rejecting.insert_with_id(&events[2]);
+        assert_eq!(rejecting.len_of_group_by_id(id), 0);
+        assert_eq!(rejecting.len(), 3);
+        assert!(rejecting.is_consistent());
+
+        let mut merging = FairQueue::with_group_overflow(2, GroupOverflow::MergeIntoNewest);
+        merging.extend(&events);
+        assert_eq!(merging.len(), events.len());
+        assert_eq!(merging.group_count(), 2);
+        assert_eq!(merging.len_of_group(&events[1]), 3);
+        let order: Vec<u32> = merging.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![1, 2, 4, 3, 5]);
+
+        let mut source = FairQueue::new();
+        source.extend(&events);
+        let mut appended = FairQueue::with_max_groups(2);
+        appended.append(&mut source);
+        assert_eq!(appended.group_count(), 2);
+        assert_eq!(appended.len(), 3);
+    }
+
+    #[test]
+    fn test_capacity_policy_at_the_boundary() {
+        let events = [
+            event(0, 0),
+            event(1, 0),
+            event(2, 1),
+            event(3, 1),
+            event(4, 2),
+        ];
+
+        // The fourth insert evicts event 0, the fifth evicts event 2 from the next group.
+        let mut oldest = FairQueue::with_capacity_policy(3, OverflowPolicy::DropOldest);
+        for event in &events[..3] {
+            oldest.insert(event);
+        }
+        assert_eq!(oldest.len(), 3);
+        oldest.insert(&events[3]);
+        assert_eq!(oldest.len(), 3);
+        oldest.insert(&events[4]);
+        assert_eq!(oldest.len(), 3);
+        assert!(oldest.try_insert(&events[0]).is_err());
+        let order: Vec<u32> = oldest.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![1, 3, 4]);
+
+        let mut newest = FairQueue::with_capacity_policy(3, OverflowPolicy::DropNewest);
+        for event in &events {
+            newest.insert(event);
+        }
+        assert_eq!(newest.len(), 3);
+        let order: Vec<u32> = newest.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![0, 2, 1]);
+
+        let mut closed = FairQueue::with_capacity_policy(0, OverflowPolicy::DropOldest);
+        closed.insert(&events[0]);
+        assert!(closed.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_policy_covers_every_insert_path() {
+        let events = [
+            event(0, 0),
+            event(1, 0),
+            event(2, 1),
+            event(3, 1),
+            event(4, 2),
+        ];
+
+        let mut newest = FairQueue::with_capacity_policy(2, OverflowPolicy::DropNewest);
+        assert!(newest.insert_tracked(&events[0]));
+        assert!(newest.insert_tracked(&events[2]));
+        assert!(!newest.insert_tracked(&events[4]));
+        newest.insert_front(&events[1]);
+        let mut other: FairQueue<_> = events[3..].iter().collect();
+        newest.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(newest.len(), 2);
+        let order: Vec<u32> = newest.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![0, 2]);
+
+        // Each insert past the cap pops the next item in fair order first.
+        let mut oldest = FairQueue::with_capacity_policy(2, OverflowPolicy::DropOldest);
+        assert!(oldest.insert_tracked(&events[0]));
+        assert!(oldest.insert_tracked(&events[2]));
+        assert!(oldest.insert_tracked(&events[4]));
+        assert_eq!(oldest.len(), 2);
+        oldest.insert_front(&events[3]);
+        assert_eq!(oldest.len(), 2);
+        assert_eq!(oldest.peek(), Some(&events[4]));
+        let mut other: FairQueue<_> = events[..2].iter().collect();
+        oldest.append(&mut other);
+        assert_eq!(oldest.len(), 2);
+        let order: Vec<u32> = oldest.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_append_concatenates_groups() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 0),
+            event(4, 1),
+            event(5, 2),
+            event(6, 0),
+        ];
+
+        let mut first: FairQueue<_> = events[..2].iter().collect();
+        let mut second: FairQueue<_> = events[2..].iter().collect();
+        first.append(&mut second);
+
+        assert!(second.is_empty());
+        assert_eq!(second.group_count(), 0);
+        assert_eq!(first.len(), 6);
+        assert_eq!(first.group_count(), 3);
+        assert_eq!(first.len_of_group(&events[0]), 3);
+
+        let order: Vec<u32> = first.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![1, 2, 5, 3, 4, 6]);
+        for group in 0..3 {
+            let timestamps: Vec<u32> = order
+                .iter()
+                .copied()
+                .filter(|&t| events[t as usize - 1].group == group)
+                .collect();
+            assert!(timestamps.is_sorted());
+        }
+    }
+
+    #[test]
+    fn test_drain_into_follows_dispatch_order() {
+        let events: Vec<Event> = [0, 1, 0, 2, 0, 1]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        let mut src: FairQueue<_> = events[1..].iter().collect();
+        src.pop();
+        let dispatch: Vec<&Event> = src.iter().collect();
+        let capacity = src.groups.capacity();
+
+        let mut dst = FairQueue::new();
+        dst.insert(&events[0]);
+        src.drain_into(&mut dst);
+        assert!(src.is_empty());
+        assert_eq!(src.groups.capacity(), capacity);
+
+        let mut expected = FairQueue::new();
+        expected.insert(&events[0]);
+        expected.extend(dispatch);
+        assert_eq!(dst.len(), 5);
+        assert!(dst.into_iter().eq(expected));
+
+        src.insert(&events[0]);
+        assert_eq!(src.pop(), Some(&events[0]));
+    }
+
+    #[test]
+    fn test_burst_drains_each_group_in_one_run() {
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 0, 2, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+        let mut queue = FairQueue::with_burst(true);
+        queue.extend(&events);
+        let replay: Vec<u32> = queue.iter().map(|e| e.timestamp).collect();
+        assert_eq!(replay, vec![0, 3, 5, 7, 1, 4, 2, 6]);
+
+        assert_eq!(queue.pop(), Some(&events[0]));
+        // A late arrival for the group being drained joins the current burst.
+        let late = event(8, 0);
+        queue.insert(&late);
+        let groups: Vec<usize> = core::iter::from_fn(|| queue.pop())
+            .map(|e| e.group)
+            .collect();
+        assert_eq!(groups, vec![0, 0, 0, 0, 1, 1, 2, 2]);
+
+        let mut queue = FairQueue::with_burst(true);
+        queue.extend(&events);
+        let (mut kept, _) = queue.partition(|e| e.group != 1);
+        let order: Vec<u32> = core::iter::from_fn(|| kept.pop())
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(order, vec![0, 3, 5, 7, 2, 6]);
+
+        // A round still takes one item per group; the burst then resumes on group 0.
+        let mut queue = FairQueue::with_burst(true);
+        queue.extend(&events);
+        let mut round = Vec::new();
+        assert_eq!(queue.pop_round(&mut round), 3);
+        let round: Vec<u32> = round.iter().map(|e| e.timestamp).collect();
+        assert_eq!(round, vec![0, 1, 2]);
+        let order: Vec<u32> = core::iter::from_fn(|| queue.pop())
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(order, vec![3, 5, 7, 4, 6]);
+    }
+
+    #[test]
+    fn test_removal_keeps_group_order() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 3),
+            event(5, 1),
+            event(6, 2),
+            event(7, 3),
+        ];
+
+        let mut queue = FairQueue::new();
+        queue.extend(&events);
+
+        // Group 0 empties first; the storage swaps group 3 into its slot, the rotation not.
+        assert_eq!(queue.pop(), Some(&events[0]));
+        let heads: Vec<u32> = queue.group_heads().map(|e| e.timestamp).collect();
+        assert_eq!(heads, vec![2, 3, 4]);
+
+        let scheduled: Vec<u32> = queue.scheduled().map(|(_, e)| e.timestamp).collect();
+        let order: Vec<u32> = queue.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![2, 3, 4, 5, 6, 7]);
+        assert_eq!(scheduled, order);
+    }
+
+    #[test]
+    fn test_iter_matches_full_pop_sequence() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 0),
+            event(4, 2),
+            event(5, 1),
+            event(6, 0),
+            event(7, 0),
+        ];
+
+        let mut queue: FairQueue<_> = events.iter().collect();
+        queue.pop();
+
+        let iter = queue.iter();
+        assert_eq!(iter.len(), queue.len());
+        let previewed: Vec<_> = iter.collect();
+        let popped: Vec<_> = queue.clone().into_iter().collect();
+        assert_eq!(previewed, popped);
+        assert_eq!((&queue).into_iter().count(), 6);
+        assert_eq!(queue.len(), 6);
+    }
+
+    #[test]
+    fn test_peek_nth_matches_cloned_pops() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 0),
+            event(4, 2),
+            event(5, 1),
+            event(6, 0),
+            event(7, 3),
+            event(8, 0),
+        ];
+
+        let mut queue: FairQueue<_> = events.iter().collect();
+        queue.pop();
+
+        for k in 0..queue.len() {
+            let mut fork = queue.clone();
+            let expected = (0..=k).filter_map(|_| fork.pop()).last();
+            assert_eq!(queue.peek_nth(k), expected);
+        }
+        assert_eq!(queue.peek_nth(queue.len()), None);
+        assert_eq!(queue.peek_nth(0), queue.peek());
+    }
+
+    #[test]
+    fn test_static_queue_in_struct() {
+        static EVENTS: [Event; 4] = [
+            Event {
+                timestamp: 1,
+                user_id: "user1",
+                group: 0,
+            },
+            Event {
+                timestamp: 2,
+                user_id: "user1",
+                group: 0,
+            },
+            Event {
+                timestamp: 3,
+                user_id: "user2",
+                group: 1,
+            },
+            Event {
+                timestamp: 4,
+                user_id: "user2",
+                group: 1,
+            },
+        ];
+
+        struct Service {
+            pending: StaticFairQueue<Event>,
+        }
+
+        let mut service = Service {
+            pending: StaticFairQueue::from_slice(&EVENTS),
+        };
+        assert_eq!(service.pending.len(), 4);
+        assert_eq!(service.pending.group_count(), 2);
+
+        let order: Vec<u32> = core::iter::from_fn(|| service.pending.pop())
+            .map(|event| event.timestamp)
+            .collect();
+        assert_eq!(order, vec![1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn test_unsized_trait_objects() {
+        trait Job {
+            fn tenant(&self) -> u32;
+            fn id(&self) -> u32;
+        }
+
+        struct Email(u32, u32);
+        struct Report(u32, u32);
+
+        impl Job for Email {
+            fn tenant(&self) -> u32 {
+                self.0
+            }
+            fn id(&self) -> u32 {
+                self.1
+            }
+        }
+
+        impl Job for Report {
+            fn tenant(&self) -> u32 {
+                self.0
+            }
+            fn id(&self) -> u32 {
+                self.1
+            }
+        }
+
+        impl FairGroup for dyn Job {
+            fn is_same_group(&self, other: &Self) -> bool {
+                self.tenant() == other.tenant()
+            }
+        }
+
+        let (email, report, other_email, other_report) =
+            (Email(1, 0), Report(1, 1), Email(2, 2), Report(1, 3));
+        let jobs: [&dyn Job; 4] = [&email, &report, &other_email, &other_report];
+
+        let mut queue: FairQueue<dyn Job> = FairQueue::new();
+        for job in jobs {
+            queue.insert(job);
+        }
+        assert_eq!(queue.group_count(), 2);
+        assert_eq!(queue.peek().map(|job| job.id()), Some(0));
+        assert_eq!(queue.check_fairness(), Ok(()));
+
+        let order: Vec<u32> = queue.into_iter().map(|job| job.id()).collect();
+        assert_eq!(order, vec![0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_drain_filter_removes_odd_items() {
+        let events: Vec<Event> = (0..10).map(|idx| event(idx, (idx % 3) as usize)).collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        let expected: Vec<u32> = queue
+            .iter()
+            .map(|e| e.timestamp)
+            .filter(|ts| ts % 2 == 1)
+            .collect();
+
+        // Stopping after one item removes only that item.
+        let first = queue.drain_filter(|e| e.timestamp % 2 == 1).next();
+        assert_eq!(first.map(|e| e.timestamp), Some(expected[0]));
+        assert_eq!(queue.len(), 9);
+
+        let mut odd: Vec<u32> = queue
+            .drain_filter(|e| e.timestamp % 2 == 1)
+            .map(|e| e.timestamp)
+            .collect();
+        odd.sort_unstable();
+        assert_eq!(odd, vec![3, 5, 7, 9]);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.check_fairness(), Ok(()));
+
+        let mut survivors: Vec<u32> = queue.into_iter().map(|e| e.timestamp).collect();
+        survivors.sort_unstable();
+        assert_eq!(survivors, vec![0, 2, 4, 6, 8]);
+    }
+
+    #[test]
+    fn test_try_insert_grouped_rejects_malformed_items() {
+        struct Record(&'static str);
+
+        impl TryFairGroup for Record {
+            fn try_is_same_group(&self, other: &Self) -> Result<bool, GroupError> {
+                let tenant = |record: &Record| {
+                    record
+                        .0
+                        .split_once(':')
+                        .map(|(tenant, _)| tenant)
+                        .ok_or(GroupError::new("missing tenant"))
+                };
+                Ok(tenant(self)? == tenant(other)?)
+            }
+        }
+
+        let records = [
+            Record("a:1"),
+            Record("b:1"),
+            Record("a:2"),
+            Record("broken"),
+        ];
+        let mut queue = FairQueue::new();
+        for record in &records[..3] {
+            assert_eq!(queue.try_insert_grouped(record), Ok(()));
+        }
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.group_count(), 2);
+
+        let err = queue.try_insert_grouped(&records[3]).unwrap_err();
+        assert_eq!(err.reason(), "missing tenant");
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.group_count(), 2);
+
+        let order: Vec<&str> = core::iter::from_fn(|| queue.pop())
+            .map(|record| record.0)
+            .collect();
+        assert_eq!(order, vec!["a:1", "b:1", "a:2"]);
+
+        // `FairGroup` types go through the infallible blanket impl.
+        let events = [event(0, 0), event(1, 0)];
+        let mut plain = FairQueue::new();
+        for event in &events {
+            assert_eq!(plain.try_insert_grouped(event), Ok(()));
+        }
+        assert_eq!(plain.group_count(), 1);
+    }
+
+    #[test]
+    fn test_group_at_pointer_follows_removals() {
+        let events = [
+            event(0, 0),
+            event(1, 1),
+            event(2, 1),
+            event(3, 2),
+            event(4, 2),
+            event(5, 2),
+        ];
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.group_at_pointer(), Some((0, 1)));
+
+        // Emptying group 0 moves the pointer to group 1, now first in the rotation.
+        queue.pop();
+        assert_eq!(queue.group_at_pointer(), Some((0, 2)));
+        queue.pop();
+        assert_eq!(queue.group_at_pointer(), Some((1, 3)));
+        queue.pop();
+        assert_eq!(queue.group_at_pointer(), Some((0, 1)));
+        queue.pop();
+        assert_eq!(queue.group_at_pointer(), Some((0, 2)));
+        queue.pop();
+        assert_eq!(queue.group_at_pointer(), Some((0, 1)));
+        queue.pop();
+        assert_eq!(queue.group_at_pointer(), None);
+    }
+
+    #[test]
+    fn test_pop_cloned_matches_pop() {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        struct Tick {
+            sensor: u8,
+            value: u32,
+        }
+
+        impl FairGroup for Tick {
+            fn is_same_group(&self, other: &Self) -> bool {
+                self.sensor == other.sensor
+            }
+        }
+
+        let ticks: Vec<Tick> = (0..6)
+            .map(|value| Tick {
+                sensor: (value % 2) as u8,
+                value,
+            })
+            .collect();
+        let mut cloned: FairQueue<_> = ticks.iter().collect();
+        let mut borrowed = cloned.clone();
+
+        while let Some(expected) = borrowed.pop() {
+            assert_eq!(cloned.peek_cloned().as_ref(), Some(expected));
+            assert_eq!(cloned.pop_cloned().as_ref(), Some(expected));
+        }
+        assert_eq!(cloned.pop_cloned(), None);
+        assert_eq!(cloned.peek_cloned(), None);
+    }
+
+    #[test]
+    fn test_group_order_reflects_arrival() {
+        let events = [
+            event(0, 0),
+            event(1, 1),
+            event(2, 2),
+            event(3, 2),
+            event(4, 3),
+            event(5, 0),
+        ];
+        let mut queue = FairQueue::new();
+        queue.extend(&events[..4]);
+        assert_eq!(queue.group_order(), vec![0, 1, 2]);
+
+        // Emptying group 0 swaps group 2 into slot 0, but the rotation keeps the arrival order.
+        queue.pop();
+        queue.insert(&events[4]);
+        assert_eq!(queue.group_order(), vec![0, 1, 2]);
+
+        // Group 0 returns as the newest group.
+        queue.insert(&events[5]);
+        assert_eq!(queue.group_order(), vec![0, 1, 2, 3]);
+        let heads: Vec<usize> = queue.group_heads().map(|e| e.group).collect();
+        assert_eq!(heads, vec![1, 2, 3, 0]);
+
+        queue.clear();
+        assert!(queue.group_order().is_empty());
+        queue.insert(&events[0]);
+        assert_eq!(queue.group_order(), vec![0]);
+    }
+
+    #[test]
+    fn test_try_pop_group_keeps_rotation() {
+        let events = [
+            event(0, 0),
+            event(1, 1),
+            event(2, 2),
+            event(3, 0),
+            event(4, 1),
+            event(5, 2),
+            event(6, 2),
+        ];
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.pop(), Some(&events[0]));
+
+        // Group 2 is flushed out of turn; group 1 is still next.
+        assert_eq!(queue.try_pop_group(&events[2]), Some(&events[2]));
+        assert_eq!(queue.pointer(), 1);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.try_pop_group(&event(9, 3)), None);
+
+        let order: Vec<u32> = queue.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![1, 5, 3, 4, 6]);
+
+        // Emptying the group under the pointer moves it to the next surviving group.
+        let pair = [event(0, 0), event(1, 1)];
+        let mut queue: FairQueue<_> = pair.iter().collect();
+        assert_eq!(queue.try_pop_group(&pair[0]), Some(&pair[0]));
+        assert_eq!(queue.group_at_pointer(), Some((0, 1)));
+        assert_eq!(queue.pop(), Some(&pair[1]));
+    }
+
+    #[test]
+    fn test_peek_group_nth_indexes_one_group() {
+        let events = [event(0, 0), event(1, 1), event(2, 0), event(3, 0)];
+        let queue: FairQueue<_> = events.iter().collect();
+
+        assert_eq!(queue.peek_group_nth(&events[0], 0), Some(&events[0]));
+        assert_eq!(queue.peek_group_nth(&events[3], 1), Some(&events[2]));
+        assert_eq!(queue.peek_group_nth(&events[0], 2), Some(&events[3]));
+        assert_eq!(queue.peek_group_nth(&events[0], 3), None);
+        assert_eq!(queue.peek_group_nth(&events[1], 1), None);
+        assert_eq!(queue.peek_group_nth(&event(4, 2), 0), None);
+        assert!(
+            (0..3)
+                .map_while(|n| queue.peek_group_nth(&events[0], n))
+                .eq(queue.iter_group(&events[0]))
+        );
+    }
+
+    #[test]
+    fn test_fold_groups_sums_per_group() {
+        let events: Vec<Event> = (0..11).map(|idx| event(idx, (idx % 3) as usize)).collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        queue.pop();
+
+        let sums = queue.fold_groups(|| 0, |sum, event| sum + event.timestamp);
+        let manual: Vec<u32> = queue
+            .groups_view()
+            .map(|group| group.iter().map(|event| event.timestamp).sum())
+            .collect();
+        assert_eq!(sums, manual);
+        assert_eq!(sums.len(), queue.group_count());
+        assert_eq!(sums.iter().sum::<u32>(), (1..11).sum::<u32>());
+
+        let empty: FairQueue<Event> = FairQueue::new();
+        assert!(empty.fold_groups(|| 0, |n, _| n + 1).is_empty());
+    }
+
+    #[test]
+    fn test_pop_newest_undoes_insert() {
+        let events = [event(0, 0), event(1, 1), event(2, 0), event(3, 1)];
+        let mut queue = FairQueue::new();
+        queue.insert(&events[0]);
+        queue.insert(&events[1]);
+
+        // Group 0 is up next, so its newest item is the one just inserted.
+        queue.insert(&events[2]);
+        assert_eq!(queue.pop_newest(), Some(&events[2]));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pointer(), 1);
+
+        queue.insert(&events[3]);
+        assert_eq!(queue.pop_newest(), Some(&events[3]));
+        assert_eq!(queue.pop(), Some(&events[0]));
+        assert_eq!(queue.pop_newest(), Some(&events[1]));
+        assert_eq!(queue.pop_newest(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_estimate_memory_tracks_capacity() {
+        let mut queue = FairQueue::new();
+        assert_eq!(queue.estimate_memory(), 0);
+
+        let events: Vec<Event> = (0..64).map(|idx| event(idx, (idx % 4) as usize)).collect();
+        queue.extend(&events);
+        let full = queue.estimate_memory();
+        assert!(full >= 64 * mem::size_of::<&Event>());
+
+        for _ in 0..60 {
+            queue.pop();
+        }
+        assert_eq!(queue.estimate_memory(), full);
+        queue.shrink_to_fit();
+        assert!(queue.estimate_memory() < full);
+        assert!(queue.estimate_memory() >= 4 * mem::size_of::<&Event>());
+    }
+
+    #[test]
+    fn test_recycle_reuses_allocations_across_epochs() {
+        let events: Vec<Event> = (0..32).map(|idx| event(idx, (idx % 4) as usize)).collect();
+        let capacities = |queue: &FairQueue<'_, Event>| {
+            let buffers: Vec<usize> = queue
+                .groups
+                .iter()
+                .chain(&queue.spare)
+                .map(VecDeque::capacity)
+                .collect();
+            (queue.groups.capacity(), queue.ids.capacity(), buffers)
+        };
+
+        let mut queue = FairQueue::new();
+        queue.extend(&events);
+        queue.recycle();
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+        let after_first = capacities(&queue);
+        assert_eq!(after_first.2.len(), 4);
+
+        queue.extend(&events);
+        assert_eq!(queue.spare.len(), 0);
+        assert_eq!(queue.len(), 32);
+        assert_eq!(queue.group_count(), 4);
+        queue.recycle();
+        let mut after_second = capacities(&queue);
+        after_second.2.sort_unstable();
+        let mut expected = after_first;
+        expected.2.sort_unstable();
+        assert_eq!(after_second, expected);
+
+        queue.clear();
+        assert!(queue.spare.is_empty());
+    }
+
+    #[test]
+    fn test_drain_rounds_by_key_sorts_each_round() {
+        let events = [
+            event(5, 0),
+            event(3, 1),
+            event(9, 2),
+            event(1, 0),
+            event(8, 1),
+            event(2, 1),
+        ];
+        let queue: FairQueue<_> = events.iter().collect();
+
+        let order: Vec<(u32, usize)> = queue
+            .drain_rounds_by_key(|e| e.timestamp)
+            .into_iter()
+            .map(|e| (e.timestamp, e.group))
+            .collect();
+        // Rounds: {5, 3, 9}, {1, 8}, {2}.
+        assert_eq!(order, vec![(3, 1), (5, 0), (9, 2), (1, 0), (8, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn test_rotate_left_and_right() {
+        let events = [event(0, 0), event(1, 1), event(2, 2), event(3, 0)];
+        let mut queue: FairQueue<_> = events.iter().collect();
+
+        queue.rotate_left(1);
+        assert_eq!(queue.peek(), Some(&events[1]));
+        queue.rotate_left(5);
+        assert_eq!(queue.peek(), Some(&events[0]));
+        queue.rotate_right(1);
+        assert_eq!(queue.peek(), Some(&events[2]));
+        queue.rotate_right(7);
+        assert_eq!(queue.peek(), Some(&events[1]));
+        assert_eq!(queue.len(), 4);
+
+        let mut empty: FairQueue<Event> = FairQueue::new();
+        empty.rotate_left(3);
+        empty.rotate_right(3);
+        assert_eq!(empty.pointer(), 0);
+    }
+
+    #[test]
+    fn test_next_group_head_matches_pop() {
+        let events = [event(0, 0), event(1, 0), event(2, 1), event(3, 0)];
+        let mut queue: FairQueue<_> = events.iter().collect();
+        let mut longest = FairQueue::with_scheduler(LongestQueueFirst);
+        longest.extend(&events);
+
+        let mut lens = Vec::new();
+        while let Some(head) = queue.next_group_head() {
+            lens.push(queue.next_group_len());
+            assert_eq!(queue.pop(), Some(head));
+        }
+        assert_eq!(lens, vec![Some(3), Some(1), Some(2), Some(1)]);
+        assert_eq!(queue.next_group_len(), None);
+
+        while let Some(head) = longest.next_group_head() {
+            assert_eq!(longest.next_group_len(), longest.group_lens().max());
+            assert_eq!(longest.pop(), Some(head));
+        }
+    }
+
+    #[test]
+    fn test_pop_detailed_flags_forced_repeat() {
+        let events = [event(0, 0), event(1, 0), event(2, 0), event(3, 1)];
+        let mut queue: FairQueue<_> = events.iter().collect();
+
+        let flags: Vec<(u32, bool)> = core::iter::from_fn(|| queue.pop_detailed())
+            .map(|outcome| (outcome.item.timestamp, outcome.repeated_group))
+            .collect();
+        assert_eq!(flags, vec![(0, false), (3, false), (1, false), (2, true)]);
+        assert_eq!(queue.pop_detailed(), None);
+    }
+
+    #[test]
+    fn test_replace_next_updates_the_pending_head() {
+        let events = [event(1, 0), event(2, 1), event(3, 0)];
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.pop(), Some(&events[0]));
+
+        // The next pop serves group 1; another group's item is handed back.
+        let stranger = event(9, 0);
+        assert_eq!(queue.replace_next(&stranger), Err(&stranger));
+        let updated = event(20, 1);
+        assert_eq!(queue.replace_next(&updated), Ok(&events[1]));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.peek(), Some(&updated));
+
+        assert_eq!(queue.pop(), Some(&updated));
+        assert_eq!(queue.pop(), Some(&events[2]));
+        assert_eq!(queue.replace_next(&updated), Err(&updated));
+    }
+
+    #[test]
+    fn test_replace_group_swaps_backlog() {
+        let events = [
+            event(0, 0),
+            event(1, 0),
+            event(2, 0),
+            event(3, 1),
+            event(4, 1),
+            event(5, 2),
+        ];
+        let snapshot = [event(10, 1), event(11, 1), event(12, 1)];
+        let mut queue: FairQueue<_> = events.iter().collect();
+
+        assert_eq!(queue.replace_group(&events[3], &snapshot), 2);
+        assert_eq!(queue.len(), 7);
+        assert_eq!(queue.group_count(), 3);
+        assert_eq!(queue.len_of_group(&snapshot[0]), 3);
+
+        let order: Vec<u32> = queue.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![0, 10, 5, 1, 11, 2, 12]);
+
+        let absent = [event(20, 3)];
+        assert_eq!(queue.replace_group(&absent[0], &absent), 0);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.replace_group(&absent[0], []), 1);
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+    }
+
+    #[test]
+    fn test_partition_by_group_parity() {
+        let events: Vec<Event> = (0..12).map(|idx| event(idx, (idx % 4) as usize)).collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        queue.pop();
+
+        let (even, odd) = queue.partition(|event| event.group % 2 == 0);
+        assert_eq!(even.len() + odd.len(), 11);
+        assert_eq!(even.group_count(), 2);
+        assert_eq!(odd.group_count(), 2);
+        assert_eq!(even.check_fairness(), Ok(()));
+        assert_eq!(odd.check_fairness(), Ok(()));
+
+        // The rotation resumes at group 1 in `odd` and at group 2 in `even`.
+        let even: Vec<u32> = even.into_iter().map(|e| e.timestamp).collect();
+        let odd: Vec<u32> = odd.into_iter().map(|e| e.timestamp).collect();
+        assert_eq!(even, vec![2, 4, 6, 8, 10]);
+        assert_eq!(odd, vec![1, 3, 5, 7, 9, 11]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_hashmap_matches_individual_inserts() {
+        let events: Vec<Event> = (0..10).map(|idx| event(idx, (idx % 3) as usize)).collect();
+        let mut map: std::collections::HashMap<usize, VecDeque<&Event>> =
+            std::collections::HashMap::new();
+        for event in &events {
+            map.entry(event.group).or_default().push_back(event);
+        }
+        map.insert(7, VecDeque::new());
+
+        // Inserting bucket by bucket in map order builds the same slots.
+        let mut inserted = FairQueue::new();
+        for bucket in map.values() {
+            inserted.extend(bucket.iter().copied());
+        }
+
+        let queue = FairQueue::from_hashmap(map);
+        assert_eq!(queue.len(), events.len());
+        assert_eq!(queue.group_count(), 3);
+        assert_eq!(queue.check_fairness(), Ok(()));
+        assert!(queue.into_iter().eq(inserted));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_count_by_sums_to_len() {
+        let events: Vec<Event> = (0..10).map(|idx| event(idx, (idx % 3) as usize)).collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        queue.pop();
+
+        let parity = queue.count_by(|event| event.timestamp % 2);
+        assert_eq!(parity.len(), 2);
+        assert_eq!(parity[&0], 4);
+        assert_eq!(parity[&1], 5);
+        assert_eq!(parity.values().sum::<usize>(), queue.len());
+
+        let groups = queue.count_by(|event| event.group);
+        assert_eq!(groups.values().sum::<usize>(), queue.len());
+        assert!(
+            queue
+                .group_lens()
+                .all(|len| groups.values().any(|&count| count == len))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_capacity_report() {
+        let mut queue = FairQueue::new();
+        assert_eq!(queue.capacity_report(), crate::CapacityReport::default());
+
+        let events: Vec<Event> = (0..12).map(|idx| event(idx, (idx % 3) as usize)).collect();
+        for event in &events {
+            queue.insert(event);
+        }
+        for _ in 0..5 {
+            queue.pop();
+        }
+
+        let report = queue.capacity_report();
+        assert_eq!(report.groups_len, 3);
+        assert!(report.groups_capacity >= 3);
+        assert_eq!(report.items, 7);
+        assert!(report.min_group_capacity <= report.max_group_capacity);
+        assert_eq!(report.wasted_capacity, report.total_group_capacity - 7);
+
+        let rendered = report.to_string();
+        assert!(rendered.contains("items 7/"));
+        assert!(rendered.contains(&format!("wasted {}", report.wasted_capacity)));
+    }
+
+    #[test]
+    fn test_pop_guard_commit_and_rollback() {
+        let a1 = event(1, 0);
+        let a2 = event(2, 0);
+        let b1 = event(3, 1);
+
+        let mut queue = FairQueue::new();
+        assert!(queue.pop_guard().is_none());
+
+        queue.insert(&a1);
+        queue.insert(&a2);
+        queue.insert(&b1);
+
+        {
+            let guard = queue.pop_guard().unwrap();
+            assert_eq!(guard.timestamp, 1);
+        }
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.peek(), Some(&a1));
+
+        queue.pop_guard().unwrap().rollback();
+        assert_eq!(queue.len(), 3);
+
+        assert_eq!(queue.pop_guard().unwrap().commit(), &a1);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(&b1));
+        assert_eq!(queue.pop(), Some(&a2));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_service_share() {
+        let a1 = event(1, 0);
+        let a2 = event(2, 0);
+        let a3 = event(3, 0);
+        let b1 = event(4, 1);
+        let missing = event(5, 2);
+
+        let mut queue = FairQueue::new();
+        assert_eq!(queue.service_share(&a1), 0.0);
+
+        queue.insert(&a1);
+        queue.insert(&a2);
+        queue.insert(&a3);
+        queue.insert(&b1);
+
+        assert_eq!(queue.service_share(&a1), 0.75);
+        assert_eq!(queue.service_share(&b1), 0.25);
+        assert_eq!(queue.service_share(&missing), 0.0);
+
+        queue.pop();
+        assert_eq!(queue.service_share(&a2), 2.0 / 3.0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_pop_proportional_follows_backlog() {
+        let mut events = Vec::new();
+        for (group, count) in [(0usize, 6u32), (1, 3), (2, 1)] {
+            for idx in 0..count {
+                events.push(event(idx, group));
+            }
+        }
+
+        let mut queue = FairQueue::new();
+        for event in &events {
+            queue.insert(event);
+        }
+
+        // Shares of 5 over 6/3/1 are 3, 1.5 and 0.5; the tie on the leftover
+        // goes to group 1 because it comes first in the rotation.
+        let batch = queue.pop_proportional(5);
+        let groups: Vec<usize> = batch.iter().map(|event| event.group).collect();
+        assert_eq!(groups, vec![0, 1, 0, 1, 0]);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.group_count(), 3);
+        assert_eq!(queue.peek().map(|event| event.group), Some(0));
+
+        let rest = queue.pop_proportional(100);
+        assert_eq!(rest.len(), 5);
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+        assert!(queue.pop_proportional(3).is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    proptest! {
+        #[test]
+        fn prop_pop_proportional_tracks_backlog(
+            groups in proptest::collection::vec(0usize..4, 1..64),
+            total in 0usize..80,
+        ) {
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, group)| event(idx as u32, *group))
+                .collect();
+
+            let mut queue = FairQueue::new();
+            for event in &events {
+                queue.insert(event);
+            }
+
+            let mut backlog = [0usize; 4];
+            for event in &events {
+                backlog[event.group] += 1;
+            }
+
+            let batch = queue.pop_proportional(total);
+            let take = total.min(events.len());
+            prop_assert_eq!(batch.len(), take);
+            prop_assert_eq!(queue.len(), events.len() - take);
+
+            let mut taken = [0usize; 4];
+            for event in &batch {
+                taken[event.group] += 1;
+            }
+            for group in 0..4 {
+                let ideal = take * backlog[group] / events.len();
+                prop_assert!(taken[group] >= ideal && taken[group] <= ideal + 1);
+            }
+        }
+    }
+
+    /// Calls every `&self` inspector and asserts that none of them moved the pointer,
+    /// the length or any group.
+    fn inspect_all<S: Scheduler + Clone>(queue: &FairQueue<'_, Event, S>, probe: &Event) {
+        let snapshot = |queue: &FairQueue<'_, Event, S>| {
+            (
+                queue.pointer(),
+                queue.len(),
+                queue.last_served_group(),
+                queue.group_lens().collect::<Vec<_>>(),
+                queue.group_heads().map(|e| e.timestamp).collect::<Vec<_>>(),
+            )
+        };
+        let before = snapshot(queue);
+
+        let _ = queue.peek();
+        let _ = queue.next_group_head();
+        let _ = queue.next_group_len();
+        let _ = queue.group_at_pointer();
+        let _ = queue.peek_group(probe);
+        let _ = queue.is_group_present(probe);
+        let _ = queue.iter_group(probe).count();
+        let _ = queue.len_of_group(probe);
+        let _ = queue.contains(|e| e.group == probe.group);
+        let _ = queue.is_fair_now();
+        let _ = queue.group_count_matching(|e| e.timestamp % 2 == 0);
+        let _ = queue.groups_view().map(|group| group.len()).sum::<usize>();
+        let _ = queue.fold_groups(|| 0, |n, _| n + 1);
+        let _ = queue.estimate_memory();
+        let _ = queue.iter().count();
+        let _ = queue.scheduled().count();
+        let _ = queue.map_view(|e| e.timestamp).count();
+        let _ = queue.peek_nth(queue.len() / 2);
+        let _ = queue.peek_round();
+        let _ = queue.check_fairness();
+        #[cfg(feature = "std")]
+        {
+            let _ = queue.peek_all();
+            let _ = queue.group_heads_vec();
+            let _ = queue.service_share(probe);
+            let _ = queue.count_by(|e| e.group);
+        }
+
+        assert_eq!(snapshot(queue), before);
+    }
+
+    fn pops_with_inspection<S: Scheduler + Clone>(
+        mut queue: FairQueue<'_, Event, S>,
+        probe: &Event,
+    ) -> Vec<u32> {
+        let mut order = Vec::new();
+        loop {
+            inspect_all(&queue, probe);
+            let Some(event) = queue.pop() else {
+                break;
+            };
+            order.push(event.timestamp);
+        }
+        order
+    }
+
+    proptest! {
+        #[test]
+        fn prop_inspectors_leave_pop_sequence_unchanged(
+            groups in proptest::collection::vec(0usize..4, 0..40),
+            probe_group in 0usize..4,
+        ) {
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, group)| event(idx as u32, *group))
+                .collect();
+            let probe = event(u32::MAX, probe_group);
+
+            let plain: FairQueue<_> = events.iter().collect();
+            let expected: Vec<u32> = plain.clone().into_iter().map(|e| e.timestamp).collect();
+            prop_assert_eq!(pops_with_inspection(plain, &probe), expected);
+
+            let mut longest = FairQueue::with_scheduler(LongestQueueFirst);
+            longest.extend(&events);
+            let expected: Vec<u32> = longest.clone().into_iter().map(|e| e.timestamp).collect();
+            prop_assert_eq!(pops_with_inspection(longest, &probe), expected);
+
+            let mut strategy = FairQueue::with_strategy(Strategy::LongestFirst);
+            strategy.extend(&events);
+            let expected: Vec<u32> = strategy.clone().into_iter().map(|e| e.timestamp).collect();
+            prop_assert_eq!(pops_with_inspection(strategy, &probe), expected);
+        }
+    }
+
+    #[test]
+    fn test_is_fair_now_tracks_last_served() {
+        let a1 = event(1, 0);
+        let a2 = event(2, 0);
+        let a3 = event(3, 0);
+        let b1 = event(4, 1);
+
+        let mut queue = FairQueue::new();
+        assert!(queue.is_fair_now());
+        assert_eq!(queue.last_served_group(), None);
+
+        queue.insert(&a1);
+        queue.insert(&a2);
+        queue.insert(&a3);
+        queue.insert(&b1);
+
+        assert_eq!(queue.pop(), Some(&a1));
+        assert_eq!(queue.last_served_group(), Some(0));
+        assert!(queue.is_fair_now());
+
+        // Forcing the rotation back onto the group just served breaks spacing.
+        queue.pointer = 0;
+        assert!(!queue.is_fair_now());
+        queue.pointer = 1;
+
+        // Group 1 empties, so nothing is tracked as last served.
+        assert_eq!(queue.pop(), Some(&b1));
+        assert_eq!(queue.last_served_group(), None);
+        assert!(queue.is_fair_now());
+
+        // A single remaining group is allowed to repeat.
+        assert_eq!(queue.pop(), Some(&a2));
+        assert_eq!(queue.last_served_group(), Some(0));
+        assert!(queue.is_fair_now());
+
+        queue.clear();
+        assert_eq!(queue.last_served_group(), None);
+    }
+
+    #[test]
+    fn test_dedup_by_collapses_adjacent_runs() {
+        let events = [
+            event(1, 0),
+            event(1, 1),
+            event(1, 0),
+            event(2, 1),
+            event(2, 0),
+            event(2, 1),
+            event(1, 0),
+        ];
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.len(), 7);
+
+        queue.dedup_by(|prev, curr| prev.timestamp == curr.timestamp);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.group_count(), 2);
+        assert_eq!(queue.len_of_group(&events[0]), 3);
+        assert_eq!(queue.len_of_group(&events[1]), 2);
+
+        let order: Vec<(usize, u32)> = queue.drain().map(|e| (e.group, e.timestamp)).collect();
+        assert_eq!(order, vec![(0, 1), (1, 1), (0, 2), (1, 2), (0, 1)]);
+    }
+
+    #[test]
+    fn test_keep_last_per_group() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 0),
+            event(4, 2),
+            event(5, 1),
+            event(6, 0),
+        ];
+
+        let mut queue = FairQueue::new();
+        for event in &events {
+            queue.insert(event);
+        }
+        assert_eq!(queue.pop(), Some(&events[0]));
+
+        queue.keep_last_per_group();
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.group_count(), 3);
+
+        assert_eq!(queue.pop(), Some(&events[4]));
+        assert_eq!(queue.pop(), Some(&events[3]));
+        assert_eq!(queue.pop(), Some(&events[5]));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_validate_names_the_broken_invariant() {
+        let events = [event(1, 0), event(2, 1), event(3, 0)];
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.validate(), Ok(()));
+        assert_eq!(FairQueue::<Event>::new().validate(), Ok(()));
+
+        queue.len = 7;
+        assert_eq!(
+            queue.validate(),
+            Err(InvariantError::LenMismatch { len: 7, stored: 3 })
+        );
+        queue.len = 3;
+
+        queue.pointer = 2;
+        assert_eq!(
+            queue.validate(),
+            Err(InvariantError::PointerOutOfRange {
+                pointer: 2,
+                group_count: 2
+            })
+        );
+        queue.pointer = 1;
+
+        let group = mem::take(&mut queue.groups[1]);
+        assert_eq!(
+            queue.validate(),
+            Err(InvariantError::EmptyGroup { slot: 1 })
+        );
+        queue.groups[1] = group;
+
+        queue.ids.pop();
+        assert_eq!(
+            queue.validate(),
+            Err(InvariantError::IdCountMismatch {
+                ids: 1,
+                group_count: 2
+            })
+        );
+        assert!(queue.checked_pop().is_err());
+    }
+
+    #[test]
+    fn test_checked_pop_reports_corruption() {
+        let a1 = event(1, 0);
+        let b1 = event(2, 1);
+
+        let mut queue = FairQueue::new();
+        assert_eq!(queue.checked_pop(), Ok(None));
+
+        queue.insert(&a1);
+        queue.insert(&b1);
+        assert_eq!(queue.checked_pop(), Ok(Some(&a1)));
+
+        // No constructor accepts raw parts, and `from_groups` repairs what it is given (see
+        // below), so the corruptions are applied to the fields directly.
+        queue.len = 5;
+        assert_eq!(queue.checked_pop(), Err(QueueError::Inconsistent));
+        queue.len = 1;
+
+        queue.pointer = 3;
+        assert_eq!(queue.checked_pop(), Err(QueueError::Inconsistent));
+        queue.pointer = 0;
+
+        queue.groups.push(VecDeque::new());
+        assert_eq!(queue.checked_pop(), Err(QueueError::Inconsistent));
+        queue.groups.pop();
+
+        assert_eq!(queue.checked_pop(), Ok(Some(&b1)));
+        assert_eq!(queue.checked_pop(), Ok(None));
+    }
+
+    #[test]
+    fn test_checked_pop_after_corrupt_from_groups() {
+        let a1 = event(1, 0);
+        let a2 = event(2, 0);
+        let b1 = event(3, 1);
+
+        // Empty groups and an out-of-range pointer are the corruptions `from_groups` can be
+        // handed; it skips the former and restarts the latter, so nothing is left to report.
+        let groups = vec![
+            VecDeque::new(),
+            VecDeque::from([&a1, &a2]),
+            VecDeque::new(),
+            VecDeque::from([&b1]),
+        ];
+        let mut queue = FairQueue::from_groups(groups, 7);
+        assert_eq!(queue.validate(), Ok(()));
+        assert_eq!(queue.group_count(), 2);
+        assert_eq!(queue.checked_pop(), Ok(Some(&a1)));
+        assert_eq!(queue.checked_pop(), Ok(Some(&b1)));
+        assert_eq!(queue.checked_pop(), Ok(Some(&a2)));
+        assert_eq!(queue.checked_pop(), Ok(None));
+
+        let mut empty: FairQueue<Event> = FairQueue::from_groups(vec![VecDeque::new(); 3], 1);
+        assert_eq!(empty.validate(), Ok(()));
+        assert_eq!(empty.checked_pop(), Ok(None));
+    }
+
+    #[test]
+    fn test_shrink_to_fit_keeps_order() {
+        let events: Vec<Event> = (0..256).map(|idx| event(idx, idx as usize % 8)).collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        for _ in 0..240 {
+            queue.pop();
+        }
+
+        let expected: Vec<_> = queue.iter().collect();
+        let (len, groups, pointer) = (queue.len(), queue.group_count(), queue.pointer);
+        let capacity = queue.groups.iter().map(VecDeque::capacity).sum::<usize>();
+        queue.shrink_to_fit();
+
+        assert!(queue.groups.iter().map(VecDeque::capacity).sum::<usize>() < capacity);
+        assert_eq!(
+            (queue.len(), queue.group_count(), queue.pointer),
+            (len, groups, pointer)
+        );
+        assert_eq!(queue.drain().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_group_heads_ignore_which_group_empties() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 3),
+            event(5, 1),
+        ];
+
+        for emptied in [0, 2, 3] {
+            let mut queue = FairQueue::new();
+            queue.extend(&events);
+            assert!(queue.rotate_to(&events[emptied]));
+            queue.pop();
+
+            let heads: Vec<usize> = queue.group_heads().map(|e| e.group).collect();
+            let arrival: Vec<usize> = (0..4).filter(|&group| group != emptied).collect();
+            assert_eq!(heads, arrival);
+            // The rotation walks the same order, resuming after the emptied group.
+            let next = arrival
+                .iter()
+                .position(|&group| group > emptied)
+                .unwrap_or(0);
+            assert_eq!(queue.peek().map(|e| e.group), Some(arrival[next]));
+        }
+
+        for emptied in [[0, 3], [3, 0], [2, 0]] {
+            let mut queue = FairQueue::new();
+            queue.extend(&events);
+            for group in emptied {
+                assert!(queue.rotate_to(&events[group]));
+                queue.pop();
+            }
+            let heads: Vec<usize> = queue.group_heads().map(|e| e.group).collect();
+            let arrival: Vec<usize> = (0..4).filter(|group| !emptied.contains(group)).collect();
+            assert_eq!(heads, arrival);
+        }
+    }
+
+    #[test]
+    fn test_shrink_groups_to_floor() {
+        let events: Vec<Event> = (0..64).map(|idx| event(idx, idx as usize)).collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        for _ in 0..60 {
+            queue.pop();
+        }
+        assert_eq!(queue.group_count(), 4);
+        assert!(queue.groups.capacity() >= 64);
+        let expected: Vec<_> = queue.iter().collect();
+
+        queue.shrink_groups_to(16);
+        assert_eq!(queue.groups.capacity(), 16);
+        assert_eq!(queue.ids.capacity(), 16);
+
+        // Never below the live groups, and never grown.
+        queue.shrink_groups_to(0);
+        assert_eq!(queue.groups.capacity(), 4);
+        queue.shrink_groups_to(32);
+        assert_eq!(queue.groups.capacity(), 4);
+        assert!(queue.is_consistent());
+        assert_eq!(queue.drain().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_capacity_hints() {
+        let events: Vec<Event> = (0..8).map(|idx| event(idx, (idx % 2) as usize)).collect();
+
+        let mut queue = FairQueue::with_group_capacity(4, 16);
+        assert!(queue.groups.capacity() >= 4);
+        for event in &events {
+            queue.insert(event);
+        }
+        assert!(queue.groups.iter().all(|group| group.capacity() >= 16));
+
+        queue.reserve(32);
+        assert!(queue.groups.capacity() >= 34);
+        assert_eq!(queue.len(), 8);
+        assert_eq!(queue.group_count(), 2);
+
+        let queue = FairQueue::<Event>::with_capacity(10);
+        assert!(queue.groups.capacity() >= 10);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_pop_n_matches_pop_loop() {
+        let events: Vec<Event> = (0..12).map(|idx| event(idx, idx as usize % 3)).collect();
+        let mut queue: FairQueue<_> = events[..10].iter().collect();
+        let mut replay = queue.clone();
+
+        let mut batch = Vec::new();
+        assert_eq!(queue.pop_n(4, &mut batch), 4);
+        queue.insert(&events[10]);
+        assert_eq!(queue.pop_n(16, &mut batch), 7);
+        assert_eq!(queue.pop_n(3, &mut batch), 0);
+        assert!(queue.is_empty());
+
+        let mut expected = Vec::new();
+        for _ in 0..4 {
+            expected.extend(replay.pop());
+        }
+        replay.insert(&events[10]);
+        expected.extend(replay.drain());
+        assert_eq!(batch, expected);
+    }
+
+    #[test]
+    fn test_groups_view_matches_heads_and_lens() {
+        let events: Vec<Event> = [0, 1, 0, 2, 1, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        queue.pop();
+
+        let views: Vec<GroupView<'_, '_, Event>> = queue.groups_view().collect();
+        assert_eq!(views.len(), queue.group_count());
+        assert!(
+            views
+                .iter()
+                .map(GroupView::head)
+                .eq(queue.group_heads().map(Some))
+        );
+        assert!(views.iter().map(GroupView::len).eq(queue.group_lens()));
+        for view in &views {
+            let head = view.head().unwrap();
+            assert!(view.iter().eq(queue.iter_group(head)));
+            assert!(!view.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_from_sorted_by_key_matches_insert() {
+        let events: Vec<Event> = [0, 0, 0, 1, 2, 2, 3, 3, 3, 3]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        let sorted = FairQueue::from_sorted_by_key(&events, |e| e.group);
+        let inserted = FairQueue::from_slice(&events);
+        assert_eq!(sorted.group_count(), 4);
+        assert_eq!(sorted.group_count(), inserted.group_count());
+        assert_eq!(sorted.len(), inserted.len());
+        assert!(sorted.is_consistent());
+        assert!(sorted.into_iter().eq(inserted));
+
+        let empty: FairQueue<Event> = FairQueue::from_sorted_by_key(&[], |e| e.group);
+        assert!(empty.is_empty() && empty.group_count() == 0);
+    }
+
+    #[test]
+    fn test_take_removes_future_position() {
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 0, 2, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        for n in 0..events.len() {
+            let mut queue = FairQueue::new();
+            queue.extend(&events);
+            let original: Vec<&Event> = queue.iter().collect();
+
+            let taken = queue.take(n);
+            assert_eq!(taken, Some(original[n]));
+            assert_eq!(queue.len(), events.len() - 1);
+            assert!(queue.is_consistent());
+
+            let remaining: Vec<&Event> = queue.iter().collect();
+            assert_eq!(remaining[..n], original[..n]);
+            for group in 0..3 {
+                let kept: Vec<u32> = remaining
+                    .iter()
+                    .filter(|e| e.group == group)
+                    .map(|e| e.timestamp)
+                    .collect();
+                let expected: Vec<u32> = original
+                    .iter()
+                    .filter(|e| e.group == group && e.timestamp != original[n].timestamp)
+                    .map(|e| e.timestamp)
+                    .collect();
+                assert_eq!(kept, expected);
+            }
+        }
+
+        // Near the end nothing is left to interleave, so the rest matches the excised order.
+        let mut queue = FairQueue::new();
+        queue.extend(&events);
+        let mut expected: Vec<u32> = queue.iter().map(|e| e.timestamp).collect();
+        assert_eq!(queue.take(6).map(|e| e.timestamp), Some(expected.remove(6)));
+        assert!(queue.iter().map(|e| e.timestamp).eq(expected));
+        assert!(queue.take(7).is_none());
+    }
+
+    #[test]
+    fn test_compact_merges_split_groups() {
+        let events: Vec<Event> = [0, 1, 0, 2, 1, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        // Build slots [0], [1], [0, 0], [2], [1] by hand, as a broken relation could.
+        let mut queue = FairQueue::new();
+        for run in [&[0][..], &[1], &[2, 5], &[3], &[4]] {
+            queue.push_group(run.iter().map(|&idx| &events[idx]).collect());
+            queue.len += run.len();
+        }
+        queue.pointer = 3;
+        assert_eq!(queue.group_count(), 5);
+
+        queue.compact();
+        assert!(queue.is_consistent());
+        assert_eq!(queue.group_count(), 3);
+        assert_eq!(queue.len(), 6);
+        let lens: Vec<usize> = queue.group_lens().collect();
+        assert_eq!(lens, vec![3, 2, 1]);
+
+        // The pointer stays on group 2; merged items follow the earlier slot's items.
+        let order: Vec<u32> = queue.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![3, 0, 1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_consuming_iterators_report_exact_len() {
+        let events: Vec<Event> = [0, 1, 0, 2, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+
+        let mut into_iter = events.iter().collect::<FairQueue<_>>().into_iter();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        let mut drain = queue.drain();
+        for remaining in (0..events.len()).rev() {
+            assert!(into_iter.next().is_some() && drain.next().is_some());
+            assert_eq!((into_iter.len(), drain.len()), (remaining, remaining));
+        }
+        assert!(into_iter.next().is_none() && drain.next().is_none());
+        assert_eq!((into_iter.len(), drain.len()), (0, 0));
+        drop(drain);
+
+        let mut queue: FairQueue<_> = events.iter().collect();
+        let mut group = queue.drain_group(&events[0]);
+        let mut collected = Vec::with_capacity(group.len());
+        for remaining in (0..3).rev() {
+            collected.extend(group.next());
+            assert_eq!(group.len(), remaining);
+        }
+        assert_eq!(collected.len(), collected.capacity());
+    }
+
+    #[test]
+    fn test_drain_in_pop_order() {
+        let events = [event(1, 0), event(2, 0), event(3, 1), event(4, 2)];
+
+        let mut queue = FairQueue::new();
+        let mut replay = FairQueue::new();
+        for event in &events {
+            queue.insert(event);
+            replay.insert(event);
+        }
+
+        let drain = queue.drain();
+        assert_eq!(drain.size_hint(), (4, Some(4)));
+        let drained: Vec<&Event> = drain.collect();
+        let expected: Vec<&Event> = core::iter::from_fn(|| replay.pop()).collect();
+        assert_eq!(drained, expected);
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+    }
+
+    #[test]
+    fn test_partial_drain_clears_on_drop() {
+        let events = [event(1, 0), event(2, 0), event(3, 1), event(4, 2)];
+
+        let mut queue = FairQueue::new();
+        for event in &events {
+            queue.insert(event);
+        }
+
+        {
+            let mut drain = queue.drain();
+            assert_eq!(drain.next(), Some(&events[0]));
+            assert_eq!(drain.size_hint(), (3, Some(3)));
+        }
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+        assert_eq!(queue.pointer, 0);
+        assert!(queue.drain().next().is_none());
+
+        queue.insert(&events[3]);
+        assert_eq!(queue.pop(), Some(&events[3]));
+    }
+
+    #[test]
+    fn test_into_iter_matches_pop_loop() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 0),
+            event(4, 2),
+            event(5, 1),
+        ];
+
+        let mut queue = FairQueue::new();
+        let mut replay = FairQueue::new();
+        for event in &events {
+            queue.insert(event);
+            replay.insert(event);
+        }
+
+        let mut expected = Vec::new();
+        while let Some(item) = replay.pop() {
+            expected.push(item);
+        }
+
+        let iter = queue.into_iter();
+        assert_eq!(iter.size_hint(), (5, Some(5)));
+        let mut collected = Vec::new();
+        for item in iter {
+            collected.push(item);
+        }
+        assert_eq!(collected, expected);
+    }
+
+    #[test]
+    fn test_clone_forks_mid_drain() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 0),
+            event(4, 2),
+            event(5, 1),
+            event(6, 0),
+        ];
+
+        let mut queue = FairQueue::new();
+        for event in &events {
+            queue.insert(event);
+        }
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(1));
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(2));
+
+        let mut fork = queue.clone();
+        let forked: Vec<u32> = fork.drain().map(|e| e.timestamp).collect();
+        assert!(fork.is_empty());
+
+        assert_eq!(queue.len(), 4);
+        let remaining: Vec<u32> = queue.drain().map(|e| e.timestamp).collect();
+        assert_eq!(remaining, forked);
+        assert_eq!(remaining, vec![4, 3, 5, 6]);
+    }
+
+    #[test]
+    fn test_debug_marks_pointer_group() {
+        let mut queue = FairQueue::new();
+        let a = event(1, 0);
+        let b = event(2, 1);
+        let a2 = event(3, 0);
+        queue.insert(&a);
+        queue.insert(&b);
+        queue.insert(&a2);
+        queue.pop();
+
+        let dump = format!("{queue:?}");
+        assert!(dump.starts_with("FairQueue { len: 2, group_count: 2, pointer: 1, groups: ["));
+        assert!(dump.contains("> [Event { timestamp: 2"));
+        assert_eq!(dump.matches("> ").count(), 1);
+    }
+
+    #[test]
+    fn test_retain_purges_across_groups() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 0),
+            event(5, 1),
+            event(6, 2),
+            event(7, 1),
+        ];
 
-        let mut queue = FairQueue::new();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(1));
 
-        queue.insert(&event1);
-        queue.insert(&event2);
-        queue.insert(&event3);
-        queue.insert(&event4);
-        queue.insert(&event5);
-        queue.insert(&event6);
-        queue.insert(&event7);
-        queue.insert(&event8);
+        // The pointer references group 1, which disappears entirely, so the
+        // rotation resumes at group 2.
+        queue.retain(|e| e.group != 1 && e.timestamp != 6);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.group_count(), 2);
+        assert!(queue.is_consistent());
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(3));
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(4));
+        assert!(queue.pop().is_none());
+    }
 
-        assert_eq!(queue.pop(), Some(&event1));
-        assert_eq!(queue.pop(), Some(&event2));
-        assert_eq!(queue.pop(), Some(&event4));
-        assert_eq!(queue.pop(), Some(&event3));
-        assert_eq!(queue.pop(), Some(&event5));
-        assert_eq!(queue.pop(), Some(&event8));
-        assert_eq!(queue.pop(), Some(&event6));
-        assert_eq!(queue.pop(), Some(&event7));
-        assert_eq!(queue.pop(), None);
+    #[test]
+    fn test_insert_prefers_group_tag() {
+        #[derive(Debug, PartialEq)]
+        struct Tagged {
+            id: u32,
+            tenant: u64,
+        }
+
+        impl FairGroup for Tagged {
+            fn is_same_group(&self, _other: &Self) -> bool {
+                unreachable!("tagged values are compared by group_tag")
+            }
+
+            fn group_tag(&self) -> Option<u64> {
+                Some(self.tenant)
+            }
+        }
+
+        let items = [
+            Tagged { id: 1, tenant: 7 },
+            Tagged { id: 2, tenant: 7 },
+            Tagged { id: 3, tenant: 9 },
+        ];
+        let mut queue: FairQueue<_> = items.iter().collect();
+
+        assert_eq!(queue.group_count(), 2);
+        let order: Vec<u32> = queue.drain().map(|item| item.id).collect();
+        assert_eq!(order, vec![1, 3, 2]);
     }
 
     #[test]
-    fn test_len_and_group_count() {
-        let event = Event {
-            timestamp: 0,
-            user_id: "user1",
-            group: 0,
-        };
-        let other = Event {
-            timestamp: 1,
-            user_id: "user2",
-            group: 1,
-        };
+    fn test_remove_pointed_head_keeps_rotation() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 0),
+            event(5, 1),
+            event(6, 2),
+        ];
 
-        let mut queue = FairQueue::new();
-        assert!(queue.is_empty());
-        assert_eq!(queue.len(), 0);
-        assert_eq!(queue.group_count(), 0);
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(1));
 
-        queue.insert(&event);
-        assert!(!queue.is_empty());
-        assert_eq!(queue.len(), 1);
-        assert_eq!(queue.group_count(), 1);
+        // The pointer references group 1; cancelling its head serves its next item.
+        assert_eq!(queue.remove(|e| e.timestamp == 2), 1);
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.peek().map(|e| e.timestamp), Some(5));
+        assert_eq!(queue.remove(|e| e.timestamp == 42), 0);
 
-        queue.insert(&other);
-        assert_eq!(queue.len(), 2);
+        // Dropping the whole pointed-at group moves on to group 2, not group 0.
+        assert_eq!(queue.remove(|e| e.group == 1), 1);
         assert_eq!(queue.group_count(), 2);
+        let order: Vec<u32> = queue.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![3, 4, 6]);
+    }
 
-        assert_eq!(queue.pop(), Some(&event));
-        assert_eq!(queue.len(), 1);
-        assert_eq!(queue.group_count(), 1);
+    #[test]
+    fn test_clear_group_mid_queue() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 0),
+            event(5, 1),
+            event(6, 2),
+        ];
+        let mut queue: FairQueue<_> = events.iter().collect();
 
-        queue.clear();
-        assert!(queue.is_empty());
-        assert_eq!(queue.group_count(), 0);
+        assert_eq!(queue.clear_group(&event(0, 1)), 2);
+        assert_eq!(queue.clear_group(&event(0, 1)), 0);
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.group_count(), 2);
+
+        let order: Vec<usize> = queue.drain().map(|e| e.group).collect();
+        assert_eq!(order, vec![0, 2, 0, 2]);
     }
 
     #[test]
-    fn test_peek_matches_pop() {
-        let event = Event {
-            timestamp: 1,
-            user_id: "user1",
-            group: 0,
-        };
-        let other = Event {
-            timestamp: 2,
-            user_id: "user2",
-            group: 1,
-        };
+    fn test_drain_group_mid_queue() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 2),
+            event(4, 0),
+            event(5, 1),
+            event(6, 2),
+            event(7, 1),
+        ];
 
-        let mut queue = FairQueue::new();
-        queue.insert(&event);
-        queue.insert(&other);
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(1));
 
-        assert_eq!(queue.peek(), Some(&event));
-        assert_eq!(queue.peek(), Some(&event));
-        assert_eq!(queue.pop(), Some(&event));
-        assert_eq!(queue.peek(), Some(&other));
+        let flushed: Vec<u32> = queue
+            .drain_group(&event(0, 1))
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(flushed, vec![2, 5, 7]);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.group_count(), 2);
+        assert_eq!(queue.drain_group(&event(0, 3)).count(), 0);
+
+        let order: Vec<usize> = queue.drain().map(|e| e.group).collect();
+        assert_eq!(order, vec![2, 0, 2]);
     }
 
     #[test]
-    fn test_group_heads_snapshot() {
-        let a1 = Event {
-            timestamp: 1,
-            user_id: "user1",
-            group: 0,
-        };
-        let b1 = Event {
-            timestamp: 2,
-            user_id: "user2",
-            group: 1,
-        };
-        let b2 = Event {
-            timestamp: 3,
-            user_id: "user2",
-            group: 1,
-        };
-        let c1 = Event {
-            timestamp: 4,
-            user_id: "user3",
-            group: 2,
-        };
+    fn test_from_stack_maps_bottom_to_front() {
+        let events: Vec<Event> = [0, 1, 0, 2, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
 
-        let mut queue = FairQueue::new();
-        queue.insert(&a1);
-        queue.insert(&b1);
-        queue.insert(&b2);
-        queue.insert(&c1);
+        let mut stack: FairStack<_> = events.iter().collect();
+        assert_eq!(stack.pop().map(|e| e.timestamp), Some(4));
 
-        let mut heads = queue.group_heads();
-        assert_eq!(heads.next(), Some(&a1));
-        assert_eq!(heads.next(), Some(&b1));
-        assert_eq!(heads.next(), Some(&c1));
-        assert_eq!(heads.next(), None);
+        // Slots and the pointer carry over; group 0 now serves its oldest item first.
+        let queue = FairQueue::from(stack);
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.group_count(), 3);
+        let order: Vec<u32> = queue.into_iter().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![1, 3, 0, 2]);
 
-        #[cfg(feature = "std")]
-        {
-            let collected = queue.group_heads_vec();
-            assert_eq!(collected, vec![&a1, &b1, &c1]);
+        let original: FairQueue<_> = events.iter().collect();
+        let round_trip = FairQueue::from(FairStack::from(original.clone()));
+        assert!(original.into_iter().eq(round_trip));
+    }
+
+    #[test]
+    fn test_longest_queue_first_order() {
+        let events = [
+            event(1, 0),
+            event(2, 0),
+            event(3, 0),
+            event(4, 1),
+            event(5, 2),
+            event(6, 2),
+        ];
+
+        let mut queue = FairQueue::with_scheduler(LongestQueueFirst);
+        queue.extend(&events);
+        assert_eq!(queue.peek().map(|e| e.timestamp), Some(1));
+
+        let planned: Vec<u32> = queue.iter().map(|e| e.timestamp).collect();
+        let mut popped = Vec::new();
+        while let Some(event) = queue.pop() {
+            popped.push(event.timestamp);
         }
+        assert_eq!(popped, vec![1, 5, 2, 4, 6, 3]);
+        assert_eq!(planned, popped);
+    }
 
-        // Ensure borrowing state not consumed.
-        assert_eq!(queue.peek(), Some(&a1));
+    #[test]
+    fn test_longest_first_strategy_breaks_ties_by_slot() {
+        let events = [
+            event(1, 0),
+            event(2, 1),
+            event(3, 1),
+            event(4, 2),
+            event(5, 2),
+            event(6, 2),
+        ];
+
+        let mut queue = FairQueue::with_strategy(Strategy::LongestFirst);
+        queue.extend(&events);
+
+        // Groups 1 and 2 tie at two items after the first pop, but group 2 was just served.
+        // Once every group holds one item, slot 0 wins; emptying it swaps group 2 into slot 0.
+        let order: Vec<u32> = queue.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![4, 2, 5, 1, 6, 3]);
+
+        let mut round_robin = FairQueue::with_strategy(Strategy::RoundRobin);
+        round_robin.extend(&events);
+        let mut plain = FairQueue::new();
+        plain.extend(&events);
+        assert!(round_robin.into_iter().eq(plain));
     }
 
     #[test]
-    fn test_pointer_wraps_after_group_removal() {
-        let a1 = Event {
-            timestamp: 1,
-            user_id: "user1",
-            group: 0,
-        };
-        let b1 = Event {
-            timestamp: 2,
-            user_id: "user2",
-            group: 1,
-        };
-        let c1 = Event {
-            timestamp: 3,
-            user_id: "user3",
-            group: 2,
-        };
-        let d1 = Event {
-            timestamp: 4,
-            user_id: "user4",
-            group: 3,
-        };
+    fn test_round_robin_scheduler_matches_new() {
+        let events: Vec<Event> = [0, 1, 0, 2, 1, 0, 2]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
 
-        let mut queue = FairQueue::new();
-        queue.insert(&a1);
-        queue.insert(&b1);
-        queue.insert(&c1);
+        let mut default = FairQueue::new();
+        let mut explicit = FairQueue::with_scheduler(RoundRobin);
+        default.extend(&events);
+        explicit.extend(&events);
+        assert_eq!(explicit.scheduler(), &RoundRobin);
 
-        assert_eq!(queue.pop(), Some(&a1));
-        queue.insert(&d1);
+        while let Some(expected) = default.pop() {
+            assert_eq!(explicit.pop(), Some(expected));
+        }
+        assert!(explicit.is_empty());
+    }
 
-        let mut groups = Vec::new();
-        while let Some(item) = queue.pop() {
-            groups.push(item.group);
+    #[test]
+    fn test_pop_round_serves_each_group_once() {
+        let events: Vec<Event> = [0, 0, 0, 1, 2, 2, 3]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(0));
+
+        let mut rounds = Vec::new();
+        let mut out = Vec::new();
+        loop {
+            let groups = queue.group_count();
+            let popped = queue.pop_round(&mut out);
+            assert_eq!(popped, groups);
+            assert!(queue.is_consistent());
+            if popped == 0 {
+                break;
+            }
+            let round: Vec<_> = out.drain(..).map(|e| (e.group, e.timestamp)).collect();
+            let mut seen: Vec<_> = round.iter().map(|&(group, _)| group).collect();
+            seen.sort_unstable();
+            seen.dedup();
+            assert_eq!(seen.len(), round.len());
+            rounds.push(round);
         }
 
-        groups.sort_unstable();
-        assert_eq!(groups, vec![1, 2, 3]);
+        // The first pop left the pointer on group 1; once it empties, group 2 leads.
+        assert_eq!(
+            rounds,
+            [vec![(1, 3), (2, 4), (3, 6), (0, 1)], vec![(2, 5), (0, 2)],]
+        );
         assert!(queue.is_empty());
+
+        let mut appended = vec![&events[0]];
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.pop_round(&mut appended), 4);
+        assert_eq!(appended.len(), 5);
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(1));
+    }
+
+    #[test]
+    fn test_try_insert_strict_rejects_non_transitive_grouping() {
+        #[derive(Debug, PartialEq)]
+        struct Near(i32);
+
+        impl FairGroup for Near {
+            fn is_same_group(&self, other: &Self) -> bool {
+                (self.0 - other.0).abs() <= 1
+            }
+        }
+
+        let values = [Near(1), Near(0), Near(2), Near(5)];
+        let mut queue = FairQueue::new();
+        assert_eq!(queue.try_insert_strict(&values[0]), Ok(()));
+        assert_eq!(queue.try_insert_strict(&values[1]), Ok(()));
+        assert_eq!(queue.try_insert_strict(&values[3]), Ok(()));
+        assert_eq!(queue.group_count(), 2);
+
+        // The group is [1, 0]: 2 matches its head 1 but not the member 0.
+        assert_eq!(
+            queue.try_insert_strict(&values[2]),
+            Err(QueueError::NonTransitiveGroup)
+        );
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.group_count(), 2);
+        assert!(queue.is_consistent());
+
+        // Plain insert accepts the same value into the group.
+        queue.insert(&values[2]);
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.group_count(), 2);
+    }
+
+    #[test]
+    fn test_check_fairness_reports_non_transitive_grouping() {
+        // "Within 1 of each other" is not transitive: 1 joins 0's group, yet is also
+        // "the same group" as 2, which is served right before it.
+        #[derive(Debug, PartialEq)]
+        struct Near(i32);
+
+        impl FairGroup for Near {
+            fn is_same_group(&self, other: &Self) -> bool {
+                (self.0 - other.0).abs() <= 1
+            }
+        }
+
+        let values = [Near(0), Near(2), Near(1), Near(0)];
+        let mut queue = FairQueue::new();
+        queue.extend(&values);
+        assert_eq!(queue.group_count(), 2);
+
+        let violation = queue.check_fairness().unwrap_err();
+        assert_eq!(violation.position(), 2);
+        assert_eq!(queue.peek_nth(violation.position()), Some(&values[2]));
+        assert_eq!(queue.len(), 4);
+
+        let panic = std::panic::catch_unwind(|| queue.debug_assert_fair());
+        assert!(panic.is_err());
+
+        let events: Vec<Event> = [0, 1, 0, 2, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| event(idx as u32, group))
+            .collect();
+        let fair: FairQueue<_> = events.iter().collect();
+        assert_eq!(fair.check_fairness(), Ok(()));
+        fair.debug_assert_fair();
     }
 
     proptest! {
+        #[test]
+        fn prop_collect_ignores_size_hint_accuracy(
+            groups in proptest::collection::vec(0usize..12, 0..64),
+            hint in prop_oneof![Just(0usize), 0usize..128, Just(usize::MAX)],
+        ) {
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, &group)| event(idx as u32, group))
+                .collect();
+
+            let mut naive = FairQueue::new();
+            for event in &events {
+                naive.insert(event);
+            }
+            let hinted: FairQueue<_> = Hinted(events.iter(), hint).collect();
+            let bound = EXTEND_GROUP_HINT.max(naive.groups.capacity());
+            prop_assert!(hinted.groups.capacity() <= bound);
+            prop_assert_eq!(&hinted.ids, &naive.ids);
+            prop_assert!(hinted.into_iter().eq(naive));
+        }
+
         #[test]
         fn prop_queue_preserves_spacing(groups in proptest::collection::vec(0usize..4, 1..32)) {
             const IDS: [&str; 4] = ["g0", "g1", "g2", "g3"];
@@ -440,6 +5701,8 @@ mod tests {
                 queue.insert(event);
             }
 
+            prop_assert!(queue.check_fairness().is_ok());
+
             let mut remaining = [0usize; 4];
             for event in &events {
                 remaining[event.group] += 1;
@@ -460,7 +5723,160 @@ mod tests {
 
                 remaining[gid] = remaining[gid].saturating_sub(1);
                 last_group = Some(gid);
+                prop_assert!(queue.is_fair_now());
+            }
+        }
+
+        #[test]
+        fn prop_len_tracks_item_count(
+            ops in proptest::collection::vec((0u8..6, 0usize..5), 1..96)
+        ) {
+            let events: Vec<Event> = ops
+                .iter()
+                .enumerate()
+                .map(|(idx, &(_, group))| event(idx as u32, group))
+                .collect();
+
+            let mut queue = FairQueue::new();
+            let mut out = Vec::new();
+            for (&(op, _), event) in ops.iter().zip(&events) {
+                match op {
+                    0 | 1 => queue.insert(event),
+                    2 => prop_assert!(queue.checked_insert(event).is_ok()),
+                    3 => {
+                        queue.pop();
+                    }
+                    4 => {
+                        queue.pop_round(&mut out);
+                    }
+                    _ => queue.retain(|e| e.timestamp % 3 != 0),
+                }
+                let count: usize = queue.group_lens().sum();
+                prop_assert_eq!(queue.len(), count);
+                prop_assert_eq!(queue.len64(), count as u64);
+                prop_assert_eq!(queue.iter().count(), count);
+                prop_assert!(queue.is_consistent());
+            }
+            while queue.pop().is_some() {}
+            prop_assert_eq!(queue.len(), 0);
+        }
+
+        #[test]
+        fn prop_collect_matches_insert_loop(groups in proptest::collection::vec(0usize..6, 0..48)) {
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, group)| event(idx as u32, *group))
+                .collect();
+
+            let mut manual = FairQueue::new();
+            for event in &events {
+                manual.insert(event);
+            }
+            let mut collected: FairQueue<_> = events.iter().collect();
+
+            prop_assert_eq!(collected.len(), manual.len());
+            prop_assert_eq!(collected.group_count(), manual.group_count());
+            while let Some(expected) = manual.pop() {
+                prop_assert_eq!(collected.pop().map(|e| e.timestamp), Some(expected.timestamp));
+            }
+            prop_assert!(collected.pop().is_none());
+        }
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn prop_into_pop_order_vec_matches_pop_loop(
+            groups in proptest::collection::vec(0usize..4, 0..48)
+        ) {
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, group)| event(idx as u32, *group))
+                .collect();
+
+            let mut manual: FairQueue<_> = events.iter().collect();
+            let queue = manual.clone();
+            let mut expected = Vec::new();
+            while let Some(event) = manual.pop() {
+                expected.push(event);
+            }
+            prop_assert_eq!(queue.into_pop_order_vec(), expected);
+        }
+
+        #[test]
+        fn prop_longest_queue_first_serves_a_longest_group(
+            groups in proptest::collection::vec(0usize..4, 1..48)
+        ) {
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, group)| event(idx as u32, *group))
+                .collect();
+
+            let mut queue = FairQueue::with_scheduler(LongestQueueFirst);
+            queue.extend(&events);
+            let planned: Vec<u32> = queue.iter().map(|e| e.timestamp).collect();
+
+            let mut remaining = [0usize; 4];
+            for event in &events {
+                remaining[event.group] += 1;
+            }
+
+            let mut popped = Vec::new();
+            while let Some(event) = queue.pop() {
+                let longest = remaining.iter().copied().max().unwrap_or(0);
+                prop_assert_eq!(remaining[event.group], longest);
+                remaining[event.group] -= 1;
+                popped.push(event.timestamp);
+            }
+            prop_assert_eq!(remaining, [0; 4]);
+            prop_assert_eq!(planned, popped);
+        }
+
+        #[test]
+        fn prop_longest_first_serves_a_longest_other_group(
+            groups in proptest::collection::vec(0usize..4, 1..48)
+        ) {
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, group)| event(idx as u32, *group))
+                .collect();
+
+            let mut queue = FairQueue::with_strategy(Strategy::LongestFirst);
+            queue.extend(&events);
+            let planned: Vec<u32> = queue.iter().map(|e| e.timestamp).collect();
+
+            let mut remaining = [0usize; 4];
+            for event in &events {
+                remaining[event.group] += 1;
+            }
+
+            let mut popped = Vec::new();
+            let mut last_group: Option<usize> = None;
+            while let Some(event) = queue.pop() {
+                let gid = event.group;
+                let others_pending = remaining
+                    .iter()
+                    .enumerate()
+                    .any(|(idx, &count)| Some(idx) != last_group && count > 0);
+                let longest = remaining
+                    .iter()
+                    .enumerate()
+                    .filter(|&(idx, _)| !others_pending || Some(idx) != last_group)
+                    .map(|(_, &count)| count)
+                    .max()
+                    .unwrap_or(0);
+                prop_assert_eq!(remaining[gid], longest);
+                if others_pending {
+                    prop_assert_ne!(Some(gid), last_group);
+                }
+                remaining[gid] -= 1;
+                last_group = Some(gid);
+                popped.push(event.timestamp);
             }
+            prop_assert_eq!(remaining, [0; 4]);
+            prop_assert_eq!(planned, popped);
         }
     }
 }
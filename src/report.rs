@@ -0,0 +1,37 @@
+use core::fmt;
+
+/// Breakdown of the memory a queue has reserved versus what it currently uses.
+/// Group capacities are counted in items, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CapacityReport {
+    /// Number of groups currently tracked.
+    pub groups_len: usize,
+    /// Capacity of the outer group vector.
+    pub groups_capacity: usize,
+    /// Total number of queued items.
+    pub items: usize,
+    /// Smallest capacity of any group, 0 when there are no groups.
+    pub min_group_capacity: usize,
+    /// Largest capacity of any group, 0 when there are no groups.
+    pub max_group_capacity: usize,
+    /// Sum of all group capacities.
+    pub total_group_capacity: usize,
+    /// Reserved but unused item slots summed over all groups.
+    pub wasted_capacity: usize,
+}
+
+impl fmt::Display for CapacityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "groups {}/{}, items {}/{} (per group {}..={}), wasted {}",
+            self.groups_len,
+            self.groups_capacity,
+            self.items,
+            self.total_group_capacity,
+            self.min_group_capacity,
+            self.max_group_capacity,
+            self.wasted_capacity,
+        )
+    }
+}
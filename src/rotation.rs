@@ -0,0 +1,565 @@
+//! Round-robin step shared by the queues: take an item from the slot under the pointer, drop
+//! the slot once it is drained and move the pointer on. Each queue describes its group storage
+//! through [`Slots`], so fixes to the rotation reach all of them. Most queues serve their slots
+//! in index order; [`Rotation`] links them in arrival order instead.
+
+#[cfg(feature = "alloc")]
+use alloc::{
+    collections::{BinaryHeap, VecDeque, vec_deque},
+    vec::Vec,
+};
+
+/// Group storage of a rotating structure: one slot per group, indexed from 0.
+pub(crate) trait Slots {
+    /// What a pop takes out of a slot.
+    type Item;
+
+    /// Returns the number of slots.
+    fn count(&self) -> usize;
+
+    /// Takes the next item out of `slot`.
+    fn take(&mut self, slot: usize) -> Option<Self::Item>;
+
+    /// Returns true when `slot` holds no items.
+    fn is_drained(&self, slot: usize) -> bool;
+
+    /// Removes the drained `slot`, which another slot then takes, usually the last one, and
+    /// fixes any index of slots.
+    fn remove(&mut self, slot: usize);
+}
+
+/// Item taken by [`pop`] with the slot it came from.
+// The heapless structures only need the item.
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+pub(crate) struct Popped<T> {
+    pub(crate) item: T,
+    pub(crate) slot: usize,
+    /// False when taking the item drained the slot, which was then removed.
+    pub(crate) kept: bool,
+}
+
+/// Order in which a rotating structure serves its slots.
+pub(crate) trait Order {
+    /// Returns the slot served after `slot`, out of `count` slots.
+    fn next(&self, slot: usize, count: usize) -> usize;
+
+    /// Forgets `slot`, whose group the storage has just removed, and returns the slot serving
+    /// continues at among the `count` slots left.
+    fn remove(&mut self, slot: usize, count: usize) -> usize;
+}
+
+/// Index order, for storage whose removed slot is taken by a group not yet served in the
+/// pass: the last one, moved in by `swap_remove`, or the next one, shifted down.
+pub(crate) struct SlotOrder;
+
+impl Order for SlotOrder {
+    #[inline(always)]
+    fn next(&self, slot: usize, count: usize) -> usize {
+        (slot + 1) % count
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, slot: usize, count: usize) -> usize {
+        if slot < count { slot } else { 0 }
+    }
+}
+
+/// Takes an item from the slot under `pointer`, serving the slots in index order.
+/// See [`pop_ordered`].
+#[inline(always)]
+pub(crate) fn pop<S: Slots>(
+    slots: &mut S,
+    pointer: &mut usize,
+    stay: bool,
+) -> Option<Popped<S::Item>> {
+    pop_ordered(slots, &mut SlotOrder, pointer, stay)
+}
+
+/// Takes an item from the slot under `pointer`, wrapping an out-of-range pointer to slot 0
+/// first. The pointer then moves to the slot `order` serves next, or with `stay` remains on
+/// the served one while it holds items. A drained slot is removed and the pointer continues
+/// at the slot `order` reports. A slot found empty is removed the same way and `None` is
+/// returned, leaving the caller to try again; `None` also means there are no slots.
+pub(crate) fn pop_ordered<S: Slots, O: Order>(
+    slots: &mut S,
+    order: &mut O,
+    pointer: &mut usize,
+    stay: bool,
+) -> Option<Popped<S::Item>> {
+    let count = slots.count();
+    if count == 0 {
+        *pointer = 0;
+        return None;
+    }
+    if *pointer >= count {
+        *pointer = 0;
+    }
+
+    let slot = *pointer;
+    let item = slots.take(slot);
+    let kept = item.is_some() && !slots.is_drained(slot);
+    if kept {
+        if !stay {
+            *pointer = order.next(slot, count);
+        }
+    } else {
+        slots.remove(slot);
+        *pointer = order.remove(slot, count - 1);
+    }
+    item.map(|item| Popped { item, slot, kept })
+}
+
+/// Removes every drained slot of `slots` and `rotation`, and returns the slot the pointer
+/// continues at: the one it was on, or the next one holding items when it was drained.
+#[cfg(feature = "alloc")]
+pub(crate) fn prune<S: Slots>(slots: &mut S, rotation: &mut Rotation, pointer: usize) -> usize {
+    let count = slots.count();
+    let mut pointed = if pointer < count {
+        rotation.walk(pointer).find(|&slot| !slots.is_drained(slot))
+    } else {
+        None
+    };
+
+    // Going down, every slot moved in by `swap_remove` has already been kept.
+    for slot in (0..count).rev() {
+        if slots.is_drained(slot) {
+            let last = slots.count() - 1;
+            slots.remove(slot);
+            rotation.remove(slot);
+            if pointed == Some(last) {
+                pointed = Some(slot);
+            }
+        }
+    }
+    pointed.unwrap_or_else(|| rotation.first())
+}
+
+/// Neighbours of a slot in a [`Rotation`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    prev: usize,
+    next: usize,
+}
+
+/// Arrival order of the slots of a storage that removes groups with `swap_remove`. Every
+/// slot links to the slots served before and after it in a ring starting at `head`, so a
+/// removal only relinks the neighbours of the removed slot and of the slot moved into it:
+/// the survivors keep their order in O(1).
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Rotation {
+    links: Vec<Link>,
+    head: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl Rotation {
+    pub(crate) const fn new() -> Self {
+        Self {
+            links: Vec::new(),
+            head: 0,
+        }
+    }
+
+    pub(crate) fn with_capacity(slots: usize) -> Self {
+        Self {
+            links: Vec::with_capacity(slots),
+            head: 0,
+        }
+    }
+
+    /// Returns the first slot in arrival order, or 0 when there are none.
+    #[inline(always)]
+    pub(crate) fn first(&self) -> usize {
+        self.head
+    }
+
+    /// Links the slot appended to the storage at the end of the rotation.
+    pub(crate) fn push(&mut self) {
+        let slot = self.links.len();
+        if slot == 0 {
+            self.head = 0;
+            self.links.push(Link { prev: 0, next: 0 });
+            return;
+        }
+        let tail = self.links[self.head].prev;
+        self.links.push(Link {
+            prev: tail,
+            next: self.head,
+        });
+        self.links[tail].next = slot;
+        self.links[self.head].prev = slot;
+    }
+
+    /// Returns the slot served before `slot`.
+    #[inline(always)]
+    pub(crate) fn prev(&self, slot: usize) -> usize {
+        self.links[slot].prev
+    }
+
+    /// Returns the slot served after `slot`.
+    #[inline(always)]
+    pub(crate) fn next(&self, slot: usize) -> usize {
+        self.links[slot].next
+    }
+
+    /// Unlinks `slot` and moves the last slot's links into it, mirroring `swap_remove` on the
+    /// storage. Returns the slot that followed `slot`, under its index after the move.
+    pub(crate) fn remove(&mut self, slot: usize) -> usize {
+        let last = self.links.len() - 1;
+        let Link { prev, next } = self.links[slot];
+        if next == slot {
+            self.clear();
+            return 0;
+        }
+        self.links[prev].next = next;
+        self.links[next].prev = prev;
+        if self.head == slot {
+            self.head = next;
+        }
+
+        self.links.swap_remove(slot);
+        let moved = |index: usize| if index == last { slot } else { index };
+        if slot != last {
+            let Link { prev, next } = self.links[slot];
+            let (prev, next) = (moved(prev), moved(next));
+            self.links[slot] = Link { prev, next };
+            self.links[prev].next = slot;
+            self.links[next].prev = slot;
+        }
+        self.head = moved(self.head);
+        moved(next)
+    }
+
+    /// Moves `slot` right before `before`, which must differ from it. Taking the place of the
+    /// first slot makes `slot` the new first one.
+    pub(crate) fn move_before(&mut self, slot: usize, before: usize) {
+        let Link { prev, next } = self.links[slot];
+        self.links[prev].next = next;
+        self.links[next].prev = prev;
+        if self.head == slot {
+            self.head = next;
+        }
+
+        let prev = self.links[before].prev;
+        self.links[prev].next = slot;
+        self.links[before].prev = slot;
+        self.links[slot] = Link { prev, next: before };
+        if self.head == before {
+            self.head = slot;
+        }
+    }
+
+    /// Iterates over every slot in arrival order.
+    pub(crate) fn iter(&self) -> Walk<'_> {
+        self.walk(self.head)
+    }
+
+    /// Iterates over every slot in arrival order, starting at `from` and wrapping around.
+    pub(crate) fn walk(&self, from: usize) -> Walk<'_> {
+        let count = self.links.len();
+        Walk {
+            links: &self.links,
+            front: from,
+            back: if count == 0 { 0 } else { self.links[from].prev },
+            remaining: count,
+        }
+    }
+
+    /// Returns the position of `slot` in arrival order. O(position).
+    pub(crate) fn position(&self, slot: usize) -> usize {
+        self.iter().position(|at| at == slot).unwrap_or(0)
+    }
+
+    /// Returns the slot at `position` in arrival order, which must be in range. O(position).
+    pub(crate) fn slot_at(&self, position: usize) -> usize {
+        self.iter().nth(position).unwrap_or(0)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.links.clear();
+        self.head = 0;
+    }
+
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.links.reserve(additional);
+    }
+
+    pub(crate) fn shrink_to(&mut self, min_capacity: usize) {
+        self.links.shrink_to(min_capacity);
+    }
+
+    /// Returns the heap bytes the links reserve.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.links.capacity() * core::mem::size_of::<Link>()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Order for Rotation {
+    #[inline(always)]
+    fn next(&self, slot: usize, _count: usize) -> usize {
+        Rotation::next(self, slot)
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, slot: usize, _count: usize) -> usize {
+        Rotation::remove(self, slot)
+    }
+}
+
+/// Slots of a [`Rotation`] in order, from either end.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub(crate) struct Walk<'r> {
+    links: &'r [Link],
+    front: usize,
+    back: usize,
+    remaining: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl Iterator for Walk<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let slot = self.front;
+        self.front = self.links[slot].next;
+        Some(slot)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl DoubleEndedIterator for Walk<'_> {
+    fn next_back(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let slot = self.back;
+        self.back = self.links[slot].prev;
+        Some(slot)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ExactSizeIterator for Walk<'_> {}
+
+/// FIFO groups, served front first.
+#[cfg(feature = "alloc")]
+impl<T> Slots for Vec<VecDeque<T>> {
+    type Item = T;
+
+    #[inline(always)]
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    #[inline(always)]
+    fn take(&mut self, slot: usize) -> Option<T> {
+        self[slot].pop_front()
+    }
+
+    #[inline(always)]
+    fn is_drained(&self, slot: usize) -> bool {
+        self[slot].is_empty()
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, slot: usize) {
+        Vec::swap_remove(self, slot);
+    }
+}
+
+/// FIFO groups stored with their key, served front first.
+#[cfg(feature = "alloc")]
+impl<K, T> Slots for Vec<(K, VecDeque<T>)> {
+    type Item = T;
+
+    #[inline(always)]
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    #[inline(always)]
+    fn take(&mut self, slot: usize) -> Option<T> {
+        self[slot].1.pop_front()
+    }
+
+    #[inline(always)]
+    fn is_drained(&self, slot: usize) -> bool {
+        self[slot].1.is_empty()
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, slot: usize) {
+        Vec::swap_remove(self, slot);
+    }
+}
+
+/// Groups served from the back, e.g. kept sorted with the next item last.
+#[cfg(feature = "alloc")]
+impl<T> Slots for Vec<Vec<T>> {
+    type Item = T;
+
+    #[inline(always)]
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    #[inline(always)]
+    fn take(&mut self, slot: usize) -> Option<T> {
+        self[slot].pop()
+    }
+
+    #[inline(always)]
+    fn is_drained(&self, slot: usize) -> bool {
+        self[slot].is_empty()
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, slot: usize) {
+        Vec::swap_remove(self, slot);
+    }
+}
+
+/// Cursors over borrowed FIFO groups, e.g. replaying a queue without touching it.
+#[cfg(feature = "alloc")]
+impl<'g, T> Slots for Vec<vec_deque::Iter<'g, T>> {
+    type Item = &'g T;
+
+    #[inline(always)]
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    #[inline(always)]
+    fn take(&mut self, slot: usize) -> Option<&'g T> {
+        self[slot].next()
+    }
+
+    #[inline(always)]
+    fn is_drained(&self, slot: usize) -> bool {
+        self[slot].len() == 0
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, slot: usize) {
+        Vec::swap_remove(self, slot);
+    }
+}
+
+/// Groups ordered by priority, served highest first.
+#[cfg(feature = "alloc")]
+impl<T: Ord> Slots for Vec<BinaryHeap<T>> {
+    type Item = T;
+
+    #[inline(always)]
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    #[inline(always)]
+    fn take(&mut self, slot: usize) -> Option<T> {
+        self[slot].pop()
+    }
+
+    #[inline(always)]
+    fn is_drained(&self, slot: usize) -> bool {
+        self[slot].is_empty()
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, slot: usize) {
+        Vec::swap_remove(self, slot);
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drained_slot_is_replaced_by_the_last() {
+        let mut slots: Vec<VecDeque<u32>> = alloc::vec![
+            VecDeque::from([0]),
+            VecDeque::from([1, 4]),
+            VecDeque::from([2]),
+        ];
+        let mut pointer = 7;
+
+        // The out-of-range pointer wraps; draining slot 0 moves slot 2 into it.
+        let popped = pop(&mut slots, &mut pointer, false).unwrap();
+        assert_eq!((popped.item, popped.slot, popped.kept), (0, 0, false));
+        assert_eq!(pointer, 0);
+        assert_eq!(slots.len(), 2);
+
+        let popped = pop(&mut slots, &mut pointer, false).unwrap();
+        assert_eq!((popped.item, popped.kept), (2, false));
+        assert_eq!(pointer, 0);
+
+        let popped = pop(&mut slots, &mut pointer, true).unwrap();
+        assert_eq!((popped.item, popped.kept), (1, true));
+        assert_eq!(pointer, 0);
+        assert_eq!(
+            pop(&mut slots, &mut pointer, false).map(|p| p.item),
+            Some(4)
+        );
+        assert!(pop(&mut slots, &mut pointer, false).is_none());
+
+        slots.push(VecDeque::new());
+        assert!(pop(&mut slots, &mut pointer, false).is_none());
+        assert!(slots.is_empty());
+    }
+
+    #[test]
+    fn rotation_keeps_arrival_order_across_removals() {
+        let mut slots: Vec<VecDeque<u32>> = alloc::vec![
+            VecDeque::from([0, 4]),
+            VecDeque::from([1]),
+            VecDeque::from([2, 5]),
+            VecDeque::from([3]),
+        ];
+        let mut rotation = Rotation::new();
+        slots.iter().for_each(|_| rotation.push());
+        let mut pointer = 0;
+
+        let mut popped = alloc::vec![];
+        while let Some(item) = pop_ordered(&mut slots, &mut rotation, &mut pointer, false) {
+            popped.push(item.item);
+        }
+        assert_eq!(popped, [0, 1, 2, 3, 4, 5]);
+
+        // Slot 3 moves into slot 1 once slot 1 drains, yet stays behind slot 2.
+        let mut rotation = Rotation::new();
+        (0..4).for_each(|_| rotation.push());
+        assert_eq!(rotation.remove(1), 2);
+        assert!(rotation.iter().eq([0, 2, 1]));
+        assert!(rotation.iter().rev().eq([1, 2, 0]));
+        // Slot 2 follows slot 0 and moves into it.
+        assert_eq!(rotation.remove(0), 0);
+        assert!(rotation.walk(1).eq([1, 0]));
+        assert_eq!(
+            (rotation.first(), rotation.position(1), rotation.slot_at(1)),
+            (0, 1, 1)
+        );
+
+        rotation.push();
+        rotation.move_before(2, 0);
+        assert!(rotation.iter().eq([2, 0, 1]));
+        assert_eq!(rotation.prev(0), 2);
+        assert_eq!(rotation.remove(2), 0);
+        assert_eq!(rotation.remove(0), 0);
+        assert_eq!(rotation.remove(0), 0);
+        assert_eq!(rotation.iter().len(), 0);
+    }
+}
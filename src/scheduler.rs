@@ -0,0 +1,141 @@
+use alloc::collections::{VecDeque, vec_deque};
+
+/// Read-only view of one group slot handed to a [`Scheduler`].
+pub trait SlotView {
+    /// Returns how many items the group still holds. Never 0 for a slot offered to a scheduler.
+    fn len(&self) -> usize;
+
+    /// Returns true when the group holds no items.
+    #[inline(always)]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> SlotView for VecDeque<T> {
+    #[inline(always)]
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+}
+
+impl<T> SlotView for vec_deque::Iter<'_, T> {
+    #[inline(always)]
+    fn len(&self) -> usize {
+        ExactSizeIterator::len(self)
+    }
+}
+
+/// Decides which group a [`FairQueue`](crate::FairQueue) serves next.
+///
+/// The queue keeps owning the round-robin pointer, group removal and the `len` bookkeeping;
+/// a scheduler only picks the slot to pop from. After every pop the pointer moves to the
+/// group after the served one in the rotation, exactly as in plain round-robin.
+///
+/// A custom scheduler must uphold these invariants:
+///
+/// - `next_slot` is only called with at least one group, every group non-empty, and
+///   `pointer < groups.len()`. `last_served` is the slot of the group the previous pop
+///   served, or `None` when that group emptied or nothing was popped yet.
+///   It must return a slot `< groups.len()`; an out-of-range slot is a bug, caught by a
+///   debug assertion and replaced by `pointer` in release builds.
+/// - `next_slot` takes `&self` and must be deterministic: given the same scheduler state and
+///   arguments it returns the same slot. `peek`, `pop_guard` and `iter` rely on this to
+///   predict `pop` without mutating anything.
+/// - State may only change in [`served`](Self::served), which the queue calls once per pop
+///   with the slot it popped from. `iter` replays `served` on a clone, so a scheduler used
+///   with `iter` must be `Clone` and its clone must behave identically.
+/// - Slots index the group storage, not the rotation: when a group empties, the last slot
+///   moves into its place.
+///
+/// Spacing between items of the same group is only guaranteed by [`RoundRobin`].
+pub trait Scheduler {
+    /// Returns the slot of the group the next pop serves.
+    fn next_slot<G: SlotView>(
+        &self,
+        pointer: usize,
+        last_served: Option<usize>,
+        groups: &[G],
+    ) -> usize;
+
+    /// Records that an item was popped from `slot`, before any emptied group is removed.
+    #[inline(always)]
+    fn served(&mut self, _slot: usize) {}
+}
+
+/// Strict round-robin, the default: serves the group under the pointer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RoundRobin;
+
+impl Scheduler for RoundRobin {
+    #[inline(always)]
+    fn next_slot<G: SlotView>(
+        &self,
+        pointer: usize,
+        _last_served: Option<usize>,
+        _groups: &[G],
+    ) -> usize {
+        pointer
+    }
+}
+
+/// Serves the group with the most queued items. Ties go to the first tied slot reached
+/// counting up from the pointer, so equally long groups still take turns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LongestQueueFirst;
+
+impl Scheduler for LongestQueueFirst {
+    fn next_slot<G: SlotView>(
+        &self,
+        pointer: usize,
+        _last_served: Option<usize>,
+        groups: &[G],
+    ) -> usize {
+        let count = groups.len();
+        let mut best = pointer;
+        for slot in (1..count).map(|step| (pointer + step) % count) {
+            if groups[slot].len() > groups[best].len() {
+                best = slot;
+            }
+        }
+        best
+    }
+}
+
+/// Built-in strategies selectable at runtime through
+/// [`FairQueue::with_strategy`](crate::FairQueue::with_strategy).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Strict round-robin, identical to [`RoundRobin`].
+    #[default]
+    RoundRobin,
+    /// Serves the group with the most queued items, skipping the group served by the previous
+    /// pop while any other group has items. Ties go to the lowest slot index. Slots are
+    /// positions, so which of two equally long groups wins can change as groups empty.
+    LongestFirst,
+}
+
+impl Scheduler for Strategy {
+    fn next_slot<G: SlotView>(
+        &self,
+        pointer: usize,
+        last_served: Option<usize>,
+        groups: &[G],
+    ) -> usize {
+        match self {
+            Self::RoundRobin => pointer,
+            Self::LongestFirst => {
+                let mut best: Option<usize> = None;
+                for slot in 0..groups.len() {
+                    if groups.len() > 1 && Some(slot) == last_served {
+                        continue;
+                    }
+                    if best.is_none_or(|best| groups[slot].len() > groups[best].len()) {
+                        best = Some(slot);
+                    }
+                }
+                best.unwrap_or(pointer)
+            }
+        }
+    }
+}
@@ -1,7 +1,15 @@
-use alloc::{vec, vec::Vec};
-use core::{ptr, slice};
+use alloc::vec::Vec;
+use core::{
+    fmt::{self, Debug},
+    iter::FusedIterator,
+    ptr, slice,
+};
 
-use crate::FairGroup;
+use crate::{
+    FairGroup, FairQueue, Scheduler,
+    queue::{DebugGroup, DebugGroups},
+    rotation,
+};
 
 /// Spatially distancing fair stack.
 /// Last in, first out inside a group while rotating across groups fairly.
@@ -39,30 +47,75 @@ pub struct FairStack<'a, V: FairGroup> {
     groups: Vec<Vec<&'a V>>,
     pointer: usize,
     len: usize,
+    group_capacity: usize,
 }
 
+/// Fair stack over `'static` data, embeddable in structs without a lifetime parameter.
+pub type StaticFairStack<V> = FairStack<'static, V>;
+
 impl<'a, V: FairGroup> FairStack<'a, V> {
     #[must_use]
     pub fn new() -> Self {
+        Self::with_group_capacity(0, 0)
+    }
+
+    /// Creates a stack with room for `groups` groups before the group list reallocates.
+    /// The hint counts groups, not items.
+    #[must_use]
+    pub fn with_capacity(groups: usize) -> Self {
+        Self::with_group_capacity(groups, 0)
+    }
+
+    /// Creates a stack with room for `groups` groups, where every group created later
+    /// reserves space for `per_group` items up front.
+    #[must_use]
+    pub fn with_group_capacity(groups: usize, per_group: usize) -> Self {
         Self {
-            groups: Vec::new(),
+            groups: Vec::with_capacity(groups),
             pointer: 0,
             len: 0,
+            group_capacity: per_group,
         }
     }
 
+    /// Reserves room for at least `additional_groups` more groups without pushing anything.
+    pub fn reserve(&mut self, additional_groups: usize) {
+        self.groups.reserve(additional_groups);
+    }
+
+    /// Builds a stack holding every item of `items`, pushed in slice order.
+    #[must_use]
+    pub fn from_slice(items: &'a [V]) -> Self {
+        let mut stack = Self::new();
+        for item in items {
+            stack.push(item);
+        }
+        stack
+    }
+
     /// Pushes an item while ensuring the group keeps participating in round-robin order.
     pub fn push(&mut self, value: &'a V) {
-        if let Some(group) = self.groups.iter_mut().find(|group| {
+        self.push_tracked(value);
+    }
+
+    /// Pushes like `push` and returns true when the item opened a new group,
+    /// false when it joined an existing one.
+    pub fn push_tracked(&mut self, value: &'a V) -> bool {
+        let created = if let Some(group) = self.groups.iter_mut().find(|group| {
             group
                 .last()
                 .is_some_and(|v| ptr::eq(*v, value) || (*v).is_same_group(value))
         }) {
             group.push(value);
+            false
         } else {
-            self.groups.push(vec![value]);
-        }
+            let mut new_group = Vec::with_capacity(self.group_capacity);
+            new_group.push(value);
+            self.groups.push(new_group);
+            true
+        };
         self.len += 1;
+        created
     }
 
     /// Pops the next item while rotating across groups fairly.
@@ -72,42 +125,21 @@ impl<'a, V: FairGroup> FairStack<'a, V> {
             return None;
         }
 
-        loop {
-            if self.groups.is_empty() {
-                self.pointer = 0;
-                return None;
-            }
-
-            if self.pointer >= self.groups.len() {
-                self.pointer = 0;
-            }
-
-            let group = &mut self.groups[self.pointer];
-
-            if let Some(item) = group.pop() {
+        while !self.groups.is_empty() {
+            if let Some(popped) = rotation::pop(&mut self.groups, &mut self.pointer, false) {
                 self.len -= 1;
-
-                if group.is_empty() {
-                    self.groups.swap_remove(self.pointer);
-                    if self.groups.is_empty() || self.pointer >= self.groups.len() {
-                        self.pointer = 0;
-                    }
-                } else if !self.groups.is_empty() {
-                    self.pointer = (self.pointer + 1) % self.groups.len();
-                }
-
-                return Some(item);
-            }
-
-            self.groups.swap_remove(self.pointer);
-            if self.groups.is_empty() {
-                self.pointer = 0;
-                return None;
-            }
-            if self.pointer >= self.groups.len() {
-                self.pointer = 0;
+                return Some(popped.item);
             }
         }
+        self.pointer = 0;
+        None
+    }
+
+    /// Pops every item in fair order through an iterator.
+    /// The stack is left empty with its pointer reset once the iterator is dropped,
+    /// even if iteration stopped early.
+    pub fn drain(&mut self) -> StackDrain<'_, 'a, V> {
+        StackDrain { stack: self }
     }
 
     /// Peeks at the next item due for popping.
@@ -134,6 +166,49 @@ impl<'a, V: FairGroup> FairStack<'a, V> {
             .and_then(|group| group.last().copied())
     }
 
+    /// Returns true when a group matching `sample` holds items, without fetching its top.
+    #[must_use]
+    pub fn is_group_present(&self, sample: &V) -> bool {
+        self.groups.iter().any(|group| {
+            group
+                .last()
+                .is_some_and(|v| ptr::eq(*v, sample) || (*v).is_same_group(sample))
+        })
+    }
+
+    /// Iterates over every item of the group of `sample` top to bottom, the order that group
+    /// is served in. Yields nothing when no group matches.
+    pub fn iter_group(&self, sample: &V) -> impl Iterator<Item = &'a V> + '_ {
+        self.groups
+            .iter()
+            .find(|group| {
+                group
+                    .last()
+                    .is_some_and(|v| ptr::eq(*v, sample) || (*v).is_same_group(sample))
+            })
+            .into_iter()
+            .flat_map(|group| group.iter().rev().copied())
+    }
+
+    /// Returns how many items the group of `sample` holds, or 0 when it has none stacked.
+    #[must_use]
+    pub fn len_of_group(&self, sample: &V) -> usize {
+        self.groups
+            .iter()
+            .find(|group| {
+                group
+                    .last()
+                    .is_some_and(|v| ptr::eq(*v, sample) || (*v).is_same_group(sample))
+            })
+            .map_or(0, Vec::len)
+    }
+
+    /// Returns true if any stacked item satisfies `f`, stopping at the first match.
+    #[must_use]
+    pub fn contains<F: FnMut(&V) -> bool>(&self, mut f: F) -> bool {
+        self.groups.iter().flatten().any(|value| f(value))
+    }
+
     /// Returns the number of enqueued items.
     #[inline(always)]
     #[must_use]
@@ -155,6 +230,21 @@ impl<'a, V: FairGroup> FairStack<'a, V> {
         self.groups.len()
     }
 
+    /// Returns the raw round-robin pointer: the slot the next pop starts from. Slots follow
+    /// the current layout, which reorders as groups empty, so a saved pointer is only
+    /// meaningful for the same layout, e.g. a `Clone` taken at the same moment.
+    #[inline(always)]
+    #[must_use]
+    pub fn pointer(&self) -> usize {
+        self.pointer
+    }
+
+    /// Moves the round-robin pointer to slot `pointer`, clamped to `0..group_count`.
+    /// Slots follow the current layout; see [`pointer`](Self::pointer).
+    pub fn set_pointer(&mut self, pointer: usize) {
+        self.pointer = pointer.min(self.groups.len().saturating_sub(1));
+    }
+
     /// Iterates over the current top item of each group without consuming them.
     #[inline(always)]
     #[must_use]
@@ -164,6 +254,21 @@ impl<'a, V: FairGroup> FairStack<'a, V> {
         }
     }
 
+    /// Counts the groups whose head item (the top of the group, as in `group_heads`) satisfies `f`.
+    #[must_use]
+    pub fn group_count_matching<F: FnMut(&V) -> bool>(&self, mut f: F) -> usize {
+        self.group_heads().filter(|head| f(head)).count()
+    }
+
+    /// Iterates over the item count of each group, in the same order as `group_heads`.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_lens(&self) -> StackGroupLens<'_, 'a, V> {
+        StackGroupLens {
+            iter: self.groups.iter(),
+        }
+    }
+
     /// Collects group heads into a vector (requires the `std` feature).
     #[cfg(feature = "std")]
     #[must_use]
@@ -171,12 +276,138 @@ impl<'a, V: FairGroup> FairStack<'a, V> {
         self.group_heads().collect()
     }
 
+    /// Consumes the stack, returning every item in the exact order `pop` would yield them
+    /// (requires the `std` feature).
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn into_pop_order_vec(self) -> std::vec::Vec<&'a V> {
+        self.into_iter().collect()
+    }
+
+    /// Keeps only the items for which `f` returns true, preserving their order within each group.
+    /// Groups left empty are dropped and the rotation resumes at the next surviving group.
+    pub fn retain<F: FnMut(&V) -> bool>(&mut self, mut f: F) {
+        let pointer = self.pointer;
+        let mut slot = 0;
+        let mut survivors_before_pointer = 0;
+
+        for group in &mut self.groups {
+            group.retain(|value| f(value));
+        }
+        self.len = self.groups.iter().map(Vec::len).sum();
+        self.groups.retain(|group| {
+            let keep = !group.is_empty();
+            if keep && slot < pointer {
+                survivors_before_pointer += 1;
+            }
+            slot += 1;
+            keep
+        });
+
+        self.pointer = if survivors_before_pointer < self.groups.len() {
+            survivors_before_pointer
+        } else {
+            0
+        };
+    }
+
+    /// Discards the group matching `sample` and returns how many items it held (0 if none).
+    /// The other groups keep their rotation order; a pointer past the removed slot moves
+    /// back with its group, and one on the removed slot moves to the following group.
+    pub fn clear_group(&mut self, sample: &V) -> usize {
+        let Some(slot) = self.groups.iter().position(|group| {
+            group
+                .last()
+                .is_some_and(|v| ptr::eq(*v, sample) || (*v).is_same_group(sample))
+        }) else {
+            return 0;
+        };
+
+        let removed = self.groups.remove(slot).len();
+        self.len -= removed;
+        if self.pointer > slot {
+            self.pointer -= 1;
+        }
+        if self.pointer >= self.groups.len() {
+            self.pointer = 0;
+        }
+        removed
+    }
+
+    /// Releases spare capacity of the group list and of every remaining group.
+    /// Items, their order and the rotation are left untouched.
+    pub fn shrink_to_fit(&mut self) {
+        self.groups.shrink_to_fit();
+        for group in &mut self.groups {
+            group.shrink_to_fit();
+        }
+    }
+
     /// Clears all items and resets the round-robin pointer.
     pub fn clear(&mut self) {
         self.groups.clear();
         self.pointer = 0;
         self.len = 0;
     }
+
+    /// Splits the stack into its groups, bottom first, and the raw pointer.
+    pub(crate) fn into_groups(self) -> (Vec<Vec<&'a V>>, usize) {
+        (self.groups, self.pointer)
+    }
+}
+
+/// Snapshots the scheduling state. Only references are copied, so `V` need not be `Clone`.
+impl<V: FairGroup> Clone for FairStack<'_, V> {
+    fn clone(&self) -> Self {
+        Self {
+            groups: self.groups.clone(),
+            pointer: self.pointer,
+            len: self.len,
+            group_capacity: self.group_capacity,
+        }
+    }
+}
+
+/// Shows `len`, `group_count`, the raw `pointer` and every group bottom to top.
+/// The group the next `pop` serves is prefixed with `> `.
+impl<V: FairGroup + Debug> Debug for FairStack<'_, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let current = if self.pointer < self.groups.len() {
+            self.pointer
+        } else {
+            0
+        };
+        f.debug_struct("FairStack")
+            .field("len", &self.len)
+            .field("group_count", &self.groups.len())
+            .field("pointer", &self.pointer)
+            .field(
+                "groups",
+                &DebugGroups(
+                    self.groups
+                        .iter()
+                        .enumerate()
+                        .map(|(slot, group)| DebugGroup {
+                            current: slot == current,
+                            items: group.iter(),
+                        }),
+                ),
+            )
+            .finish()
+    }
+}
+
+/// Emits the groups bottom to top in their current slot order followed by the raw `pointer`.
+#[cfg(feature = "serde")]
+impl<V: FairGroup + serde::Serialize> serde::Serialize for FairStack<'_, V> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("FairStack", 2)?;
+        state.serialize_field("groups", &self.groups)?;
+        state.serialize_field("pointer", &self.pointer)?;
+        state.end()
+    }
 }
 
 impl<V: FairGroup> Default for FairStack<'_, V> {
@@ -185,24 +416,146 @@ impl<V: FairGroup> Default for FairStack<'_, V> {
     }
 }
 
+impl<'a, V: FairGroup> Extend<&'a V> for FairStack<'a, V> {
+    /// Pushes every item in iteration order, exactly like repeated `push` calls.
+    fn extend<I: IntoIterator<Item = &'a V>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<'a, V: FairGroup> FromIterator<&'a V> for FairStack<'a, V> {
+    fn from_iter<I: IntoIterator<Item = &'a V>>(iter: I) -> Self {
+        let mut stack = Self::new();
+        stack.extend(iter);
+        stack
+    }
+}
+
+/// Moves the groups of a queue over, keeping their slots, the pointer and `len`.
+/// Within each group the front of the queue becomes the bottom of the stack and the back
+/// becomes the top, so items keep their insertion order: the queue would have served a
+/// group oldest first, the stack serves it newest first. The scheduler, removal strategy
+/// and insert limits of the queue are dropped.
+impl<'a, V: FairGroup, S: Scheduler> From<FairQueue<'a, V, S>> for FairStack<'a, V> {
+    fn from(queue: FairQueue<'a, V, S>) -> Self {
+        let len = queue.len();
+        let (groups, pointer) = queue.into_groups();
+        Self {
+            groups: groups.into_iter().map(Vec::from).collect(),
+            pointer,
+            len,
+            ..Self::new()
+        }
+    }
+}
+
+/// Iterator over the item count of each group.
+pub struct StackGroupLens<'stack, 'value, V: FairGroup> {
+    iter: slice::Iter<'stack, Vec<&'value V>>,
+}
+
+impl<V: FairGroup> Iterator for StackGroupLens<'_, '_, V> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(Vec::len)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<V: FairGroup> ExactSizeIterator for StackGroupLens<'_, '_, V> {}
+
+impl<'a, V: FairGroup> IntoIterator for FairStack<'a, V> {
+    type Item = &'a V;
+    type IntoIter = StackIntoIter<'a, V>;
+
+    /// Consumes the stack, yielding items in the order `pop` would return them.
+    fn into_iter(self) -> Self::IntoIter {
+        StackIntoIter { stack: self }
+    }
+}
+
+/// Owning iterator over a [`FairStack`] in fair pop order.
+pub struct StackIntoIter<'a, V: FairGroup> {
+    stack: FairStack<'a, V>,
+}
+
+impl<'a, V: FairGroup> Iterator for StackIntoIter<'a, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.stack.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.stack.len, Some(self.stack.len))
+    }
+}
+
+impl<V: FairGroup> ExactSizeIterator for StackIntoIter<'_, V> {}
+
+impl<V: FairGroup> FusedIterator for StackIntoIter<'_, V> {}
+
+/// Draining iterator returned by [`FairStack::drain`].
+pub struct StackDrain<'stack, 'value, V: FairGroup> {
+    stack: &'stack mut FairStack<'value, V>,
+}
+
+impl<'value, V: FairGroup> Iterator for StackDrain<'_, 'value, V> {
+    type Item = &'value V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.stack.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.stack.len, Some(self.stack.len))
+    }
+}
+
+impl<V: FairGroup> ExactSizeIterator for StackDrain<'_, '_, V> {}
+
+impl<V: FairGroup> FusedIterator for StackDrain<'_, '_, V> {}
+
+impl<V: FairGroup> Drop for StackDrain<'_, '_, V> {
+    fn drop(&mut self) {
+        self.stack.clear();
+    }
+}
+
 /// Iterator over the last element of each group.
 pub struct StackGroupHeads<'stack, 'value, V: FairGroup> {
     iter: slice::Iter<'stack, Vec<&'value V>>,
 }
 
-impl<'stack, 'value, V: FairGroup> Iterator for StackGroupHeads<'stack, 'value, V> {
+impl<'value, V: FairGroup> Iterator for StackGroupHeads<'_, 'value, V> {
     type Item = &'value V;
 
     fn next(&mut self) -> Option<Self::Item> {
-        for group in &mut self.iter {
-            if let Some(item) = group.last() {
-                return Some(*item);
-            }
-        }
-        None
+        self.iter.next().and_then(|group| group.last().copied())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
     }
 }
 
+impl<V: FairGroup> DoubleEndedIterator for StackGroupHeads<'_, '_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next_back()
+            .and_then(|group| group.last().copied())
+    }
+}
+
+/// Exact because tracked groups are never empty, so every group yields one head.
+impl<V: FairGroup> ExactSizeIterator for StackGroupHeads<'_, '_, V> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -297,6 +650,212 @@ mod tests {
         assert_eq!(stack.group_count(), 0);
     }
 
+    #[test]
+    fn test_group_lens_sum_to_len() {
+        const IDS: [&str; 3] = ["g0", "g1", "g2"];
+
+        let events: Vec<Event> = [0, 1, 0, 2, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+
+        let mut stack: FairStack<_> = events.iter().collect();
+        let lens = stack.group_lens();
+        assert_eq!(lens.len(), stack.group_count());
+        assert_eq!(lens.collect::<Vec<_>>(), vec![3, 1, 1]);
+
+        stack.pop();
+        stack.pop();
+        assert_eq!(stack.group_lens().len(), stack.group_heads().count());
+        assert_eq!(stack.group_lens().sum::<usize>(), stack.len());
+    }
+
+    #[test]
+    fn test_contains_scans_all_groups() {
+        let a = Event {
+            timestamp: 1,
+            user_id: "user1",
+            group: 0,
+        };
+        let b = Event {
+            timestamp: 2,
+            user_id: "user2",
+            group: 1,
+        };
+        let mut stack = FairStack::new();
+        stack.push(&a);
+        stack.push(&b);
+
+        assert!(stack.contains(|e| e.user_id == "user2"));
+        assert!(!stack.contains(|e| e.timestamp == 3));
+        stack.pop();
+        assert!(!stack.contains(|e| e.timestamp == 1));
+        assert!(stack.contains(|e| e.timestamp == 2));
+    }
+
+    #[test]
+    fn test_len_of_group_tracks_backlog() {
+        const IDS: [&str; 3] = ["g0", "g1", "g2"];
+
+        let events: Vec<Event> = [0, 1, 0, 0, 2]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+
+        let mut stack: FairStack<_> = events[..4].iter().collect();
+        assert_eq!(stack.len_of_group(&events[0]), 3);
+        assert_eq!(stack.len_of_group(&events[1]), 1);
+        assert_eq!(stack.len_of_group(&events[4]), 0);
+
+        stack.pop();
+        stack.pop();
+        stack.push(&events[4]);
+        assert_eq!(stack.len_of_group(&events[0]), 2);
+        assert_eq!(stack.len_of_group(&events[1]), 0);
+        assert_eq!(stack.len_of_group(&events[4]), 1);
+    }
+
+    #[test]
+    fn test_push_tracked_reports_new_groups() {
+        const IDS: [&str; 3] = ["g0", "g1", "g2"];
+
+        let events: Vec<Event> = [0, 1, 0, 2, 1]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+
+        let mut stack = FairStack::new();
+        let created: Vec<bool> = events.iter().map(|e| stack.push_tracked(e)).collect();
+        assert_eq!(created, vec![true, true, false, true, false]);
+        assert_eq!(stack.len(), 5);
+        assert_eq!(stack.group_count(), 3);
+
+        assert_eq!(stack.clear_group(&events[3]), 1);
+        assert!(stack.push_tracked(&events[3]));
+        assert!(!stack.push_tracked(&events[0]));
+    }
+
+    #[test]
+    fn test_iter_group_follows_dispatch_order() {
+        const IDS: [&str; 3] = ["g0", "g1", "g2"];
+
+        let events: Vec<Event> = [0, 1, 0, 2, 0, 1]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+
+        let mut stack: FairStack<_> = events.iter().collect();
+        let group: Vec<u32> = stack.iter_group(&events[0]).map(|e| e.timestamp).collect();
+        assert_eq!(group, vec![4, 2, 0]);
+        assert_eq!(stack.iter_group(&events[3]).count(), 1);
+
+        let dispatched: Vec<u32> = core::iter::from_fn(|| stack.pop())
+            .filter(|e| e.group == 0)
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(group, dispatched);
+    }
+
+    #[test]
+    fn test_set_pointer_restores_schedule() {
+        const IDS: [&str; 3] = ["g0", "g1", "g2"];
+
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 2]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+        let mut stack: FairStack<_> = events.iter().collect();
+        stack.pop();
+
+        let saved = stack.pointer();
+        let expected = stack.peek();
+        stack.set_pointer(saved + 1);
+        assert_ne!(stack.peek(), expected);
+        stack.set_pointer(saved);
+        assert_eq!(stack.pop(), expected);
+
+        stack.set_pointer(99);
+        assert_eq!(stack.pointer(), stack.group_count() - 1);
+        let mut empty: FairStack<Event> = FairStack::new();
+        empty.set_pointer(5);
+        assert_eq!(empty.pointer(), 0);
+    }
+
+    #[test]
+    fn test_is_group_present() {
+        const IDS: [&str; 2] = ["g0", "g1"];
+
+        let events: Vec<Event> = [0, 1, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+
+        let mut stack = FairStack::new();
+        assert!(!stack.is_group_present(&events[0]));
+        stack.push(&events[0]);
+        assert!(stack.is_group_present(&events[2]));
+        assert!(!stack.is_group_present(&events[1]));
+        stack.push(&events[1]);
+        assert!(stack.is_group_present(&events[1]));
+
+        stack.pop();
+        assert!(!stack.is_group_present(&events[0]));
+        assert!(stack.is_group_present(&events[1]));
+    }
+
+    #[test]
+    fn test_group_count_matching() {
+        const IDS: [&str; 4] = ["g0", "g1", "g2", "g3"];
+
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 3]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+
+        let mut stack: FairStack<_> = events.iter().collect();
+        assert_eq!(stack.group_count_matching(|e| e.timestamp >= 3), 3);
+        assert_eq!(stack.group_count_matching(|_| true), stack.group_count());
+
+        assert_eq!(stack.pop().map(|e| e.timestamp), Some(3));
+        assert_eq!(stack.group_count_matching(|e| e.timestamp >= 3), 2);
+        assert_eq!(stack.group_count_matching(|e| e.timestamp > 5), 0);
+    }
+
     #[test]
     fn test_group_heads_snapshot() {
         let a1 = Event {
@@ -326,7 +885,12 @@ mod tests {
         stack.push(&b1);
         stack.push(&c1);
 
+        assert_eq!(stack.group_heads().len(), stack.group_count());
+        let reversed: Vec<&Event> = stack.group_heads().rev().collect();
+        assert_eq!(reversed, [&c1, &b1, &a2]);
+
         let mut heads = stack.group_heads();
+        assert_eq!(heads.len(), 3);
         assert_eq!(heads.next(), Some(&a2));
         assert_eq!(heads.next(), Some(&b1));
         assert_eq!(heads.next(), Some(&c1));
@@ -382,6 +946,322 @@ mod tests {
         assert!(stack.is_empty());
     }
 
+    #[test]
+    fn test_static_stack_from_slice() {
+        static EVENTS: [Event; 3] = [
+            Event {
+                timestamp: 1,
+                user_id: "user1",
+                group: 0,
+            },
+            Event {
+                timestamp: 2,
+                user_id: "user1",
+                group: 0,
+            },
+            Event {
+                timestamp: 3,
+                user_id: "user2",
+                group: 1,
+            },
+        ];
+
+        let mut stack: StaticFairStack<Event> = FairStack::from_slice(&EVENTS);
+        assert_eq!(stack.pop(), Some(&EVENTS[1]));
+        assert_eq!(stack.pop(), Some(&EVENTS[2]));
+        assert_eq!(stack.pop(), Some(&EVENTS[0]));
+        assert!(stack.pop().is_none());
+    }
+
+    #[test]
+    fn test_clear_group_mid_stack() {
+        const IDS: [&str; 3] = ["g0", "g1", "g2"];
+
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 2]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+        let mut stack: FairStack<_> = events.iter().collect();
+        stack.pop();
+
+        assert_eq!(stack.clear_group(&events[1]), 2);
+        assert_eq!(stack.clear_group(&events[1]), 0);
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack.group_count(), 2);
+
+        let order: Vec<u32> = stack.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![5, 0, 2]);
+    }
+
+    #[test]
+    fn test_shrink_to_fit_keeps_order() {
+        const IDS: [&str; 4] = ["g0", "g1", "g2", "g3"];
+
+        let events: Vec<Event> = (0..256)
+            .map(|idx| Event {
+                timestamp: idx,
+                user_id: IDS[idx as usize % 4],
+                group: idx as usize % 4,
+            })
+            .collect();
+        let mut stack: FairStack<_> = events.iter().collect();
+        for _ in 0..240 {
+            stack.pop();
+        }
+
+        let expected: Vec<_> = stack.clone().into_iter().collect();
+        let (len, groups, pointer) = (stack.len(), stack.group_count(), stack.pointer);
+        let capacity = stack.groups.iter().map(Vec::capacity).sum::<usize>();
+        stack.shrink_to_fit();
+
+        assert!(stack.groups.iter().map(Vec::capacity).sum::<usize>() < capacity);
+        assert_eq!(
+            (stack.len(), stack.group_count(), stack.pointer),
+            (len, groups, pointer)
+        );
+        assert_eq!(stack.drain().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_capacity_hints() {
+        let a1 = Event {
+            timestamp: 1,
+            user_id: "user1",
+            group: 0,
+        };
+        let b1 = Event {
+            timestamp: 2,
+            user_id: "user2",
+            group: 1,
+        };
+
+        let mut stack = FairStack::with_group_capacity(4, 8);
+        assert!(stack.groups.capacity() >= 4);
+        stack.push(&a1);
+        stack.push(&b1);
+        assert!(stack.groups.iter().all(|group| group.capacity() >= 8));
+
+        stack.reserve(16);
+        assert!(stack.groups.capacity() >= 18);
+        assert_eq!(stack.len(), 2);
+
+        let stack = FairStack::<Event>::with_capacity(6);
+        assert!(stack.groups.capacity() >= 6);
+    }
+
+    #[test]
+    fn test_partial_drain_clears_on_drop() {
+        let a1 = Event {
+            timestamp: 1,
+            user_id: "user1",
+            group: 0,
+        };
+        let a2 = Event {
+            timestamp: 2,
+            user_id: "user1",
+            group: 0,
+        };
+        let b1 = Event {
+            timestamp: 3,
+            user_id: "user2",
+            group: 1,
+        };
+
+        let mut stack = FairStack::new();
+        stack.push(&a1);
+        stack.push(&a2);
+        stack.push(&b1);
+
+        {
+            let mut drain = stack.drain();
+            assert_eq!(drain.size_hint(), (3, Some(3)));
+            assert_eq!(drain.next(), Some(&a2));
+            assert_eq!(drain.next(), Some(&b1));
+        }
+        assert!(stack.is_empty());
+        assert_eq!(stack.group_count(), 0);
+
+        stack.push(&a1);
+        stack.push(&b1);
+        assert_eq!(stack.drain().collect::<Vec<_>>(), vec![&a1, &b1]);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn test_into_iter_matches_pop_loop() {
+        let a1 = Event {
+            timestamp: 1,
+            user_id: "user1",
+            group: 0,
+        };
+        let a2 = Event {
+            timestamp: 2,
+            user_id: "user1",
+            group: 0,
+        };
+        let b1 = Event {
+            timestamp: 3,
+            user_id: "user2",
+            group: 1,
+        };
+
+        let mut stack = FairStack::new();
+        stack.push(&a1);
+        stack.push(&a2);
+        stack.push(&b1);
+
+        let mut drained = stack.clone();
+        let mut drain = drained.drain();
+        for remaining in (0..3).rev() {
+            assert!(drain.next().is_some());
+            assert_eq!(drain.len(), remaining);
+        }
+        drop(drain);
+
+        let mut iter = stack.into_iter();
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some(&a2));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.collect::<Vec<_>>(), vec![&b1, &a1]);
+    }
+
+    #[test]
+    fn test_clone_forks_mid_drain() {
+        const IDS: [&str; 3] = ["g0", "g1", "g2"];
+
+        let events: Vec<Event> = [0, 1, 0, 2, 1, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+
+        let mut stack: FairStack<_> = events.iter().collect();
+        stack.pop();
+
+        let mut fork = stack.clone();
+        let forked: Vec<u32> = fork.drain().map(|e| e.timestamp).collect();
+        assert!(fork.is_empty());
+
+        assert_eq!(stack.len(), events.len() - 1);
+        let remaining: Vec<u32> = stack.drain().map(|e| e.timestamp).collect();
+        assert_eq!(remaining, forked);
+    }
+
+    #[test]
+    fn test_debug_marks_pointer_group() {
+        let a = Event {
+            timestamp: 1,
+            user_id: "user1",
+            group: 0,
+        };
+        let b = Event {
+            timestamp: 2,
+            user_id: "user2",
+            group: 1,
+        };
+
+        let mut stack = FairStack::new();
+        stack.push(&a);
+        stack.push(&b);
+
+        let dump = format!("{stack:?}");
+        assert!(dump.starts_with("FairStack { len: 2, group_count: 2, pointer: 0, groups: ["));
+        assert!(dump.contains("> [Event { timestamp: 1"));
+        assert_eq!(dump.matches("> ").count(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_lists_groups_and_pointer() {
+        #[derive(Debug, PartialEq, serde::Serialize)]
+        struct Job {
+            id: u32,
+            group: usize,
+        }
+
+        impl FairGroup for Job {
+            fn is_same_group(&self, other: &Self) -> bool {
+                self.group == other.group
+            }
+        }
+
+        let jobs = [
+            Job { id: 1, group: 0 },
+            Job { id: 2, group: 1 },
+            Job { id: 3, group: 0 },
+        ];
+        let mut stack: FairStack<_> = jobs.iter().collect();
+        stack.pop();
+
+        let json = serde_json::to_string(&stack).unwrap();
+        assert_eq!(
+            json,
+            r#"{"groups":[[{"id":1,"group":0}],[{"id":2,"group":1}]],"pointer":1}"#
+        );
+    }
+
+    #[test]
+    fn test_retain_purges_across_groups() {
+        const IDS: [&str; 3] = ["g0", "g1", "g2"];
+
+        let events: Vec<Event> = [0, 1, 2, 0, 1, 2, 1]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+
+        let mut stack: FairStack<_> = events.iter().collect();
+        assert_eq!(stack.pop().map(|e| e.timestamp), Some(3));
+
+        // The pointer references group 1, which disappears entirely, so the
+        // rotation resumes at group 2.
+        stack.retain(|e| e.group != 1 && e.timestamp != 5);
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack.group_count(), 2);
+        assert_eq!(stack.pop().map(|e| e.timestamp), Some(2));
+        assert_eq!(stack.pop().map(|e| e.timestamp), Some(0));
+        assert!(stack.pop().is_none());
+    }
+
+    #[test]
+    fn test_from_queue_maps_front_to_bottom() {
+        const IDS: [&str; 3] = ["g0", "g1", "g2"];
+
+        let events: Vec<Event> = [0, 1, 0, 2, 0]
+            .iter()
+            .enumerate()
+            .map(|(idx, &group)| Event {
+                timestamp: idx as u32,
+                user_id: IDS[group],
+                group,
+            })
+            .collect();
+
+        let mut queue: FairQueue<_> = events.iter().collect();
+        assert_eq!(queue.pop().map(|e| e.timestamp), Some(0));
+
+        // Slots and the pointer carry over; group 0 now serves its newest item first.
+        let mut stack = FairStack::from(queue);
+        assert_eq!(stack.len(), 4);
+        assert_eq!(stack.group_count(), 3);
+        let order: Vec<u32> = stack.drain().map(|e| e.timestamp).collect();
+        assert_eq!(order, vec![1, 3, 4, 2]);
+    }
+
     proptest! {
         #[test]
         fn prop_stack_preserves_spacing(groups in proptest::collection::vec(0usize..4, 1..32)) {
@@ -423,5 +1303,59 @@ mod tests {
                 last_group = Some(gid);
             }
         }
+
+        #[test]
+        fn prop_collect_matches_push_loop(groups in proptest::collection::vec(0usize..4, 0..32)) {
+            const IDS: [&str; 4] = ["g0", "g1", "g2", "g3"];
+
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, group)| Event {
+                    timestamp: idx as u32,
+                    user_id: IDS[*group],
+                    group: *group,
+                })
+                .collect();
+
+            let mut manual = FairStack::new();
+            for event in &events {
+                manual.push(event);
+            }
+            let mut collected: FairStack<_> = events.iter().collect();
+
+            prop_assert_eq!(collected.len(), manual.len());
+            prop_assert_eq!(collected.group_count(), manual.group_count());
+            while let Some(expected) = manual.pop() {
+                prop_assert_eq!(collected.pop().map(|e| e.timestamp), Some(expected.timestamp));
+            }
+            prop_assert!(collected.pop().is_none());
+        }
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn prop_into_pop_order_vec_matches_pop_loop(
+            groups in proptest::collection::vec(0usize..4, 0..32)
+        ) {
+            const IDS: [&str; 4] = ["g0", "g1", "g2", "g3"];
+
+            let events: Vec<Event> = groups
+                .iter()
+                .enumerate()
+                .map(|(idx, group)| Event {
+                    timestamp: idx as u32,
+                    user_id: IDS[*group],
+                    group: *group,
+                })
+                .collect();
+
+            let mut manual: FairStack<_> = events.iter().collect();
+            let stack = manual.clone();
+            let mut expected = Vec::new();
+            while let Some(event) = manual.pop() {
+                expected.push(event);
+            }
+            prop_assert_eq!(stack.into_pop_order_vec(), expected);
+        }
     }
 }
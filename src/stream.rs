@@ -0,0 +1,332 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::{sync::Mutex, vec::Vec};
+
+use futures_core::Stream;
+
+use crate::{FairGroup, SyncFairQueue};
+
+/// Wake-up bookkeeping shared by producers and consumers.
+#[derive(Default)]
+struct StreamState {
+    closed: bool,
+    wakers: Vec<Waker>,
+}
+
+/// Asynchronous consumer view over a [`SyncFairQueue`] (requires the `async` feature).
+///
+/// `poll_next` follows the `Stream::poll_next` contract: it yields the next item in fair order,
+/// returns `Poll::Pending` while the queue is empty and wakes the task on the next `push`, and
+/// returns `Poll::Ready(None)` once the stream is closed and drained. Items still come out in
+/// exactly the order `FairQueue::pop` defines, regardless of how polls interleave with pushes.
+/// A shared reference implements [`Stream`], so consumers can use stream combinators, or
+/// await [`next`](Self::next) directly.
+///
+/// ```
+/// use core::task::{Context, Poll, Waker};
+/// use fairqueue::{FairGroup, FairQueueStream};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     user_id: u32,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.user_id == other.user_id
+///     }
+/// }
+///
+/// let event = Event { user_id: 1 };
+/// let stream = FairQueueStream::new();
+/// let mut cx = Context::from_waker(Waker::noop());
+///
+/// assert_eq!(stream.poll_next(&mut cx), Poll::Pending);
+/// stream.push(&event);
+/// assert_eq!(stream.poll_next(&mut cx), Poll::Ready(Some(&event)));
+/// stream.close();
+/// assert_eq!(stream.poll_next(&mut cx), Poll::Ready(None));
+/// ```
+pub struct FairQueueStream<'a, V> {
+    queue: SyncFairQueue<'a, V>,
+    state: Mutex<StreamState>,
+}
+
+impl<'a, V: FairGroup> FairQueueStream<'a, V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_queue(SyncFairQueue::new())
+    }
+
+    /// Streams the items of an existing shared queue, keeping its rotation.
+    #[must_use]
+    pub fn from_queue(queue: SyncFairQueue<'a, V>) -> Self {
+        Self {
+            queue,
+            state: Mutex::new(StreamState::default()),
+        }
+    }
+
+    /// Inserts an item and wakes every task waiting for one.
+    /// Items pushed after `close` are still delivered before the stream ends.
+    pub fn push(&self, value: &'a V) {
+        self.queue.insert(value);
+        self.wake_all();
+    }
+
+    /// Marks the stream as finished; consumers get `None` once the queue is drained.
+    pub fn close(&self) {
+        self.lock_state().closed = true;
+        self.wake_all();
+    }
+
+    /// Returns true once `close` has been called.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.lock_state().closed
+    }
+
+    /// Attempts to pop the next item, registering the task's waker when none is available.
+    pub fn poll_next(&self, cx: &mut Context<'_>) -> Poll<Option<&'a V>> {
+        // Holding the state lock across the pop means a concurrent `push` either lands
+        // before the pop or wakes the waker registered below; no wake-up is lost.
+        let mut state = self.lock_state();
+        if let Some(item) = self.queue.pop() {
+            return Poll::Ready(Some(item));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+
+    /// Returns a future resolving to the next item, or `None` after close and drain.
+    pub fn next(&self) -> StreamNext<'_, 'a, V> {
+        StreamNext { stream: self }
+    }
+
+    /// Returns the shared queue, e.g. to inspect its length.
+    #[must_use]
+    pub fn queue(&self) -> &SyncFairQueue<'a, V> {
+        &self.queue
+    }
+
+    fn wake_all(&self) {
+        let wakers = core::mem::take(&mut self.lock_state().wakers);
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, StreamState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<V: FairGroup> Default for FairQueueStream<'_, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Future returned by [`FairQueueStream::next`].
+pub struct StreamNext<'stream, 'value, V> {
+    stream: &'stream FairQueueStream<'value, V>,
+}
+
+impl<'value, V: FairGroup> Future for StreamNext<'_, 'value, V> {
+    type Output = Option<&'value V>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.stream.poll_next(cx)
+    }
+}
+
+/// Streams through a shared reference, so producers keep pushing while a task consumes.
+impl<'a, V: FairGroup> Stream for &FairQueueStream<'a, V> {
+    type Item = &'a V;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        FairQueueStream::poll_next(self.get_mut(), cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.len(), None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        task::Wake,
+        thread::{self, Thread},
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = core::pin::pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn push_wakes_pending_consumer() {
+        let events = [
+            Event {
+                timestamp: 1,
+                group: 0,
+            },
+            Event {
+                timestamp: 2,
+                group: 0,
+            },
+            Event {
+                timestamp: 3,
+                group: 1,
+            },
+        ];
+        let stream = FairQueueStream::new();
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(stream.poll_next(&mut cx), Poll::Pending);
+        assert_eq!(stream.poll_next(&mut cx), Poll::Pending);
+        stream.push(&events[0]);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+
+        stream.push(&events[1]);
+        stream.push(&events[2]);
+        assert_eq!(stream.poll_next(&mut cx), Poll::Ready(Some(&events[0])));
+        assert_eq!(stream.poll_next(&mut cx), Poll::Ready(Some(&events[2])));
+
+        stream.close();
+        assert!(stream.is_closed());
+        assert_eq!(stream.poll_next(&mut cx), Poll::Ready(Some(&events[1])));
+        assert_eq!(stream.poll_next(&mut cx), Poll::Ready(None));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn awaits_items_pushed_from_another_thread() {
+        let events: Vec<Event> = (0..32)
+            .map(|timestamp| Event {
+                timestamp,
+                group: timestamp as usize % 3,
+            })
+            .collect();
+        let stream = FairQueueStream::new();
+
+        let received = thread::scope(|scope| {
+            scope.spawn(|| {
+                for batch in events.chunks(4) {
+                    for event in batch {
+                        stream.push(event);
+                    }
+                    thread::yield_now();
+                }
+                stream.close();
+            });
+
+            block_on(async {
+                let mut received = Vec::new();
+                while let Some(event) = stream.next().await {
+                    received.push(event.timestamp);
+                }
+                received
+            })
+        });
+
+        assert_eq!(received.len(), events.len());
+        for group in 0..3 {
+            let timestamps: Vec<u32> = received
+                .iter()
+                .copied()
+                .filter(|&timestamp| timestamp as usize % 3 == group)
+                .collect();
+            assert!(timestamps.is_sorted());
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_interleaves_pushes_and_awaits() {
+        let events: Vec<Event> = (0..12)
+            .map(|timestamp| Event {
+                timestamp,
+                group: timestamp as usize % 3,
+            })
+            .collect();
+        let stream = FairQueueStream::new();
+
+        let producer = async {
+            for batch in events.chunks(3) {
+                for event in batch {
+                    stream.push(event);
+                }
+                tokio::task::yield_now().await;
+            }
+            stream.close();
+        };
+        let consumer = async {
+            let mut items = &stream;
+            let mut received = Vec::new();
+            while let Some(event) =
+                core::future::poll_fn(|cx| Pin::new(&mut items).poll_next(cx)).await
+            {
+                received.push(event.timestamp);
+            }
+            received
+        };
+        let ((), received) = tokio::join!(producer, consumer);
+
+        // Every batch holds one item per group, so fair order is arrival order.
+        assert!(received.iter().copied().eq(0..12));
+        assert_eq!((&stream).size_hint(), (0, None));
+    }
+}
@@ -0,0 +1,236 @@
+use std::sync::{Mutex, MutexGuard};
+
+use crate::{FairGroup, FairQueue};
+
+/// Fair queue behind an internal `Mutex`, shareable between producer and consumer threads
+/// (requires the `sync` feature). Every method locks for the duration of one operation and
+/// hands out the stored `&'a V` references by copy, so no guard escapes.
+/// The queue is `Send` and `Sync` whenever `V: Sync`.
+///
+/// A poisoned lock is recovered rather than propagated: the only user code run under the
+/// lock is `is_same_group`, which is called before the queue is modified.
+///
+/// ```
+/// use fairqueue::{FairGroup, SyncFairQueue};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Event {
+///     user_id: u32,
+///     value: u32,
+/// }
+///
+/// impl FairGroup for Event {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.user_id == other.user_id
+///     }
+/// }
+///
+/// let events = [
+///     Event { user_id: 1, value: 1 },
+///     Event { user_id: 2, value: 2 },
+/// ];
+///
+/// let queue = SyncFairQueue::new();
+/// std::thread::scope(|scope| {
+///     for event in &events {
+///         let queue = &queue;
+///         scope.spawn(move || queue.insert(event));
+///     }
+/// });
+///
+/// assert_eq!(queue.len(), 2);
+/// assert!(queue.pop().is_some());
+/// assert!(queue.pop().is_some());
+/// assert!(queue.is_empty());
+/// ```
+pub struct SyncFairQueue<'a, V> {
+    inner: Mutex<FairQueue<'a, V>>,
+}
+
+impl<'a, V: FairGroup> SyncFairQueue<'a, V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_queue(FairQueue::new())
+    }
+
+    /// Wraps an existing queue, keeping its items and rotation.
+    #[must_use]
+    pub fn from_queue(queue: FairQueue<'a, V>) -> Self {
+        Self {
+            inner: Mutex::new(queue),
+        }
+    }
+
+    /// Inserts a new item under the lock.
+    pub fn insert(&self, value: &'a V) {
+        self.lock().insert(value);
+    }
+
+    /// Pops the next item under the lock.
+    pub fn pop(&self) -> Option<&'a V> {
+        self.lock().pop()
+    }
+
+    /// Peeks at the next item under the lock. Another thread may pop it before the caller does.
+    #[must_use]
+    pub fn peek(&self) -> Option<&'a V> {
+        self.lock().peek()
+    }
+
+    /// Returns the number of enqueued items at the time of the call.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns true when the queue held no items at the time of the call.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Unwraps the inner queue.
+    #[must_use]
+    pub fn into_inner(self) -> FairQueue<'a, V> {
+        self.inner
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FairQueue<'a, V>> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<V: FairGroup> Default for SyncFairQueue<'_, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, vec::Vec};
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    const PRODUCERS: usize = 4;
+    const PER_PRODUCER: u32 = 64;
+
+    fn events() -> Vec<Vec<Event>> {
+        (0..PRODUCERS)
+            .map(|group| {
+                (0..PER_PRODUCER)
+                    .map(|timestamp| Event { timestamp, group })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn is_send_and_sync() {
+        assert_send_sync::<SyncFairQueue<'static, Event>>();
+    }
+
+    #[test]
+    fn concurrent_producers_and_consumer() {
+        let events = events();
+        let queue = SyncFairQueue::new();
+        let total = PRODUCERS * PER_PRODUCER as usize;
+
+        let received = thread::scope(|scope| {
+            for batch in &events {
+                let queue = &queue;
+                scope.spawn(move || {
+                    for event in batch {
+                        queue.insert(event);
+                    }
+                });
+            }
+
+            let consumer = scope.spawn(|| {
+                let mut received = Vec::with_capacity(total);
+                while received.len() < total {
+                    match queue.pop() {
+                        Some(event) => received.push(event),
+                        None => thread::yield_now(),
+                    }
+                }
+                received
+            });
+            consumer.join().unwrap()
+        });
+
+        assert_eq!(received.len(), total);
+        assert!(queue.is_empty());
+        for group in 0..PRODUCERS {
+            let timestamps: Vec<u32> = received
+                .iter()
+                .filter(|event| event.group == group)
+                .map(|event| event.timestamp)
+                .collect();
+            assert_eq!(timestamps, (0..PER_PRODUCER).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn spacing_holds_after_concurrent_inserts() {
+        let events = events();
+        let queue = SyncFairQueue::new();
+
+        thread::scope(|scope| {
+            for batch in &events {
+                let queue = &queue;
+                scope.spawn(move || {
+                    for event in batch {
+                        queue.insert(event);
+                    }
+                });
+            }
+        });
+
+        let drained = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let mut drained = Vec::new();
+                    while let Some(event) = queue.pop() {
+                        drained.push(event);
+                    }
+                    drained
+                })
+                .join()
+                .unwrap()
+        });
+
+        let mut remaining = [PER_PRODUCER as usize; PRODUCERS];
+        let mut last_group: Option<usize> = None;
+        for event in drained {
+            let gid = event.group;
+            let other_pending = remaining
+                .iter()
+                .enumerate()
+                .any(|(idx, &count)| idx != gid && count > 0);
+            if other_pending && let Some(prev) = last_group {
+                assert_ne!(prev, gid);
+            }
+            remaining[gid] -= 1;
+            last_group = Some(gid);
+        }
+        assert_eq!(remaining, [0; PRODUCERS]);
+    }
+}
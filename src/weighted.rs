@@ -0,0 +1,381 @@
+use alloc::{collections::VecDeque, vec::Vec};
+use core::ptr;
+
+use crate::{
+    FairGroup,
+    rotation::{self, Rotation, Slots},
+};
+
+/// Group of a weighted queue with its quota and the unspent part of the current burst.
+struct WeightedGroup<'a, V> {
+    weight: u32,
+    deficit: u32,
+    items: VecDeque<&'a V>,
+}
+
+/// Each item taken spends one unit of the group's burst quota.
+impl<'a, V> Slots for Vec<WeightedGroup<'a, V>> {
+    type Item = &'a V;
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn take(&mut self, slot: usize) -> Option<&'a V> {
+        let group = &mut self[slot];
+        let item = group.items.pop_front()?;
+        group.deficit = group.deficit.saturating_sub(1);
+        Some(item)
+    }
+
+    fn is_drained(&self, slot: usize) -> bool {
+        self[slot].items.is_empty()
+    }
+
+    fn remove(&mut self, slot: usize) {
+        self.swap_remove(slot);
+    }
+}
+
+/// Fair queue that serves groups in proportion to per-group weights (deficit round robin).
+///
+/// Each group takes the weight given with its first item. When the rotation reaches a group,
+/// it may dispatch up to `weight` items in a row before the pointer moves on, so a backlogged
+/// group of weight 3 receives three pops for every pop of a backlogged group of weight 1.
+/// Groups are visited in arrival order, so groups with equal weights alternate exactly like in
+/// [`FairQueue`](crate::FairQueue). A group of weight 0 is skipped while any group with a
+/// positive weight holds items; once only zero-weight groups remain, they are served one item
+/// per turn. Unlike `FairQueue`, consecutive pops from one group are expected when weights
+/// exceed 1.
+///
+/// ```
+/// use fairqueue::{FairGroup, WeightedFairQueue};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Job {
+///     tenant: &'static str,
+///     id: u32,
+/// }
+///
+/// impl FairGroup for Job {
+///     fn is_same_group(&self, other: &Self) -> bool {
+///         self.tenant == other.tenant
+///     }
+/// }
+///
+/// let jobs = [
+///     Job { tenant: "premium", id: 1 },
+///     Job { tenant: "premium", id: 2 },
+///     Job { tenant: "premium", id: 3 },
+///     Job { tenant: "premium", id: 4 },
+///     Job { tenant: "free", id: 10 },
+///     Job { tenant: "free", id: 11 },
+/// ];
+///
+/// let mut queue = WeightedFairQueue::new();
+/// for job in &jobs[..4] {
+///     queue.insert_weighted(job, 3);
+/// }
+/// for job in &jobs[4..] {
+///     queue.insert_weighted(job, 1);
+/// }
+///
+/// let order: Vec<u32> = std::iter::from_fn(|| queue.pop()).map(|job| job.id).collect();
+/// assert_eq!(order, vec![1, 2, 3, 10, 4, 11]);
+/// ```
+pub struct WeightedFairQueue<'a, V: FairGroup> {
+    groups: Vec<WeightedGroup<'a, V>>,
+    rotation: Rotation,
+    pointer: usize,
+    len: usize,
+}
+
+impl<'a, V: FairGroup> WeightedFairQueue<'a, V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            groups: Vec::new(),
+            rotation: Rotation::new(),
+            pointer: 0,
+            len: 0,
+        }
+    }
+
+    /// Inserts an item with weight 1, the plain round-robin share.
+    pub fn insert(&mut self, value: &'a V) {
+        self.insert_weighted(value, 1);
+    }
+
+    /// Inserts an item into its group. `weight` only applies when the item opens a new group;
+    /// an existing group keeps the weight it was created with.
+    pub fn insert_weighted(&mut self, value: &'a V, weight: u32) {
+        if let Some(group) = self.groups.iter_mut().find(|group| {
+            group
+                .items
+                .front()
+                .is_some_and(|v| ptr::eq(*v, value) || (*v).is_same_group(value))
+        }) {
+            group.items.push_back(value);
+        } else {
+            let mut items = VecDeque::new();
+            items.push_back(value);
+            self.groups.push(WeightedGroup {
+                weight,
+                deficit: 0,
+                items,
+            });
+            self.rotation.push();
+        }
+        self.len += 1;
+    }
+
+    /// Retrieves the next item, letting the current group continue its burst while it has
+    /// quota left.
+    pub fn pop(&mut self) -> Option<&'a V> {
+        if self.len == 0 || self.groups.is_empty() {
+            return None;
+        }
+
+        let has_weighted = self.groups.iter().any(|group| group.weight > 0);
+        loop {
+            if self.pointer >= self.groups.len() {
+                self.pointer = self.rotation.first();
+            }
+
+            let group = &mut self.groups[self.pointer];
+            if group.deficit == 0 {
+                if group.weight == 0 && has_weighted {
+                    self.pointer = self.rotation.next(self.pointer);
+                    continue;
+                }
+                group.deficit = group.weight.max(1);
+            }
+
+            // The group keeps the pointer while its burst has quota left after this item.
+            let stay = group.deficit > 1;
+            let popped = rotation::pop_ordered(
+                &mut self.groups,
+                &mut self.rotation,
+                &mut self.pointer,
+                stay,
+            )?;
+            self.len -= 1;
+            return Some(popped.item);
+        }
+    }
+
+    /// Returns the weight of the group `sample` belongs to, or `None` when no group matches.
+    #[must_use]
+    pub fn weight_of_group(&self, sample: &V) -> Option<u32> {
+        Some(self.groups[self.slot_of(sample)?].weight)
+    }
+
+    /// Changes the weight of the group `sample` belongs to, effective immediately; does
+    /// nothing when no group matches. A group in the middle of its burst keeps at most
+    /// `weight` of the items left in that burst: raising the weight takes effect from the
+    /// group's next turn, while lowering it can end the current burst early, in which case
+    /// the rotation moves on to the next group.
+    pub fn set_group_weight(&mut self, sample: &V, weight: u32) {
+        let Some(slot) = self.slot_of(sample) else {
+            return;
+        };
+        let group = &mut self.groups[slot];
+        group.weight = weight;
+        if group.deficit > weight {
+            group.deficit = weight;
+            if weight == 0 && slot == self.pointer {
+                self.pointer = self.rotation.next(self.pointer);
+            }
+        }
+    }
+
+    /// Returns the number of enqueued items.
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when the queue holds no items.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct groups tracked by the queue.
+    #[inline(always)]
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Clears all items and resets the round-robin pointer.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.rotation.clear();
+        self.pointer = 0;
+        self.len = 0;
+    }
+
+    /// Finds the slot of the group `sample` belongs to.
+    fn slot_of(&self, sample: &V) -> Option<usize> {
+        self.groups.iter().position(|group| {
+            group
+                .items
+                .front()
+                .is_some_and(|v| ptr::eq(*v, sample) || (*v).is_same_group(sample))
+        })
+    }
+}
+
+impl<V: FairGroup> Default for WeightedFairQueue<'_, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Event {
+        timestamp: u32,
+        group: usize,
+    }
+
+    impl FairGroup for Event {
+        fn is_same_group(&self, other: &Self) -> bool {
+            self.group == other.group
+        }
+    }
+
+    fn drain(queue: &mut WeightedFairQueue<'_, Event>) -> Vec<u32> {
+        let mut order = Vec::new();
+        while let Some(event) = queue.pop() {
+            order.push(event.timestamp);
+        }
+        order
+    }
+
+    #[test]
+    fn zero_weight_waits_for_weighted_groups() {
+        let events: Vec<Event> = [(0, 0), (1, 1), (2, 0), (3, 1), (4, 2)]
+            .iter()
+            .map(|&(timestamp, group)| Event { timestamp, group })
+            .collect();
+
+        let mut queue = WeightedFairQueue::new();
+        queue.insert_weighted(&events[0], 0);
+        queue.insert_weighted(&events[1], 2);
+        queue.insert_weighted(&events[2], 5);
+        queue.insert(&events[3]);
+        queue.insert_weighted(&events[4], 0);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.group_count(), 3);
+
+        // Group 1 keeps weight 2; groups 0 and 2 only run once it is gone.
+        assert_eq!(drain(&mut queue), vec![1, 3, 4, 0, 2]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.group_count(), 0);
+    }
+
+    #[test]
+    fn equal_weights_alternate() {
+        let events: Vec<Event> = (0..6)
+            .map(|timestamp| Event {
+                timestamp,
+                group: timestamp as usize % 2,
+            })
+            .collect();
+
+        let mut queue = WeightedFairQueue::new();
+        for event in &events {
+            queue.insert(event);
+        }
+        assert_eq!(drain(&mut queue), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn reweighting_mid_drain_changes_the_ratio() {
+        let events: Vec<Event> = (0..40)
+            .map(|timestamp| Event {
+                timestamp,
+                group: timestamp as usize % 2,
+            })
+            .collect();
+
+        let mut queue = WeightedFairQueue::new();
+        for event in &events {
+            queue.insert(event);
+        }
+        assert_eq!(queue.weight_of_group(&events[0]), Some(1));
+        assert_eq!(
+            queue.weight_of_group(&Event {
+                timestamp: 0,
+                group: 7
+            }),
+            None
+        );
+
+        let mut counts = [0usize; 2];
+        for _ in 0..4 {
+            counts[queue.pop().unwrap().group] += 1;
+        }
+        assert_eq!(counts, [2, 2]);
+
+        queue.set_group_weight(&events[0], 3);
+        assert_eq!(queue.weight_of_group(&events[0]), Some(3));
+        let mut counts = [0usize; 2];
+        for _ in 0..16 {
+            counts[queue.pop().unwrap().group] += 1;
+        }
+        assert_eq!(counts, [12, 4]);
+
+        // Mid-burst: two items are left in group 0's burst, and weight 1 caps them at one.
+        assert_eq!(queue.pop().map(|event| event.group), Some(0));
+        queue.set_group_weight(&events[0], 1);
+        let order: Vec<usize> = (0..4).map(|_| queue.pop().unwrap().group).collect();
+        assert_eq!(order, vec![0, 1, 0, 1]);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_counts_track_weights(
+            weights in proptest::collection::vec(1u32..5, 1..5),
+            rounds in 1usize..6,
+        ) {
+            let mut events = Vec::new();
+            for (group, &weight) in weights.iter().enumerate() {
+                for _ in 0..weight as usize * rounds {
+                    events.push(Event {
+                        timestamp: events.len() as u32,
+                        group,
+                    });
+                }
+            }
+
+            let mut queue = WeightedFairQueue::new();
+            for event in &events {
+                queue.insert_weighted(event, weights[event.group]);
+            }
+
+            let cycle: usize = weights.iter().map(|&weight| weight as usize).sum();
+            let mut counts = vec![0usize; weights.len()];
+            let mut popped = 0;
+            while let Some(event) = queue.pop() {
+                counts[event.group] += 1;
+                popped += 1;
+                if popped % cycle == 0 {
+                    let completed = popped / cycle;
+                    for (count, &weight) in counts.iter().zip(&weights) {
+                        prop_assert_eq!(*count, weight as usize * completed);
+                    }
+                }
+            }
+            prop_assert_eq!(popped, events.len());
+        }
+    }
+}
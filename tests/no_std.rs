@@ -0,0 +1,134 @@
+//! Exercises the allocation-backed structures from a `#![no_std]` crate with only `alloc`.
+//! Any `std` path leaking into those APIs fails to compile here under the default features.
+//! Under the `heapless` feature the bounded structures are checked at their capacity limits
+//! too, also with `--no-default-features`, where the crate itself links no `alloc`.
+
+#![no_std]
+#![cfg(any(feature = "alloc", feature = "heapless"))]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use fairqueue::FairGroup;
+
+#[derive(Debug, PartialEq)]
+struct Event {
+    tenant: u8,
+    value: u32,
+}
+
+impl FairGroup for Event {
+    fn is_same_group(&self, other: &Self) -> bool {
+        self.tenant == other.tenant
+    }
+}
+
+fn events() -> Vec<Event> {
+    (0..6)
+        .map(|value| Event {
+            tenant: (value % 3) as u8,
+            value,
+        })
+        .collect()
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn queue_round_robin_without_std() {
+    let events = events();
+    let mut queue = fairqueue::FairQueue::new();
+    for event in &events {
+        queue.insert(event);
+    }
+    assert_eq!(queue.len(), 6);
+    assert_eq!(queue.group_count(), 3);
+    assert_eq!(queue.peek(), Some(&events[0]));
+
+    let heads: Vec<u8> = queue.group_heads().map(|event| event.tenant).collect();
+    assert_eq!(heads, [0, 1, 2]);
+
+    let order: Vec<u32> = core::iter::from_fn(|| queue.pop())
+        .map(|event| event.value)
+        .collect();
+    assert_eq!(order, [0, 1, 2, 3, 4, 5]);
+    assert!(queue.is_empty());
+
+    queue.insert(&events[0]);
+    queue.clear();
+    assert_eq!(queue.pop(), None);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn stack_round_robin_without_std() {
+    let events = events();
+    let mut stack = fairqueue::FairStack::new();
+    for event in &events {
+        stack.push(event);
+    }
+    assert_eq!(stack.len(), 6);
+    assert_eq!(stack.group_count(), 3);
+    assert!(stack.peek().is_some());
+    assert_eq!(stack.group_heads().count(), 3);
+
+    let mut seen = 0;
+    while let Some(event) = stack.pop() {
+        assert!(event.value < 6);
+        seen += 1;
+    }
+    assert_eq!(seen, 6);
+
+    stack.push(&events[1]);
+    stack.clear();
+    assert!(stack.is_empty());
+}
+
+#[cfg(feature = "heapless")]
+mod heapless_backed {
+    use super::{Event, events};
+    use fairqueue::heapless::{FairQueue, FairStack};
+
+    #[test]
+    fn queue_rejects_past_group_and_item_capacity() {
+        let events = events();
+        // Tenants 0, 1, 2, 0, 1, 2: two groups of at most one item each.
+        let mut queue: FairQueue<Event, 2, 1> = FairQueue::new();
+        assert!(queue.insert(&events[0]).is_ok());
+        assert!(queue.insert(&events[1]).is_ok());
+        assert_eq!(
+            queue.insert(&events[2]).unwrap_err().into_inner(),
+            &events[2]
+        );
+        assert_eq!(
+            queue.insert(&events[3]).unwrap_err().into_inner(),
+            &events[3]
+        );
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.group_count(), 2);
+
+        assert_eq!(queue.pop(), Some(&events[0]));
+        assert!(queue.insert(&events[2]).is_ok());
+        assert_eq!(queue.pop(), Some(&events[1]));
+        assert_eq!(queue.pop(), Some(&events[2]));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn stack_rejects_past_group_and_item_capacity() {
+        let events = events();
+        let mut stack: FairStack<Event, 3, 2> = FairStack::new();
+        for event in &events {
+            assert!(stack.push(event).is_ok());
+        }
+        assert_eq!(stack.len(), 6);
+        assert_eq!(stack.group_count(), 3);
+        assert_eq!(stack.push(&events[0]).unwrap_err().into_inner(), &events[0]);
+        assert_eq!(stack.len(), 6);
+
+        assert_eq!(stack.pop(), Some(&events[3]));
+        assert!(stack.push(&events[0]).is_ok());
+        assert_eq!(stack.peek(), Some(&events[4]));
+        stack.clear();
+        assert!(stack.pop().is_none());
+    }
+}